@@ -0,0 +1,200 @@
+use std::{fs, path::Path};
+
+use anyhow::{bail, Context};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use rusqlite::{types::ValueRef, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{config::Config, data_dir, db, update::confirm};
+
+const TABLES: [&str; 2] = ["freecompany", "submarine"];
+
+/// What `export` writes: every row of the plugin's FC and submarine tables, column by
+/// column, so a newer or older plugin's database still takes whatever columns it shares
+#[derive(Serialize, Deserialize)]
+struct Export {
+    exported: DateTime<Utc>,
+    databases: Vec<Database>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Database {
+    /// The source's label, to find where it goes back; none for the auto-detected one
+    label: Option<String>,
+    user_version: i64,
+    freecompany: Vec<Map<String, Value>>,
+    submarine: Vec<Map<String, Value>>,
+}
+
+impl Database {
+    fn rows(&self, table: &str) -> &[Map<String, Value>] {
+        if table == "freecompany" { &self.freecompany } else { &self.submarine }
+    }
+}
+
+/// Writes every local database's FCs and subs to `output`, or stdout
+pub fn export(config: &Config, output: Option<&Path>) -> anyhow::Result<()> {
+    let mut databases = Vec::new();
+    for (label, path) in db::local_dbs(&config.sources) {
+        let db = db::open_db_at(&path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let schema = db::Schema::detect(&db)?;
+        databases.push(Database {
+            label,
+            user_version: schema.user_version,
+            freecompany: read_table(&db, "freecompany")?,
+            submarine: read_table(&db, "submarine")?,
+        });
+    }
+    if databases.is_empty() {
+        bail!("There's no local database to export");
+    }
+    let json = serde_json::to_string_pretty(&Export { exported: Utc::now(), databases })?;
+    match output {
+        Some(output) => {
+            fs::write(output, json).with_context(|| format!("Couldn't write {}", output.display()))?;
+            eprintln!("Exported to {}", output.display());
+        }
+        None => println!("{json}"),
+    }
+    Ok(())
+}
+
+fn read_table(db: &Connection, table: &str) -> anyhow::Result<Vec<Map<String, Value>>> {
+    let mut stmt = db.prepare(&format!("SELECT * FROM {table}"))?;
+    let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+    let mut rows = stmt.query([])?;
+    let mut table = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut values = Map::new();
+        for (i, column) in columns.iter().enumerate() {
+            values.insert(column.clone(), to_json(row.get_ref(i)?));
+        }
+        table.push(values);
+    }
+    Ok(table)
+}
+
+fn to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(integer) => integer.into(),
+        ValueRef::Real(real) => real.into(),
+        ValueRef::Text(text) => String::from_utf8_lossy(text).into(),
+        // JSON has no bytes
+        ValueRef::Blob(blob) => serde_json::json!({ "blob": base64::engine::general_purpose::STANDARD.encode(blob) }),
+    }
+}
+
+fn from_json(value: &Value) -> anyhow::Result<rusqlite::types::Value> {
+    use rusqlite::types::Value as Sql;
+    Ok(match value {
+        Value::Null => Sql::Null,
+        Value::Bool(bool) => Sql::Integer((*bool).into()),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => Sql::Integer(integer),
+            None => Sql::Real(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(text) => Sql::Text(text.clone()),
+        Value::Object(object) if object.len() == 1 && object.contains_key("blob") => {
+            let blob = object["blob"].as_str().context("A blob that isn't base64")?;
+            Sql::Blob(base64::engine::general_purpose::STANDARD.decode(blob)?)
+        }
+        Value::Array(_) | Value::Object(_) => bail!("Can't store {value} in a column"),
+    })
+}
+
+/// Replaces the FCs and subs in each local database with the ones exported to `file`,
+/// matched up by source label. What's there now is copied to the data folder first.
+pub fn import(config: &Config, file: &Path, yes: bool) -> anyhow::Result<()> {
+    let contents = fs::read_to_string(file).with_context(|| format!("Couldn't read {}", file.display()))?;
+    let export: Export =
+        serde_json::from_str(&contents).with_context(|| format!("{} isn't a sub-returns export", file.display()))?;
+    let local = db::local_dbs(&config.sources);
+
+    let mut planned = Vec::new();
+    for database in &export.databases {
+        let target = match local.iter().find(|(label, _)| label.is_some() && *label == database.label) {
+            Some((_, path)) => path,
+            // Moving between machines with different labels, or none at all
+            None if export.databases.len() == 1 && local.len() == 1 => &local[0].1,
+            None => bail!(
+                "No source labelled '{}' to import into, add it to [[sources]] or export only one",
+                database.label.as_deref().unwrap_or("(auto-detected)")
+            ),
+        };
+        if planned.iter().any(|(path, _)| path == target) {
+            bail!("{} would be imported into twice", target.display());
+        }
+        planned.push((target.clone(), database));
+    }
+    if planned.is_empty() {
+        bail!("{} has no databases in it", file.display());
+    }
+
+    println!("From the export taken {}:", export.exported.with_timezone(&chrono::Local).format("%e %B %Y at %I:%M %p"));
+    for (path, database) in &planned {
+        println!(
+            "  {} FC(s) and {} sub(s) will replace what's in {}",
+            database.freecompany.len(),
+            database.submarine.len(),
+            path.display()
+        );
+    }
+    println!("Close the game first, SubmarineTracker writes over the database with what it has loaded");
+    if !yes && !confirm("Import?")? {
+        bail!("Cancelled, nothing was imported");
+    }
+
+    let backups = data_dir().join("before-import");
+    fs::create_dir_all(&backups)?;
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+    for (i, (path, database)) in planned.iter().enumerate() {
+        let mut db = db::open_db_at(path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        db::Schema::detect(&db)?;
+        let backup = backups.join(format!("{stamp}-{i}.db"));
+        db.execute("VACUUM INTO ?1", [backup.to_string_lossy()]).with_context(|| {
+            format!("Couldn't copy {} to {}, nothing was imported", path.display(), backup.display())
+        })?;
+
+        let tx = db.transaction()?;
+        for table in TABLES {
+            let columns = db::table_columns(&tx, table)?;
+            tx.execute(&format!("DELETE FROM {table}"), [])?;
+            let mut dropped: Vec<&str> = Vec::new();
+            for row in database.rows(table) {
+                let mut names = Vec::new();
+                let mut values = Vec::new();
+                for (name, value) in row {
+                    match columns.iter().find(|column| *column == name) {
+                        Some(column) => {
+                            names.push(column);
+                            values.push(value);
+                        }
+                        None if !dropped.contains(&name.as_str()) => dropped.push(name),
+                        None => {}
+                    }
+                }
+                if names.is_empty() {
+                    continue;
+                }
+                let values = values.into_iter().map(from_json).collect::<anyhow::Result<Vec<_>>>()?;
+                let quoted: Vec<String> =
+                    names.iter().map(|name| format!("\"{}\"", name.replace('"', "\"\""))).collect();
+                let placeholders = vec!["?"; names.len()].join(", ");
+                tx.execute(
+                    &format!("INSERT INTO {table} ({}) VALUES ({placeholders})", quoted.join(", ")),
+                    rusqlite::params_from_iter(values),
+                )?;
+            }
+            if !dropped.is_empty() {
+                eprintln!("{} has no {table} column(s) {}, left those out", path.display(), dropped.join(", "));
+            }
+        }
+        tx.commit()?;
+        db.close().map_err(|(_, e)| e)?;
+        println!("Imported into {} (what was there is in {})", path.display(), backup.display());
+    }
+    Ok(())
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Client, StatusCode};
+use tracing::debug;
+
+use crate::{
+    calendar::{self, Calendar, EVENT_LENGTH},
+    config::CaldavConfig,
+    db::SubInfo,
+    i18n::Messages,
+};
+
+/// A calendar on a CalDAV server, with each sub's event at `<uid>.ics` in it
+pub struct CalDav<'a> {
+    config: &'a CaldavConfig,
+    client: Client,
+}
+
+impl<'a> CalDav<'a> {
+    pub fn new(config: &'a CaldavConfig) -> anyhow::Result<Self> {
+        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        Ok(CalDav { config, client })
+    }
+
+    fn event_url(&self, key: &str) -> String {
+        format!("{}/{}.ics", self.config.url.trim_end_matches('/'), uid(key))
+    }
+}
+
+impl Calendar for CalDav<'_> {
+    fn name(&self) -> &str {
+        &self.config.url
+    }
+
+    fn put(&mut self, sub: &SubInfo, now: DateTime<Utc>, text: &Messages) -> anyhow::Result<()> {
+        let url = self.event_url(&sub.key());
+        debug!(url, sub = sub.name, "putting event");
+        let response = self
+            .client
+            .put(&url)
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .header("Content-Type", "text/calendar; charset=utf-8")
+            .body(event(sub, now, text))
+            .send()
+            .with_context(|| format!("Couldn't reach the calendar at {url}"))?;
+        if !response.status().is_success() {
+            bail!("The calendar wouldn't take {}'s event: {}", sub.name, response.status());
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &str) -> anyhow::Result<()> {
+        let url = self.event_url(key);
+        debug!(url, "deleting event");
+        let response = self
+            .client
+            .delete(&url)
+            .basic_auth(&self.config.username, Some(&self.config.password))
+            .send()
+            .with_context(|| format!("Couldn't reach the calendar at {url}"))?;
+        // Already gone is as good as deleted
+        if !response.status().is_success() && response.status() != StatusCode::NOT_FOUND {
+            bail!("The calendar wouldn't delete {url}: {}", response.status());
+        }
+        Ok(())
+    }
+}
+
+/// Source labels can be anything, so only the safe characters of the key are kept
+fn uid(key: &str) -> String {
+    let key: String = key.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '-' }).collect();
+    format!("sub-returns-{key}")
+}
+
+fn event(sub: &SubInfo, now: DateTime<Utc>, text: &Messages) -> String {
+    let stamp = |time: DateTime<Utc>| time.format("%Y%m%dT%H%M%SZ").to_string();
+    let lines = [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//sub-returns//EN".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid(&sub.key())),
+        format!("DTSTAMP:{}", stamp(now)),
+        format!("DTSTART:{}", stamp(sub.return_time)),
+        format!("DTEND:{}", stamp(sub.return_time + EVENT_LENGTH)),
+        format!("SUMMARY:{}", escape(&calendar::summary(sub, text))),
+        format!("DESCRIPTION:{}", escape(&calendar::description(sub, text))),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ];
+    lines.iter().map(|line| fold(line) + "\r\n").collect()
+}
+
+/// RFC 5545 text: backslashes, commas, semicolons and newlines are escaped
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Lines over 75 bytes go on continuation lines starting with a space, split between
+/// characters rather than in the middle of one
+fn fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded += "\r\n ";
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
@@ -0,0 +1,115 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+    data_dir,
+    db::SubInfo,
+    i18n::{fill, Messages},
+    routes,
+};
+
+/// How long each return's event is, just so it shows up as more than a line
+pub const EVENT_LENGTH: TimeDelta = TimeDelta::minutes(15);
+
+/// Somewhere `sync` can keep an event per sub, found again by the sub's `SubInfo::key`
+pub trait Calendar {
+    /// Which calendar it is, so pointing the config at a different one starts afresh
+    fn name(&self) -> &str;
+    /// Adds the sub's event, or moves it if it's already there, with its text in the
+    /// language of `text`
+    fn put(&mut self, sub: &SubInfo, now: DateTime<Utc>, text: &Messages) -> anyhow::Result<()>;
+    fn delete(&mut self, key: &str) -> anyhow::Result<()>;
+}
+
+/// What's been put on a calendar, so only what changed is sent again
+#[derive(Serialize, Deserialize, Default)]
+struct Synced {
+    #[serde(alias = "url")]
+    calendar: String,
+    /// Return time, in Unix seconds, of the event for each sub's `SubInfo::key`
+    events: HashMap<String, i64>,
+}
+
+/// An event for each sub's return, moved when the return time changes and removed
+/// once the sub isn't among `subs` any more. Subs already back only get one if they
+/// had one before, so an old database doesn't fill up the past. What's on the
+/// calendar is kept track of in `record` in the data dir.
+pub fn sync(calendar: &mut dyn Calendar, record: &str, subs: &[SubInfo], text: &Messages) -> anyhow::Result<()> {
+    let path = data_dir().join(record);
+    let mut synced = load(&path).filter(|synced| synced.calendar == calendar.name()).unwrap_or_else(|| Synced {
+        calendar: calendar.name().to_string(),
+        ..Synced::default()
+    });
+    let now = Utc::now();
+
+    let (mut added, mut moved, mut removed) = (0, 0, 0);
+    let mut result = Ok(());
+    for sub in subs {
+        let key = sub.key();
+        let existing = synced.events.get(&key).copied();
+        if existing == Some(sub.return_time.timestamp()) || (existing.is_none() && sub.return_time <= now) {
+            continue;
+        }
+        if let Err(e) = calendar.put(sub, now, text) {
+            result = Err(e);
+            break;
+        }
+        synced.events.insert(key, sub.return_time.timestamp());
+        match existing {
+            Some(_) => moved += 1,
+            None => added += 1,
+        }
+    }
+    let gone: Vec<String> = synced
+        .events
+        .keys()
+        .filter(|key| !subs.iter().any(|sub| sub.key() == **key))
+        .cloned()
+        .collect();
+    for key in gone {
+        if result.is_err() {
+            break;
+        }
+        result = calendar.delete(&key);
+        if result.is_ok() {
+            synced.events.remove(&key);
+            removed += 1;
+        }
+    }
+    // Saved even after a failure, so whatever did make it isn't sent twice
+    save(&path, &synced)?;
+    info!(added, moved, removed, "synced calendar");
+    println!("{added} added, {moved} moved, {removed} removed");
+    result
+}
+
+pub fn summary(sub: &SubInfo, text: &Messages) -> String {
+    fill(text.event_summary, &[("name", &sub.name)])
+}
+
+/// Who the sub belongs to and where it's been
+pub fn description(sub: &SubInfo, text: &Messages) -> String {
+    let mut description = sub.owner();
+    if !sub.route.is_empty() {
+        description += &format!("\n{}", routes::describe(&sub.route));
+    }
+    if sub.estimated {
+        description += &format!("\n{}", text.event_estimated);
+    }
+    description
+}
+
+fn load(path: &Path) -> Option<Synced> {
+    serde_json::from_str(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn save(path: &Path, synced: &Synced) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(synced)?)?;
+    Ok(())
+}
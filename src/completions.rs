@@ -0,0 +1,35 @@
+use std::io::Write;
+
+use clap::{builder::PossibleValuesParser, Command};
+use clap_complete::Shell;
+
+use crate::db::SubInfo;
+
+/// `completions`: the completion script for `shell`. The characters, FCs and subs in
+/// `subs` go in as the values `--character`, `--fc` and `--sub` complete to, so it's
+/// worth making again after adding any.
+pub fn write(out: &mut dyn Write, mut command: Command, shell: Shell, subs: &[SubInfo]) -> anyhow::Result<()> {
+    let values = |value: fn(&SubInfo) -> &str| {
+        let mut values: Vec<String> = subs.iter().map(|sub| value(sub).to_string()).collect();
+        values.sort();
+        values.dedup();
+        values
+    };
+    for (arg, values) in [
+        ("characters", values(|sub| &sub.character_name)),
+        ("fcs", values(|sub| &sub.tag)),
+        ("subs", values(|sub| &sub.name)),
+    ] {
+        // Without a database there's nothing to offer, and an empty list would
+        // complete to nothing at all rather than anything
+        if !values.is_empty() {
+            command = command.mut_arg(arg, |arg| arg.value_parser(PossibleValuesParser::new(values)));
+        }
+    }
+    let name = command.get_name().to_string();
+    // clap_complete panics if it can't write, so it writes here and the error is ours
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut command, name, &mut script);
+    out.write_all(&script)?;
+    Ok(())
+}
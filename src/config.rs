@@ -0,0 +1,660 @@
+use std::{
+    fs,
+    io::Write,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Days, Local, NaiveTime, TimeZone, Utc, Weekday};
+use serde::Deserialize;
+
+use crate::{db::SubInfo, duration::ConfigDuration, i18n::Language};
+
+/// User settings from `config.toml` in the platform config dir. Every field is optional;
+/// a missing file behaves exactly like the compile-time defaults.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct Config {
+    pub bridge: BridgeConfig,
+    pub pushover: PushoverOptions,
+    /// Databases to read, for people with more than one game install. Empty means
+    /// whichever one is found in the usual places.
+    pub sources: Vec<SourceConfig>,
+    pub sync: SyncConfig,
+    /// Subs the daemon never notifies about
+    pub muted: Vec<MutedSub>,
+    /// Names to show subs by instead of the ones they have in game
+    pub aliases: Vec<SubAlias>,
+    /// Where each character's notifications go, anyone not listed gets both
+    pub characters: Vec<CharacterPrefs>,
+    /// Somewhere else for an FC's notifications to go, by its tag
+    pub free_companies: Vec<FcRoute>,
+    pub display: DisplayConfig,
+    pub theme: ThemeConfig,
+    pub loot: LootConfig,
+    pub notifications: NotificationConfig,
+    /// No notifications in this window, just a catch-up once it's over
+    pub quiet_hours: Option<QuietHours>,
+    /// When you're around to collect subs, for `plan`
+    pub play_windows: Vec<PlayWindow>,
+    /// Somewhere to tell the daemon is still alive, so its dying doesn't go unnoticed
+    pub heartbeat: Option<HeartbeatConfig>,
+    /// Hold back pushes that are due while you're at this computer
+    pub presence: Option<PresenceConfig>,
+    /// Also announce returns over XMPP, to someone or in a room
+    pub xmpp: Option<XmppConfig>,
+    /// Also announce returns in a Discord channel, and answer slash commands with a bot
+    pub discord: Option<DiscordConfig>,
+    /// Also ping phones paired with KDE Connect
+    pub kdeconnect: Option<KdeConnectConfig>,
+    /// A calendar for `sync caldav` to keep the return schedule on
+    pub caldav: Option<CaldavConfig>,
+    /// A Google calendar for `sync google`
+    pub google: Option<GoogleConfig>,
+    pub repairs: RepairConfig,
+}
+
+/// Handlebars templates for notification text. The variables are the same as
+/// `--template`'s, plus `count` and `others` for how many subs a push covers and
+/// `lead` for heads-ups.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct NotificationConfig {
+    /// Desktop notifications on this machine (on if left out)
+    pub desktop: Option<bool>,
+    /// Pushes through the bridge (on if left out)
+    pub push: Option<bool>,
+    pub push_title: Option<String>,
+    pub push_body: Option<String>,
+    pub desktop_title: Option<String>,
+    pub desktop_body: Option<String>,
+    /// Heads-ups before a sub returns, e.g. `["30m", "5m"]`
+    pub lead_times: Vec<ConfigDuration>,
+    pub lead_title: Option<String>,
+    pub lead_body: Option<String>,
+    /// Keep reminding this often while a returned sub hasn't been collected and sent
+    /// out again
+    pub nag_interval: Option<ConfigDuration>,
+    /// Give up after this many reminders (no limit if left out)
+    pub nag_max: Option<u32>,
+    pub nag_title: Option<String>,
+    pub nag_body: Option<String>,
+    /// Once a returned sub has waited this long to be collected, say so once more at
+    /// `[pushover] overdue_priority` and as an urgent desktop notification. Reminders
+    /// before then are at the usual priority.
+    pub overdue_after: Option<ConfigDuration>,
+    pub overdue_title: Option<String>,
+    pub overdue_body: Option<String>,
+    /// Send a rundown of the next 24 hours' returns every day at this time
+    pub digest_time: Option<LocalTime>,
+    /// Confirm when subs are sent out again, with when they'll be back
+    pub voyage_started: bool,
+    pub voyage_title: Option<String>,
+    pub voyage_body: Option<String>,
+    /// Instead of a notification per return, one when all of a character's subs are
+    /// back
+    pub all_home: bool,
+    pub all_home_title: Option<String>,
+    pub all_home_body: Option<String>,
+    /// Sound for the all-home notification, so it stands out from the rest
+    pub all_home_sound: Option<String>,
+    /// Warn once if even the latest return is this far in the past, since it means
+    /// SubmarineTracker has stopped updating
+    pub stale_after: Option<ConfigDuration>,
+    /// Desktop notification sound, a name from the platform's sounds (e.g.
+    /// `message-new-instant` on Linux, `Glass` on macOS, `Reminder` on Windows) or
+    /// `none` for silence
+    pub sound: Option<String>,
+    /// Returns this close together are sent as one notification (5m if left out,
+    /// `--group-window` overrides it)
+    pub group_window: Option<ConfigDuration>,
+    /// macOS: bundle id to send notifications through Notification Center as, e.g.
+    /// `com.apple.Terminal`. Left out they're shown with osascript, which always works
+    /// but has no buttons. Read at startup.
+    pub macos_app: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// strftime format for return times, `--time-format` overrides it
+    pub time_format: Option<String>,
+    /// 24-hour times, `--24h` turns it on. Left out, it follows the locale.
+    pub hour24: Option<bool>,
+    /// IANA timezone to show times in, instead of the system's (`--tz` overrides it)
+    pub timezone: Option<String>,
+    /// Order of the characters in the listing
+    pub group_order: GroupOrder,
+    /// Language for the listing and notifications, `--lang` overrides it. Left out, it
+    /// follows the locale.
+    pub language: Option<Language>,
+}
+
+/// Colours and glyphs for the listing
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// Colours for subs that are back, nearly back and the rest: a name like `green`,
+    /// `bright_red` or `bold cyan`, an SGR code like `1;35`, or `none`
+    pub returned: Option<String>,
+    pub soon: Option<String>,
+    pub later: Option<String>,
+    /// How close to its return a sub counts as nearly back (1h if left out)
+    pub soon_within: Option<ConfigDuration>,
+    /// Shown in front of subs that are back, e.g. `✓`
+    pub returned_marker: Option<String>,
+    /// Draw lines around the table's cells
+    pub box_drawing: bool,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct LootConfig {
+    /// World or data centre to price loot on without `--prices`
+    pub market: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GroupOrder {
+    /// Whoever has the next sub back comes first
+    #[default]
+    Soonest,
+    Alphabetical,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct MutedSub {
+    /// Sub name (case-insensitive) or id
+    pub sub: String,
+    /// Only mute it for this character, for when two characters use the same sub names
+    pub character: Option<String>,
+}
+
+impl MutedSub {
+    pub fn matches(&self, sub: &SubInfo) -> bool {
+        matches_sub(&self.sub, self.character.as_deref(), sub)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubAlias {
+    /// Sub name as it is in game (case-insensitive) or id
+    pub sub: String,
+    /// Only for this character's sub of that name
+    pub character: Option<String>,
+    /// What to call it instead
+    pub name: String,
+}
+
+/// `wanted` is a sub name (case-insensitive) or id, and `character` a full character
+/// name if it's only that character's
+fn matches_sub(wanted: &str, character: Option<&str>, sub: &SubInfo) -> bool {
+    let character_matches = character.is_none_or(|character| character.eq_ignore_ascii_case(&sub.character_name));
+    let sub_matches = wanted.parse::<i64>() == Ok(sub.id) || wanted.eq_ignore_ascii_case(&sub.name);
+    character_matches && sub_matches
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CharacterPrefs {
+    /// Full character name (case-insensitive)
+    pub name: String,
+    /// Left out, these follow `[notifications]`
+    pub desktop: Option<bool>,
+    pub push: Option<bool>,
+}
+
+/// Where one FC's notifications go in place of the top-level settings, e.g. a static's
+/// FC to the static's Discord. Whatever it leaves out doesn't get that FC's
+/// notifications at all, the desktop aside.
+#[derive(Deserialize, Debug, Clone)]
+pub struct FcRoute {
+    /// The FC's tag (case-insensitive)
+    pub tag: String,
+    /// Pushes, with these set over `[pushover]`'s, e.g. `device` for only one phone
+    pub pushover: Option<PushoverOptions>,
+    /// A webhook, or a channel for the bot in `[discord]` to post in
+    pub discord: Option<DiscordConfig>,
+    /// Someone or a room to message from the account in `[xmpp]`
+    pub xmpp: Option<XmppRoute>,
+    pub kdeconnect: Option<KdeConnectConfig>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct XmppRoute {
+    pub to: String,
+    #[serde(default)]
+    pub muc: bool,
+    pub nick: Option<String>,
+}
+
+/// Which kinds of notification a sub's returns should raise
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Delivery {
+    pub desktop: bool,
+    pub push: bool,
+    /// Over XMPP, Discord and KDE Connect, which follow `push` since they're just as
+    /// remote, but go by the FC's own route rather than needing its Pushover
+    pub announce: bool,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct HeartbeatConfig {
+    /// Fetched after each good pass over the databases, e.g. a healthchecks.io check's
+    /// ping URL
+    pub url: String,
+    /// At most this often (1m if left out)
+    pub interval: Option<ConfigDuration>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct PresenceConfig {
+    /// What happens to a push that's due while you're here
+    pub action: PresenceAction,
+    /// How much later a push goes for `delay` (10m if left out)
+    pub delay: Option<ConfigDuration>,
+    /// No keyboard or mouse for this long, or a locked screen, counts as away (5m if
+    /// left out)
+    pub idle_after: Option<ConfigDuration>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PresenceAction {
+    /// Don't send it, the desktop notification's enough
+    #[default]
+    Skip,
+    /// Send it `delay` later
+    Delay,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct XmppConfig {
+    /// The account to send from, e.g. `subs@example.org`
+    pub jid: String,
+    pub password: String,
+    /// Who to send to, or the room with `muc` set, e.g. `fc@conference.example.org`
+    pub to: String,
+    /// `to` is a group chat room, which is joined to post in
+    #[serde(default)]
+    pub muc: bool,
+    /// Nickname in the room ("sub-returns" if left out)
+    pub nick: Option<String>,
+    /// Server to connect to as `host` or `host:port`, when it isn't the JID's domain
+    /// on 5222
+    pub server: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct DiscordConfig {
+    /// A channel webhook's URL, to announce returns through
+    pub webhook: Option<String>,
+    /// A bot's token, for the bot to answer `/subs`, `/next` and `/when`. Read at
+    /// startup.
+    pub token: Option<String>,
+    /// Channel id for the bot to announce returns in, when there's no webhook
+    pub channel: Option<String>,
+    /// Server id to register the commands in, where they show up straight away
+    /// rather than after a while everywhere the bot is
+    pub guild: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(default)]
+pub struct KdeConnectConfig {
+    /// Device ids to ping, from `kdeconnect-cli --list-devices`; every paired one
+    /// that's reachable if left out
+    pub devices: Vec<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CaldavConfig {
+    /// The calendar's URL, e.g. `https://cloud.example.com/remote.php/dav/calendars/me/subs/`
+    /// on Nextcloud or `https://caldav.fastmail.com/dav/calendars/user/me@fastmail.com/<id>/`
+    pub url: String,
+    pub username: String,
+    /// Best an app password, so it can be revoked without changing the real one
+    pub password: String,
+}
+
+/// An OAuth client made in the Google Cloud console, of the "Desktop app" kind, with
+/// the Calendar API turned on for its project
+#[derive(Deserialize, Debug, Clone)]
+pub struct GoogleConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    /// The calendar's id, from its settings page (the account's main one if left out)
+    pub calendar: Option<String>,
+}
+
+/// When the daemon warns that a sub will need repairs once it's back
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct RepairConfig {
+    /// Percent condition of the worst part, 0 turns the warning off
+    pub threshold: f64,
+    /// Percent each sector of a voyage takes off every part
+    pub wear_per_sector: f64,
+}
+
+impl Default for RepairConfig {
+    fn default() -> Self {
+        RepairConfig { threshold: 20.0, wear_per_sector: 2.0 }
+    }
+}
+
+/// Settings for splitting reading (`--agent`) and notifying (`--collector`) across
+/// two machines.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default)]
+pub struct SyncConfig {
+    /// Shared secret, the same on both ends
+    pub key: Option<String>,
+    /// Agent: where to send snapshots, e.g. `http://server:1415/`
+    pub collector_url: Option<String>,
+    /// Agent: how this machine is labelled on the collector (defaults to the hostname)
+    pub agent_name: Option<String>,
+    /// Collector: address to listen on (defaults to `127.0.0.1:1415`, only this machine)
+    pub listen: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SourceConfig {
+    /// Shown next to the character in listings and notifications
+    pub label: String,
+    /// The SubmarineTracker plugin folder, or the database file inside it. Can also be
+    /// `ssh://user@host/path` for a database on another machine
+    pub path: PathBuf,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(default)]
+pub struct BridgeConfig {
+    pub url: String,
+    pub psk: String,
+    /// Most notifications to post a minute, the rest wait their turn (no limit if left
+    /// out). 0 is turned down, since nothing would ever go out.
+    pub max_per_minute: Option<NonZeroU32>,
+    /// Hold notifications until none have come in for this long, so a burst (like
+    /// `--update` rewriting every row) goes out as one post. 5s if left out.
+    pub settle: Option<ConfigDuration>,
+}
+
+/// Extra Pushover message fields, passed through the bridge on every push
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(default)]
+pub struct PushoverOptions {
+    pub priority: Option<Priority>,
+    /// Priority for reminders about subs that are back but haven't been collected,
+    /// e.g. `emergency` to keep going off until acknowledged
+    pub overdue_priority: Option<Priority>,
+    /// One of Pushover's sound names, e.g. `cosmic`
+    pub sound: Option<String>,
+    /// Only send to this device, rather than all of them
+    pub device: Option<String>,
+    /// A link shown under the message, e.g. to the plugin's overview
+    pub url: Option<String>,
+    pub url_title: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    Lowest,
+    Low,
+    Normal,
+    High,
+    /// Repeats every minute for up to an hour until it's acknowledged
+    Emergency,
+}
+
+impl PushoverOptions {
+    /// Fills in the options on a bridge message, leaving anything already set alone
+    pub fn apply(&self, notif: &mut serde_json::Map<String, serde_json::Value>, overdue: bool) {
+        let priority = if overdue {
+            self.overdue_priority.or(self.priority)
+        } else {
+            self.priority
+        };
+        if let Some(priority) = priority {
+            notif.entry("priority").or_insert_with(|| priority.value().into());
+            if priority == Priority::Emergency {
+                notif.entry("retry").or_insert(60.into());
+                notif.entry("expire").or_insert(3600.into());
+            }
+        }
+        for (field, value) in [
+            ("sound", &self.sound),
+            ("device", &self.device),
+            ("url", &self.url),
+            ("url_title", &self.url_title),
+        ] {
+            if let Some(value) = value {
+                notif.entry(field).or_insert_with(|| value.clone().into());
+            }
+        }
+    }
+}
+
+impl Priority {
+    /// Pushover's number for it
+    fn value(self) -> i8 {
+        match self {
+            Priority::Lowest => -2,
+            Priority::Low => -1,
+            Priority::Normal => 0,
+            Priority::High => 1,
+            Priority::Emergency => 2,
+        }
+    }
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        // Not proud of this but it meets my needs ok
+        BridgeConfig {
+            url: env!("PUSHOVER_BRIDGE_URL").to_string(),
+            psk: env!("PUSHOVER_BRIDGE_PSK").to_string(),
+            max_per_minute: None,
+            settle: None,
+        }
+    }
+}
+
+impl Config {
+    /// Renames subs that have an alias. Done as they're read, so everything after,
+    /// muting and `--sub` included, goes by the alias.
+    pub fn apply_aliases(&self, subs: &mut [SubInfo]) {
+        for sub in subs {
+            let alias = self.aliases.iter().find(|alias| matches_sub(&alias.sub, alias.character.as_deref(), sub));
+            if let Some(alias) = alias {
+                sub.name.clone_from(&alias.name);
+            }
+        }
+    }
+
+    pub fn is_muted(&self, sub: &SubInfo) -> bool {
+        self.muted.iter().any(|muted| muted.matches(sub))
+    }
+
+    pub fn delivery(&self, sub: &SubInfo) -> Delivery {
+        let everyone = self.default_delivery();
+        let prefs = self
+            .characters
+            .iter()
+            .find(|prefs| prefs.name.eq_ignore_ascii_case(&sub.character_name));
+        let push = prefs.and_then(|prefs| prefs.push).unwrap_or(everyone.push);
+        Delivery {
+            desktop: prefs.and_then(|prefs| prefs.desktop).unwrap_or(everyone.desktop),
+            push: push && self.route(sub).is_none_or(|route| route.pushover.is_some()),
+            announce: push,
+        }
+    }
+
+    /// The `[[free_companies]]` entry for `sub`'s FC, if it has one
+    pub fn route(&self, sub: &SubInfo) -> Option<&FcRoute> {
+        self.free_companies.iter().find(|route| route.tag.eq_ignore_ascii_case(&sub.tag))
+    }
+
+    /// For anyone not in `[[characters]]`, and whatever isn't about a particular sub
+    pub fn default_delivery(&self) -> Delivery {
+        let push = self.notifications.push.unwrap_or(true);
+        Delivery { desktop: self.notifications.desktop.unwrap_or(true), push, announce: push }
+    }
+
+    /// Adds a `[[muted]]` entry for `sub` to the end of the config file, creating it if
+    /// need be. By id, since names can be shared between characters.
+    pub fn append_muted(path: &Path, sub: &SubInfo) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("Couldn't open {}", path.display()))?;
+        writeln!(
+            file,
+            "\n# {name}, muted from a notification\n[[muted]]\nsub = \"{id}\"\ncharacter = {character}",
+            name = sub.name,
+            id = sub.id,
+            character = toml::Value::String(sub.character_name.clone()),
+        )?;
+        Ok(())
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Config> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e).with_context(|| format!("Couldn't read {}", path.display())),
+        };
+        toml::from_str(&contents).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+}
+
+/// A time of day in the config, like `"07:00"`, taken as local time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTime(pub NaiveTime);
+
+impl<'de> Deserialize<'de> for LocalTime {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        NaiveTime::parse_from_str(&s, "%H:%M")
+            .map(LocalTime)
+            .map_err(|_| serde::de::Error::custom(format!("invalid time {s:?}, expected something like 07:00")))
+    }
+}
+
+impl LocalTime {
+    /// The first time after `after` that the clock shows this
+    pub fn next_after(&self, after: DateTime<Utc>) -> DateTime<Utc> {
+        let local = after.with_timezone(&Local);
+        let mut date = local.date_naive();
+        loop {
+            // A DST gap can swallow the time, in which case the next day will do
+            if let Some(next) = Local.from_local_datetime(&date.and_time(self.0)).earliest() {
+                if next > local {
+                    return next.with_timezone(&Utc);
+                }
+            }
+            date = date + Days::new(1);
+        }
+    }
+}
+
+/// A daily window, in local time, with no notifications. It can wrap past midnight
+/// (`start = "23:00"`, `end = "07:00"`).
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct QuietHours {
+    start: LocalTime,
+    end: LocalTime,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: DateTime<Utc>) -> bool {
+        let local = time.with_timezone(&Local).time();
+        let (start, end) = (self.start.0, self.end.0);
+        if start <= end {
+            start <= local && local < end
+        } else {
+            local >= start || local < end
+        }
+    }
+
+    /// When the quiet window that `time` is in (or the next one) is over
+    pub fn end_after(&self, time: DateTime<Utc>) -> DateTime<Utc> {
+        self.end.next_after(time)
+    }
+}
+
+/// When you're usually around to collect subs, in local time, like
+/// `days = "weekdays"`, `start = "19:00"`, `end = "23:00"`. It can wrap past midnight.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlayWindow {
+    #[serde(default)]
+    days: PlayDays,
+    start: LocalTime,
+    end: LocalTime,
+}
+
+/// `"daily"`, `"weekdays"`, `"weekends"` or a list like `["mon", "thu"]`
+#[derive(Debug, Clone)]
+pub struct PlayDays(Vec<Weekday>);
+
+impl Default for PlayDays {
+    fn default() -> Self {
+        PlayDays(ALL_DAYS.to_vec())
+    }
+}
+
+const ALL_DAYS: [Weekday; 7] =
+    [Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri, Weekday::Sat, Weekday::Sun];
+
+impl<'de> Deserialize<'de> for PlayDays {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Named(String),
+            List(Vec<String>),
+        }
+        let day = |name: &str| {
+            name.parse::<Weekday>()
+                .map_err(|_| serde::de::Error::custom(format!("unknown day {name:?}, expected something like \"mon\"")))
+        };
+        let days = match Raw::deserialize(deserializer)? {
+            Raw::Named(name) => match name.to_lowercase().as_str() {
+                "daily" => ALL_DAYS.to_vec(),
+                "weekdays" => ALL_DAYS[..5].to_vec(),
+                "weekends" => ALL_DAYS[5..].to_vec(),
+                other => vec![day(other)?],
+            },
+            Raw::List(names) => names.iter().map(|name| day(name)).collect::<Result<_, _>>()?,
+        };
+        Ok(PlayDays(days))
+    }
+}
+
+impl PlayWindow {
+    /// Every time this window opens and closes in the next `days`, as (start, end),
+    /// starting with one that might already be open
+    pub fn occurrences(&self, now: DateTime<Utc>, days: u64) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let today = now.with_timezone(&Local).date_naive();
+        let at = |date, time: NaiveTime| {
+            Local.from_local_datetime(&chrono::NaiveDateTime::new(date, time)).earliest().map(|t| t.with_timezone(&Utc))
+        };
+        (0..=days + 1)
+            .filter_map(|offset| today.checked_sub_days(Days::new(1))?.checked_add_days(Days::new(offset)))
+            .filter(|date| self.days.0.contains(&date.weekday()))
+            .filter_map(|date| {
+                let end_date = if self.end.0 <= self.start.0 { date.succ_opt()? } else { date };
+                Some((at(date, self.start.0)?, at(end_date, self.end.0)?))
+            })
+            .filter(|(_, end)| *end > now)
+            .collect()
+    }
+}
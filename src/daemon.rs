@@ -0,0 +1,1034 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::Context;
+use chrono::{DateTime, Local, TimeDelta, Utc};
+use notify_rust::Notification;
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    config::{self, Config, PresenceAction, PresenceConfig, QuietHours},
+    config_path, data_dir,
+    db::{self, get_all_submarine_info, SubInfo},
+    desktop, digest, discord,
+    duration::format_duration,
+    i18n::fill,
+    ipc::{self, ControlCommand},
+    lock::InstanceLock,
+    notifier::Announcer,
+    outbox::Outbox,
+    predict,
+    presence::{self, Presence},
+    quiet,
+    repairs, resources, schedule,
+    sectors::Sectors,
+    spool::Spool,
+    state::StateFile,
+    sync, systemd, template::{self, Template}, time_display, timefmt, view_filter, voyage_log, LaunchArgs,
+};
+#[cfg(target_os = "linux")]
+use crate::dbus;
+
+/// A gap this long between passes over the databases is taken as the machine having
+/// slept through it
+const WAKE_GAP: TimeDelta = TimeDelta::minutes(5);
+/// Longest between passes with nothing due, which is also how often sources that can't
+/// be watched for changes (remote, collector and JSON ones) are read again
+const MAX_SLEEP: TimeDelta = TimeDelta::seconds(30);
+/// How often the databases are checked for changes while waiting
+const TICK: Duration = Duration::from_secs(1);
+/// Longest any one request in a pass may take, well inside the unit's `WatchdogSec`
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub fn run(args: &LaunchArgs) -> anyhow::Result<()> {
+    let replace = args.replace;
+    let collector = args.collector;
+    let filter = view_filter(args);
+
+    let _lock = InstanceLock::acquire(&data_dir(), replace)?;
+
+    let (mut config, mut display, mut templates) = load_daemon_config(args)?;
+    if let Err(e) = desktop::init(&config.notifications) {
+        warn!(error = format!("{e:#}"), "couldn't register for notifications");
+    }
+    let client = Client::builder().timeout(HTTP_TIMEOUT).build()?;
+    let spool = Spool::new(data_dir().join("bridge-spool.jsonl"));
+    let mut outbox = Outbox::new();
+    let mut presence = Presence::new();
+    let announcer = Announcer::start();
+    let discord_bot = config.discord.as_ref().and_then(|discord| {
+        let token = discord.token.clone()?;
+        Some(discord::Bot::start(token, discord.guild.clone(), display.text()))
+    });
+
+    let state_file = StateFile::new(data_dir().join("notify-state.json"));
+    let mut notifs_data: HashMap<String, NotifyMeta> = state_file.load();
+    let sources = if collector {
+        vec![db::SubSource::collector(sync::start_collector(&config.sync)?)]
+    } else {
+        db::open_sources(&config.sources)?
+    };
+
+    // SIGINT/SIGTERM on unix, Ctrl+C and console close on Windows
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .context("Couldn't install the shutdown signal handler")?;
+
+    let (control_tx, control_rx) = mpsc::channel();
+    if let Err(e) = ipc::serve(control_tx) {
+        warn!(error = %e, "control socket unavailable");
+    }
+    #[cfg(target_os = "linux")]
+    let mut bus = dbus::Bus::start()
+        .inspect_err(|e| warn!(error = format!("{e:#}"), "D-Bus service unavailable"))
+        .ok();
+    let (action_tx, action_rx) = mpsc::channel();
+    let mut paused = false;
+    let mut last_db_read: Option<DateTime<Utc>> = None;
+    let mut ready = false;
+    let mut last_push: Option<DateTime<Utc>> = None;
+    let mut pending: Vec<Value> = Vec::new();
+    // Returns that happened during quiet hours, for the desktop catch-up, and the same
+    // for the notifiers
+    let mut held: Vec<SubInfo> = Vec::new();
+    let mut held_announced: Vec<SubInfo> = Vec::new();
+    // Announcements [presence] put off while you were here
+    let mut delayed_announcements: Vec<DelayedAnnouncement> = Vec::new();
+    let mut next_digest: Option<DateTime<Utc>> = None;
+    let mut stale_warned = false;
+    let watchdog_interval = systemd::watchdog_interval();
+    let mut last_watchdog_ping = std::time::Instant::now();
+    let mut last_heartbeat: Option<std::time::Instant> = None;
+    // For what each route costs in tanks
+    let mut sectors = Sectors::load();
+    // Owners already told they're short on tanks, until they aren't
+    let mut low_tanks_warned: Vec<String> = Vec::new();
+    // The ids of each owner's grouped pushes, to take back any a regrouping leaves behind
+    let mut owner_pushes: HashMap<String, Vec<String>> = HashMap::new();
+    let mut last_pass: Option<DateTime<Utc>> = None;
+    // Only read again when they change or it's been a while, in between the last read
+    // does, before aliases and filters since those can be reloaded
+    let db_paths = if collector { Vec::new() } else { db::db_paths(&config.sources) };
+    let mut last_modified = db::modified_times(&db_paths);
+    let mut last_read: Option<(Instant, Vec<SubInfo>)> = None;
+    // What woke the wait early, for the pass to handle
+    let mut requests = Vec::new();
+    let mut actions = Vec::new();
+
+    while running.load(Ordering::SeqCst) {
+        // Passes are at most MAX_SLEEP apart, so a gap well past that since the last one means
+        // the machine was asleep and everything that came back meanwhile is due at once
+        let pass_started = Utc::now();
+        let asleep_since = last_pass.filter(|last| pass_started - *last > WAKE_GAP);
+        if let Some(since) = asleep_since {
+            info!(%since, "woke up");
+        }
+        last_pass = Some(pass_started);
+        for request in std::mem::take(&mut requests).into_iter().chain(control_rx.try_iter()) {
+            let response = match request.command {
+                ControlCommand::Pause => {
+                    paused = true;
+                    json!({"ok": true, "paused": paused})
+                }
+                ControlCommand::Resume => {
+                    paused = false;
+                    json!({"ok": true, "paused": paused})
+                }
+                ControlCommand::ReloadConfig => match load_daemon_config(args) {
+                    Ok((new_config, new_display, new_templates)) => {
+                        config = new_config;
+                        display = new_display;
+                        templates = new_templates;
+                        json!({"ok": true})
+                    }
+                    Err(e) => json!({"ok": false, "error": format!("{e:#}")}),
+                },
+                ControlCommand::DumpState => {
+                    json!({"ok": true, "paused": paused, "subs": notifs_data})
+                }
+                ControlCommand::Status => json!({
+                    "ok": true,
+                    "pid": std::process::id(),
+                    "paused": paused,
+                    "last_db_read": last_db_read,
+                    "last_push": last_push,
+                    "pending": pending,
+                }),
+            };
+            request.reply(response);
+        }
+
+        for action in std::mem::take(&mut actions).into_iter().chain(action_rx.try_iter()) {
+            match action {
+                desktop::Action::Snooze(keys) => {
+                    info!(subs = ?keys, "snoozed");
+                    for key in keys {
+                        if let Some(meta) = notifs_data.get_mut(&key) {
+                            meta.snoozed_until = Some(Utc::now() + TimeDelta::hours(1));
+                        }
+                    }
+                    if let Err(e) = state_file.save(&notifs_data) {
+                        error!(error = format!("{e:#}"), "couldn't save notification state");
+                    }
+                }
+                desktop::Action::Mute(sub) => {
+                    info!(sub_id = sub.id, sub = %sub.name, "muted from a notification");
+                    let muted = Config::append_muted(&config_path(), &sub).and_then(|_| load_daemon_config(args));
+                    match muted {
+                        Ok((new_config, new_display, new_templates)) => {
+                            config = new_config;
+                            display = new_display;
+                            templates = new_templates;
+                        }
+                        Err(e) => error!(error = format!("{e:#}"), "couldn't mute sub"),
+                    }
+                }
+            }
+        }
+
+        match spool.flush(|payload| post_to_bridge(&client, &config.bridge, payload)) {
+            Ok(0) => {}
+            Ok(sent) => {
+                info!(sent, "flushed spooled bridge payloads");
+                last_push = Some(Utc::now());
+            }
+            Err(e) => error!(error = format!("{e:#}"), "couldn't read bridge spool"),
+        }
+
+        let modified = db::modified_times(&db_paths);
+        let read = match last_read.take() {
+            Some((at, subs)) if modified == last_modified && at.elapsed() < MAX_SLEEP.to_std().unwrap_or_default() => {
+                Ok((at, subs))
+            }
+            _ => get_all_submarine_info(&sources).map(|mut subs| {
+                last_db_read = Some(Utc::now());
+                predict::fill_in(&mut subs, &sources);
+                (Instant::now(), subs)
+            }),
+        };
+        let mut subs = match read {
+            Ok((at, subs)) => {
+                last_modified = modified;
+                last_read = Some((at, subs.clone()));
+                subs
+            }
+            Err(e) => {
+                // Most likely the plugin holding a write lock for longer than we'll wait,
+                // which sorts itself out; next time around will pick up whatever changed
+                warn!(error = format!("{e:#}"), "couldn't read the database");
+                std::thread::sleep(TICK);
+                continue;
+            }
+        };
+        config.apply_aliases(&mut subs);
+        subs.retain(|sub| filter.matches(sub) && !config.is_muted(sub));
+        if !ready {
+            systemd::notify("READY=1");
+            ready = true;
+        }
+        if let Some(bot) = &discord_bot {
+            bot.update(&subs);
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(published) = &mut bus {
+            if let Err(e) = published.update(&subs, Utc::now()) {
+                warn!(error = format!("{e:#}"), "couldn't update D-Bus, stopping the service");
+                bus = None;
+            }
+        }
+        pending.clear();
+        let mut bridge_json_payload = serde_json::Map::new();
+        // (Re)scheduled this time around and due a push, whose owners' pushes are regrouped
+        let mut pushing: Vec<&SubInfo> = Vec::new();
+        let mut state_changed = false;
+        let quiet_now = config.quiet_hours.is_some_and(|quiet| quiet.contains(Utc::now()));
+        let mut dispatched: Vec<&SubInfo> = Vec::new();
+        let all_home = config.notifications.all_home;
+        // Owners with a return (re)scheduled this time around, and ones with a sub that
+        // just came back
+        let mut scheduled_owners: Vec<String> = Vec::new();
+        let mut returned_now: Vec<String> = Vec::new();
+        let group_window = args
+            .group_window
+            .or(config.notifications.group_window)
+            .map_or(TimeDelta::minutes(5), |window| window.0);
+        // Back this time around and due a desktop notification, shown a group at a time
+        let mut returning: Vec<&SubInfo> = Vec::new();
+        // Came back while the machine was asleep, shown as one
+        let mut away: Vec<&SubInfo> = Vec::new();
+        // Back this time around, for the notifiers
+        let mut announced: Vec<&SubInfo> = Vec::new();
+        for sub in &subs {
+            let delivery = config.delivery(sub);
+            let mut meta = notifs_data
+                .get(&sub.key())
+                .cloned()
+                .unwrap_or_else(|| NotifyMeta {
+                    submarine_id: sub.id,
+                    will_notify: true,
+                    last_return_time: Default::default(),
+                    leads_sent: Vec::new(),
+                    nags_sent: 0,
+                    last_nag: None,
+                    snoozed_until: None,
+                    repair_warned: false,
+                    overdue_sent: false,
+                });
+            if meta.last_return_time != sub.return_time && sub.return_time > Local::now() {
+                // A later return than one we already knew about means it's been sent out
+                // again. The first sighting of a sub doesn't count.
+                if meta.last_return_time != DateTime::<Utc>::default() && sub.return_time > meta.last_return_time {
+                    dispatched.push(sub);
+                    if let Err(e) = voyage_log::append(sub, Utc::now()) {
+                        warn!(error = format!("{e:#}"), "couldn't log voyage");
+                    }
+                }
+                meta.will_notify = true;
+                meta.last_return_time = sub.return_time;
+                scheduled_owners.push(sub.owner());
+                meta.leads_sent.clear();
+                meta.nags_sent = 0;
+                meta.last_nag = None;
+                meta.snoozed_until = None;
+                meta.repair_warned = false;
+                meta.overdue_sent = false;
+                info!(
+                    sub_id = sub.id,
+                    sub = %sub.name,
+                    owner = %sub.owner(),
+                    return_time = %sub.return_time.with_timezone(&Local),
+                    "notification scheduled"
+                );
+
+                if delivery.push {
+                    pushing.push(sub);
+                    // Heads-ups are one per sub rather than grouped, they're for getting
+                    // online in time, not for knowing what's back
+                    for lead in &config.notifications.lead_times {
+                        let alert_time = sub.return_time - lead.0;
+                        if alert_time <= Utc::now() {
+                            continue;
+                        }
+                        let context = template::lead_context(sub, &display, lead.0);
+                        let Some((title, body)) = render(&templates.lead_title, &templates.lead_body, &context) else {
+                            continue;
+                        };
+                        let notif =
+                            json!({"title": title, "message": body, "timestamp": alert_time.timestamp_millis()});
+                        bridge_json_payload.insert(
+                            format!("{key}-lead-{secs}", key = sub.key(), secs = lead.0.num_seconds()),
+                            routed(&config, sub, notif, false),
+                        );
+                    }
+                }
+            }
+
+            // Held back while the rest of its group is still out, so they all come up
+            // together when the last one is in
+            if meta.will_notify && sub.return_time <= Local::now() && schedule::group_end(&subs, sub, group_window) <= Utc::now() {
+                meta.will_notify = false;
+                if paused || !delivery.announce {
+                    // Not announced at all
+                } else if quiet_now {
+                    held_announced.push(sub.clone());
+                } else {
+                    announced.push(sub);
+                }
+                if all_home {
+                    returned_now.push(sub.owner());
+                } else if !delivery.desktop {
+                    // List-only, or push-only
+                } else if quiet_now {
+                    held.push(sub.clone());
+                } else if paused {
+                    // Not shown at all, that's what pausing is for
+                } else if asleep_since.is_some_and(|since| sub.return_time > since) {
+                    away.push(sub);
+                } else {
+                    returning.push(sub);
+                }
+            }
+            if meta.snoozed_until.is_some_and(|until| until <= Utc::now()) {
+                meta.snoozed_until = None;
+                if delivery.desktop && !paused && !quiet_now {
+                    if asleep_since.is_some() {
+                        away.push(sub);
+                    } else {
+                        returning.push(sub);
+                    }
+                }
+            }
+            // Still sitting there since it was notified about, so nag. Subs that were
+            // already back when the daemon first saw them don't count.
+            let waiting =
+                !meta.will_notify && sub.return_time <= Utc::now() && meta.last_return_time == sub.return_time;
+            let nag_due = match config.notifications.nag_interval {
+                Some(interval) if waiting => {
+                    let since = meta.last_nag.unwrap_or(sub.return_time);
+                    Utc::now() - since >= interval.0
+                        && config.notifications.nag_max.is_none_or(|max| meta.nags_sent < max)
+                }
+                _ => false,
+            };
+            // Without overdue_after every reminder is overdue
+            let overdue =
+                config.notifications.overdue_after.is_none_or(|after| Utc::now() - sub.return_time >= after.0);
+            if nag_due && !paused && !quiet_now && (delivery.push || delivery.desktop) {
+                meta.nags_sent += 1;
+                meta.last_nag = Some(Utc::now());
+                let context = template::notification_context(sub, &display, 1);
+                let rendered = render(&templates.nag_title, &templates.nag_body, &context);
+                info!(sub_id = sub.id, sub = %sub.name, nags = meta.nags_sent, "nagging about uncollected sub");
+                if let (Some((title, body)), true) = (&rendered, delivery.push) {
+                    let notif = json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()});
+                    let mut notif = routed(&config, sub, notif, overdue);
+                    if let Some(fields) = notif.as_object_mut() {
+                        config.pushover.apply(fields, overdue);
+                    }
+                    bridge_json_payload.insert(format!("{key}-nag-{n}", key = sub.key(), n = meta.nags_sent), notif);
+                }
+                if let (Some((title, body)), true) = (&rendered, delivery.desktop) {
+                    show(&mut desktop::notification(title, body), config.notifications.sound.as_deref());
+                }
+            }
+
+            let escalate = config.notifications.overdue_after.is_some() && waiting && overdue && !meta.overdue_sent;
+            if escalate && !paused && !quiet_now && (delivery.push || delivery.desktop) {
+                meta.overdue_sent = true;
+                let context = template::notification_context(sub, &display, 1);
+                let rendered = render(&templates.overdue_title, &templates.overdue_body, &context);
+                warn!(sub_id = sub.id, sub = %sub.name, "sub is overdue");
+                if let (Some((title, body)), true) = (&rendered, delivery.push) {
+                    let notif = json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()});
+                    let mut notif = routed(&config, sub, notif, true);
+                    if let Some(fields) = notif.as_object_mut() {
+                        config.pushover.apply(fields, true);
+                    }
+                    bridge_json_payload.insert(format!("{key}-overdue", key = sub.key()), notif);
+                }
+                if let (Some((title, body)), true) = (&rendered, delivery.desktop) {
+                    let mut notification = desktop::notification(title, body);
+                    #[cfg(all(unix, not(target_os = "macos")))]
+                    notification.urgency(notify_rust::Urgency::Critical);
+                    show(&mut notification, config.notifications.sound.as_deref());
+                }
+            }
+
+            // Once per voyage, as early as possible so there's time to get the kits in
+            if !meta.repair_warned && !paused && !quiet_now {
+                if let Some((title, body)) = repairs::warning(sub, &config.repairs, display.text()) {
+                    meta.repair_warned = true;
+                    info!(sub_id = sub.id, sub = %sub.name, "will need repairs");
+                    if delivery.push {
+                        let notif =
+                            json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()});
+                        bridge_json_payload
+                            .insert(format!("{key}-repair", key = sub.key()), routed(&config, sub, notif, false));
+                    }
+                    if delivery.desktop {
+                        show(
+                            desktop::notification(&title, &body).icon("dialog-warning"),
+                            config.notifications.sound.as_deref(),
+                        );
+                    }
+                }
+            }
+
+            // Only the closest of any heads-ups that are due, the rest are marked sent so
+            // starting up five minutes before a return gives one alert rather than three
+            let now = Utc::now();
+            let leads: Vec<TimeDelta> = config.notifications.lead_times.iter().map(|lead| lead.0).collect();
+            let due = schedule::due_leads(sub, &leads, &meta.leads_sent, now);
+            if meta.will_notify && sub.return_time > now {
+                if let Some(closest) = due.iter().min() {
+                    meta.leads_sent.extend(due.iter().map(|lead| lead.num_seconds()));
+                    let context = template::lead_context(sub, &display, *closest);
+                    let rendered = render(&templates.lead_title, &templates.lead_body, &context);
+                    if let (Some((title, body)), false) = (rendered, paused || quiet_now || !delivery.desktop) {
+                        show(&mut desktop::notification(&title, &body), config.notifications.sound.as_deref());
+                    }
+                }
+            }
+            if meta.will_notify && sub.return_time > Local::now() {
+                pending.push(json!({
+                    "name": sub.name,
+                    "owner": sub.owner(),
+                    "return_time": sub.return_time,
+                }));
+            }
+            if notifs_data.get(&sub.key()) != Some(&meta) {
+                notifs_data.insert(sub.key(), meta);
+                state_changed = true;
+            }
+        }
+        if state_changed {
+            if let Err(e) = state_file.save(&notifs_data) {
+                error!(error = format!("{e:#}"), "couldn't save notification state");
+            }
+        }
+        // One push per run of an owner's returns, going off when the last of them is in.
+        // A return being (re)scheduled can join or split their runs, so all of that
+        // owner's upcoming ones are grouped again, each run under an id that only
+        // changes when the run does.
+        if !all_home {
+            let mut owners: Vec<String> = pushing.iter().map(|sub| sub.owner()).collect();
+            owners.sort();
+            owners.dedup();
+            for owner in owners {
+                let upcoming: Vec<&SubInfo> = subs
+                    .iter()
+                    .filter(|sub| sub.owner() == owner && sub.return_time > Utc::now() && config.delivery(sub).push)
+                    .filter(|sub| notifs_data.get(&sub.key()).is_some_and(|meta| meta.will_notify))
+                    .collect();
+                let mut ids = Vec::new();
+                for group in schedule::group_returns(&upcoming, group_window) {
+                    let last = group[group.len() - 1];
+                    let context = template::notification_context(last, &display, group.len() as u32);
+                    let id = format!("{owner}-{key}-{time}", key = last.key(), time = last.return_time.timestamp());
+                    // Still counts as theirs, so a broken template doesn't cancel what's there
+                    ids.push(id.clone());
+                    let Some((title, body)) = render(&templates.push_title, &templates.push_body, &context) else {
+                        continue;
+                    };
+                    let notif =
+                        json!({"title": title, "message": body, "timestamp": last.return_time.timestamp_millis()});
+                    bridge_json_payload.insert(id, routed(&config, last, notif, false));
+                }
+                // Ones already handed to the bridge are its to send, there's no taking those back
+                for stale in owner_pushes.insert(owner, ids.clone()).unwrap_or_default() {
+                    if !ids.contains(&stale) {
+                        debug!(id = stale, "regrouped, dropping push");
+                        outbox.cancel(&stale);
+                        presence.cancel(&stale);
+                    }
+                }
+            }
+        }
+        for group in schedule::group_returns(&returning, group_window) {
+            let last = group[group.len() - 1];
+            let context = template::notification_context(last, &display, group.len() as u32);
+            let Some((title, body)) = render(&templates.desktop_title, &templates.desktop_body, &context) else {
+                continue;
+            };
+            let sound = config.notifications.sound.as_deref();
+            if let Err(e) = desktop::show_return(&mut desktop::notification(&title, &body), sound, &group, &action_tx) {
+                warn!(error = format!("{e:#}"), "couldn't show desktop notification");
+            }
+        }
+        let mut announcements: Vec<(SubInfo, String, String)> = Vec::new();
+        for group in schedule::group_returns(&announced, group_window) {
+            let context = template::notification_context(group[group.len() - 1], &display, group.len() as u32);
+            if let Some((title, body)) = render(&templates.push_title, &templates.push_body, &context) {
+                announcements.push((group[0].clone(), title, body));
+            }
+        }
+        if !held_announced.is_empty() && !quiet_now && !paused {
+            // A catch-up for each FC's route, like the pushes get
+            let route_of = |sub: &SubInfo| config.route(sub).map(|route| route.tag.to_lowercase());
+            let mut routes: Vec<Option<String>> = held_announced.iter().map(route_of).collect();
+            routes.sort();
+            routes.dedup();
+            for route in routes {
+                let back: Vec<&SubInfo> = held_announced.iter().filter(|sub| route_of(sub) == route).collect();
+                let (title, body) = quiet::catch_up_message(&back, None, &display);
+                announcements.push((back[0].clone(), title, body));
+            }
+            held_announced.clear();
+        }
+        if !announcements.is_empty() {
+            let here = config.presence.as_ref().filter(|presence| presence::here(presence));
+            match here {
+                None => {
+                    for (sub, title, body) in announcements {
+                        announcer.announce(&config, config.route(&sub), title, body);
+                    }
+                }
+                Some(PresenceConfig { action: PresenceAction::Skip, .. }) => {
+                    info!(count = announcements.len(), "you're at the computer, skipping announcements");
+                }
+                Some(PresenceConfig { action: PresenceAction::Delay, delay, .. }) => {
+                    let due = Utc::now() + delay.map_or(TimeDelta::minutes(10), |delay| delay.0);
+                    info!(count = announcements.len(), %due, "you're at the computer, delaying announcements");
+                    for (sub, title, body) in announcements {
+                        delayed_announcements.push(DelayedAnnouncement { due, sub, title, body });
+                    }
+                }
+            }
+        }
+        // Like the pushes, they go when they're due without asking again
+        let (due, later) = std::mem::take(&mut delayed_announcements)
+            .into_iter()
+            .partition::<Vec<_>, _>(|delayed| delayed.due <= Utc::now() && !quiet_now);
+        delayed_announcements = later;
+        if !paused {
+            for delayed in due {
+                announcer.announce(&config, config.route(&delayed.sub), delayed.title, delayed.body);
+            }
+        }
+        if !away.is_empty() {
+            let (title, body) = quiet::catch_up_message(&away, Some(display.text().away_title), &display);
+            info!(subs = away.len(), "returned while asleep");
+            show(&mut desktop::notification(&title, &body), config.notifications.sound.as_deref());
+        }
+        if all_home {
+            let news = FleetNews { scheduled: &scheduled_owners, returned: &returned_now, silent: quiet_now || paused };
+            notify_all_home(&config, &subs, &news, &mut bridge_json_payload, &templates, &display);
+        }
+        if !held.is_empty() && !quiet_now && !paused {
+            let (title, body) = quiet::catch_up_message(&held.iter().collect::<Vec<_>>(), None, &display);
+            show(&mut desktop::notification(&title, &body), config.notifications.sound.as_deref());
+            held.clear();
+        }
+        dispatched.retain(|sub| {
+            let delivery = config.delivery(sub);
+            delivery.push || delivery.desktop
+        });
+        if config.notifications.voyage_started && !paused {
+            // One per owner, since theirs usually all go out together and each FC's go
+            // wherever it's routed. Subs are sorted by return time, so the first is the
+            // first back.
+            let mut owners: Vec<String> = dispatched.iter().map(|sub| sub.owner()).collect();
+            owners.sort();
+            owners.dedup();
+            for owner in owners {
+                let group: Vec<&SubInfo> = dispatched.iter().copied().filter(|sub| sub.owner() == owner).collect();
+                let context = template::notification_context(group[0], &display, group.len() as u32);
+                let Some((title, body)) = render(&templates.voyage_title, &templates.voyage_body, &context) else {
+                    continue;
+                };
+                info!(%owner, subs = group.len(), "voyage started");
+                if group.iter().any(|sub| config.delivery(sub).push) {
+                    let notif = json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()});
+                    bridge_json_payload
+                        .insert(format!("voyage-{}", group[0].key()), routed(&config, group[0], notif, false));
+                }
+                if !quiet_now && group.iter().any(|sub| config.delivery(sub).desktop) {
+                    show(&mut desktop::notification(&title, &body), config.notifications.sound.as_deref());
+                }
+            }
+        }
+        sectors.fetch(&client, subs.iter().flat_map(|sub| sub.route.iter().copied()));
+        let short = resources::short_on_tanks(&subs, &sectors);
+        low_tanks_warned.retain(|owner| short.iter().any(|(short, _, _)| short == owner));
+        for (owner, have, need) in short {
+            if low_tanks_warned.contains(&owner) || paused || quiet_now {
+                continue;
+            }
+            low_tanks_warned.push(owner.clone());
+            let Some(first) = subs.iter().find(|sub| sub.owner() == owner) else {
+                continue;
+            };
+            let delivery = config.delivery(first);
+            let title = fill(display.text().low_tanks_title, &[("owner", &owner)]);
+            let body =
+                fill(display.text().low_tanks_body, &[("have", &have.to_string()), ("need", &need.to_string())]);
+            warn!(%owner, have, need, "low on ceruleum tanks");
+            if delivery.push {
+                let notif = json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()});
+                bridge_json_payload.insert(format!("tanks-{owner}"), routed(&config, first, notif, false));
+            }
+            if delivery.desktop {
+                show(
+                    desktop::notification(&title, &body).icon("dialog-warning"),
+                    config.notifications.sound.as_deref(),
+                );
+            }
+        }
+        // Every return being long gone means the plugin has stopped writing, not that
+        // nothing's happening
+        let newest = subs.iter().map(|sub| sub.return_time).max();
+        match (config.notifications.stale_after, newest) {
+            (Some(stale_after), Some(newest)) if Utc::now() - newest > stale_after.0 => {
+                if !stale_warned && !paused && !quiet_now {
+                    stale_warned = true;
+                    let title = display.text().stale_title;
+                    let body =
+                        fill(display.text().stale_body, &[("time", &format_duration(Utc::now() - newest))]);
+                    warn!(%newest, "submarine data looks stale");
+                    let delivery = config.default_delivery();
+                    if delivery.push {
+                        bridge_json_payload.insert(
+                            "stale-data".to_string(),
+                            json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()}),
+                        );
+                    }
+                    if delivery.desktop {
+                        show(
+                            desktop::notification(title, &body).icon("dialog-warning"),
+                            config.notifications.sound.as_deref(),
+                        );
+                    }
+                }
+            }
+            _ => stale_warned = false,
+        }
+        if let Some(quiet) = config.quiet_hours {
+            hold_for_quiet_hours(&mut bridge_json_payload, &quiet, &subs, &display);
+        }
+        // After quiet hours are applied, the digest goes out when it's asked for
+        match (config.notifications.digest_time, next_digest) {
+            (None, _) => next_digest = None,
+            (Some(digest_time), None) => next_digest = Some(digest_time.next_after(Utc::now())),
+            (Some(digest_time), Some(due)) if Utc::now() >= due => {
+                next_digest = Some(digest_time.next_after(Utc::now()));
+                if !paused {
+                    let (title, body) = digest::message(&subs, Utc::now(), &display);
+                    info!("sending daily digest");
+                    let delivery = config.default_delivery();
+                    if delivery.push {
+                        bridge_json_payload.insert(
+                            format!("digest-{}", due.timestamp()),
+                            json!({"title": title, "message": body, "timestamp": Utc::now().timestamp_millis()}),
+                        );
+                    }
+                    if delivery.desktop {
+                        show(
+                            &mut desktop::notification(&title, &body),
+                            config.notifications.sound.as_deref(),
+                        );
+                    }
+                }
+            }
+            _ => {}
+        }
+        for fields in bridge_json_payload.values_mut().filter_map(Value::as_object_mut) {
+            config.pushover.apply(fields, false);
+        }
+        if !paused {
+            if config.presence.is_some() {
+                presence.hold(bridge_json_payload);
+            } else {
+                outbox.queue(bridge_json_payload);
+            }
+        }
+        // Like anything else, pushes that come due while paused aren't sent
+        let due = presence.due(config.presence.as_ref(), Utc::now());
+        if !paused {
+            outbox.queue(due);
+        }
+        let settle = config.bridge.settle.map_or(TimeDelta::seconds(5), |settle| settle.0);
+        if let Some(payload) = outbox.take(settle.to_std().unwrap_or_default(), config.bridge.max_per_minute) {
+            let payload = Value::Object(payload);
+            debug!(%payload, "posting to pushover bridge");
+            // Anything queued from an earlier outage has to go first, otherwise this
+            // would jump the queue and land on the phone out of order
+            let delivered = if spool.is_empty() {
+                post_to_bridge(&client, &config.bridge, &payload)
+                    .inspect_err(|e| warn!(error = format!("{e:#}"), "bridge post failed, spooling payload"))
+                    .is_ok()
+            } else {
+                false
+            };
+            if delivered {
+                last_push = Some(Utc::now());
+            } else if let Err(e) = spool.push(&payload) {
+                error!(error = format!("{e:#}"), %payload, "couldn't spool bridge payload, it's lost");
+            }
+        }
+
+        if let Some(interval) = watchdog_interval {
+            if last_watchdog_ping.elapsed() >= interval / 2 {
+                systemd::notify("WATCHDOG=1");
+                last_watchdog_ping = std::time::Instant::now();
+            }
+        }
+        if let Some(heartbeat) = &config.heartbeat {
+            let interval = heartbeat.interval.map_or(TimeDelta::minutes(1), |interval| interval.0);
+            if last_heartbeat.is_none_or(|last| last.elapsed() >= interval.to_std().unwrap_or_default()) {
+                last_heartbeat = Some(std::time::Instant::now());
+                if let Err(e) = ping_heartbeat(&client, &heartbeat.url) {
+                    warn!(error = format!("{e:#}"), "heartbeat failed");
+                }
+            }
+        }
+
+        // Nothing changes between now and whatever's next, unless the databases do or
+        // something comes in over the socket or from a notification's buttons
+        let now = Utc::now();
+        let since = |instant: Instant| now - TimeDelta::from_std(instant.elapsed()).unwrap_or_default();
+        let mut wake = vec![now + MAX_SLEEP];
+        wake.extend(next_sub_event(&subs, &notifs_data, &config, group_window, now));
+        wake.extend(next_digest);
+        wake.extend(presence.next_due());
+        if !outbox.is_empty() {
+            wake.push(now + TimeDelta::seconds(1));
+        }
+        if let (Some(quiet), false) = (config.quiet_hours, held.is_empty() && held_announced.is_empty()) {
+            wake.push(quiet.end_after(now));
+        }
+        wake.extend(delayed_announcements.iter().map(|delayed| delayed.due));
+        if let (Some(stale_after), Some(newest), false) =
+            (config.notifications.stale_after, subs.iter().map(|sub| sub.return_time).max(), stale_warned)
+        {
+            wake.push(newest + stale_after.0);
+        }
+        if let Some(interval) = watchdog_interval.and_then(|interval| TimeDelta::from_std(interval / 2).ok()) {
+            wake.push(since(last_watchdog_ping) + interval);
+        }
+        if let (Some(heartbeat), Some(last)) = (&config.heartbeat, last_heartbeat) {
+            wake.push(since(last) + heartbeat.interval.map_or(TimeDelta::minutes(1), |interval| interval.0));
+        }
+        let wake = wake.into_iter().min().unwrap_or(now);
+        debug!(%wake, "waiting");
+        while running.load(Ordering::SeqCst) && Utc::now() < wake {
+            match control_rx.recv_timeout(TICK) {
+                Ok(request) => {
+                    requests.push(request);
+                    break;
+                }
+                // Without the control socket nothing can come in, so wait the tick out
+                Err(RecvTimeoutError::Disconnected) => std::thread::sleep(TICK),
+                Err(RecvTimeoutError::Timeout) => {}
+            }
+            if let Ok(action) = action_rx.try_recv() {
+                actions.push(action);
+                break;
+            }
+            if db::modified_times(&db_paths) != last_modified {
+                break;
+            }
+        }
+    }
+
+    systemd::notify("STOPPING=1");
+    info!("shutting down");
+    // Whatever was still settling or over the limit goes out on the next start
+    outbox.queue(presence.drain());
+    if let Some(payload) = outbox.take(Duration::ZERO, None) {
+        let payload = Value::Object(payload);
+        if let Err(e) = spool.push(&payload) {
+            error!(error = format!("{e:#}"), %payload, "couldn't spool bridge payload, it's lost");
+        }
+    }
+    state_file.save(&notifs_data)?;
+    for source in sources {
+        source.close()?;
+    }
+    Ok(())
+}
+
+/// The soonest anything about `subs` is due: a return, the end of a group, a heads-up,
+/// a snooze running out or a reminder
+fn next_sub_event(
+    subs: &[SubInfo],
+    notifs_data: &HashMap<String, NotifyMeta>,
+    config: &Config,
+    group_window: TimeDelta,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    let mut events = Vec::new();
+    for sub in subs {
+        let Some(meta) = notifs_data.get(&sub.key()) else {
+            continue;
+        };
+        if meta.will_notify {
+            events.push(sub.return_time);
+            events.push(schedule::group_end(subs, sub, group_window));
+            for lead in &config.notifications.lead_times {
+                if !meta.leads_sent.contains(&lead.0.num_seconds()) {
+                    events.push(sub.return_time - lead.0);
+                }
+            }
+        }
+        events.extend(meta.snoozed_until);
+        if let Some(interval) = config.notifications.nag_interval {
+            let more = config.notifications.nag_max.is_none_or(|max| meta.nags_sent < max);
+            if !meta.will_notify && more && sub.return_time <= now {
+                events.push(meta.last_nag.unwrap_or(sub.return_time) + interval.0);
+            }
+        }
+        if let Some(after) = config.notifications.overdue_after.filter(|_| !meta.will_notify && !meta.overdue_sent) {
+            events.push(sub.return_time + after.0);
+        }
+    }
+    // Already past is handled this pass, or was missed for good
+    events.into_iter().filter(|event| *event > now).min()
+}
+
+/// For `all_home`: one push per owner, timed for when their last sub is back, and a
+/// desktop notification once it is. Subs that are already back count towards "all".
+fn notify_all_home(
+    config: &Config,
+    subs: &[SubInfo],
+    news: &FleetNews,
+    payload: &mut serde_json::Map<String, Value>,
+    templates: &template::NotificationTemplates,
+    display: &timefmt::TimeDisplay,
+) {
+    let mut owners: Vec<String> = subs.iter().map(|sub| sub.owner()).collect();
+    owners.sort();
+    owners.dedup();
+    for owner in owners {
+        let fleet: Vec<&SubInfo> = subs.iter().filter(|sub| sub.owner() == owner).collect();
+        // Sorted by return time, so the last one in is last
+        let last = fleet[fleet.len() - 1];
+        let context = template::notification_context(last, display, fleet.len() as u32);
+        let delivery = config.delivery(last);
+        let pushing = delivery.push && news.scheduled.contains(&owner) && last.return_time > Utc::now();
+        let showing =
+            delivery.desktop && news.returned.contains(&owner) && last.return_time <= Utc::now() && !news.silent;
+        if !pushing && !showing {
+            continue;
+        }
+        let Some((title, body)) = render(&templates.all_home_title, &templates.all_home_body, &context) else {
+            continue;
+        };
+        if pushing {
+            let notif = json!({"title": title, "message": body, "timestamp": last.return_time.timestamp_millis()});
+            payload.insert(format!("home-{owner}"), routed(config, last, notif, false));
+        }
+        if showing {
+            let sound = config.notifications.all_home_sound.as_deref().or(config.notifications.sound.as_deref());
+            show(&mut desktop::notification(&title, &body), sound);
+        }
+    }
+}
+
+/// Takes pushes that would land during quiet hours out of the payload, and schedules a
+/// catch-up for the end of each window instead. The catch-up covers every sub back in
+/// that window, not just the ones in this payload, since it replaces any earlier one.
+fn hold_for_quiet_hours(
+    payload: &mut serde_json::Map<String, Value>,
+    quiet: &QuietHours,
+    subs: &[SubInfo],
+    display: &timefmt::TimeDisplay,
+) {
+    let mut window_ends: Vec<DateTime<Utc>> = Vec::new();
+    payload.retain(|_, notif| {
+        match notif["timestamp"].as_i64().and_then(DateTime::from_timestamp_millis) {
+            Some(time) if quiet.contains(time) => {
+                window_ends.push(quiet.end_after(time));
+                false
+            }
+            _ => true,
+        }
+    });
+    window_ends.sort();
+    window_ends.dedup();
+    for end in window_ends {
+        let back: Vec<&SubInfo> = subs
+            .iter()
+            .filter(|sub| quiet.contains(sub.return_time) && quiet.end_after(sub.return_time) == end)
+            .collect();
+        if back.is_empty() {
+            // Only heads-ups and reminders were held, and those aren't worth catching up on
+            continue;
+        }
+        let (title, body) = quiet::catch_up_message(&back, None, display);
+        payload.insert(
+            format!("quiet-{}", end.timestamp()),
+            json!({"title": title, "message": body, "timestamp": end.timestamp_millis()}),
+        );
+    }
+}
+
+/// The config plus the time display and notification templates it defines (`args`
+/// still beating the config's display settings), so a reload with a broken template
+/// can be turned down before anything is swapped out
+fn load_daemon_config(
+    args: &LaunchArgs,
+) -> anyhow::Result<(Config, timefmt::TimeDisplay, template::NotificationTemplates)> {
+    let config = Config::load(&config_path())?;
+    let display = time_display(args, &config)?;
+    let templates = template::NotificationTemplates::new(&config.notifications, &display)?;
+    Ok((config, display, templates))
+}
+
+/// Shows `notification`, or logs why it couldn't be: like the bridge and the notifiers,
+/// one that doesn't go isn't worth stopping the daemon over
+fn show(notification: &mut Notification, sound: Option<&str>) {
+    if let Err(e) = desktop::show(notification, sound) {
+        warn!(error = format!("{e:#}"), "couldn't show desktop notification");
+    }
+}
+
+/// A notification's title and body, or None if a template asks for something that
+/// isn't there, which only loses that one notification
+fn render(title: &Template, body: &Template, context: &Value) -> Option<(String, String)> {
+    match (title.render(context), body.render(context)) {
+        (Ok(title), Ok(body)) => Some((title, body)),
+        (Err(e), _) | (_, Err(e)) => {
+            warn!(error = format!("{e:#}"), "couldn't fill in a notification template");
+            None
+        }
+    }
+}
+
+/// `notif` with `sub`'s FC's own Pushover options, which are filled in before
+/// `[pushover]`'s so they win
+fn routed(config: &Config, sub: &SubInfo, mut notif: Value, overdue: bool) -> Value {
+    let pushover = config.route(sub).and_then(|route| route.pushover.as_ref());
+    if let (Some(pushover), Some(fields)) = (pushover, notif.as_object_mut()) {
+        pushover.apply(fields, overdue);
+    }
+    notif
+}
+
+fn post_to_bridge(client: &Client, bridge: &config::BridgeConfig, payload: &Value) -> anyhow::Result<()> {
+    client
+        .post(&bridge.url)
+        .header("Authorization", format!("Bearer {}", bridge.psk))
+        .json(payload)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+fn ping_heartbeat(client: &Client, url: &str) -> anyhow::Result<()> {
+    // Short, since it holds up the loop and a slow check is as good as a missed one
+    client.get(url).timeout(Duration::from_secs(10)).send()?.error_for_status()?;
+    Ok(())
+}
+
+/// What a pass saw happen to each owner's subs, for `notify_all_home`
+struct FleetNews<'a> {
+    /// Owners with a return (re)scheduled
+    scheduled: &'a [String],
+    /// Owners with a sub that just came back
+    returned: &'a [String],
+    /// Desktop notifications are held, for quiet hours or while paused
+    silent: bool,
+}
+
+/// Announced for `sub`'s FC once it's `due`
+struct DelayedAnnouncement {
+    due: DateTime<Utc>,
+    sub: SubInfo,
+    title: String,
+    body: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotifyMeta {
+    pub submarine_id: i64,
+    pub will_notify: bool,
+    pub last_return_time: DateTime<Utc>,
+    /// Heads-ups already shown for this return, as seconds before it
+    #[serde(default)]
+    pub leads_sent: Vec<i64>,
+    /// Reminders sent since it came back without being collected
+    #[serde(default)]
+    pub nags_sent: u32,
+    #[serde(default)]
+    pub last_nag: Option<DateTime<Utc>>,
+    /// Show the return notification again at this time
+    #[serde(default)]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    /// Already told this voyage will leave it needing repairs
+    #[serde(default)]
+    pub repair_warned: bool,
+    /// Already escalated for sitting uncollected past `overdue_after`
+    #[serde(default)]
+    pub overdue_sent: bool,
+}
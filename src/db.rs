@@ -0,0 +1,608 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, TimeZone, Utc};
+use rusqlite::{Connection, ErrorCode};
+use serde::{Deserialize, Serialize};
+
+use crate::{config::SourceConfig, data_dir, json_store, remote::RemoteDb};
+
+#[cfg(target_os = "windows")]
+const SUBTRACKER_FOLDERS: &[&str] = &[r#"AppData\Roaming\XIVLauncher\pluginConfigs\SubmarineTracker"#];
+#[cfg(target_os = "linux")]
+const SUBTRACKER_FOLDERS: &[&str] = &[
+    ".xlcore/pluginConfigs/SubmarineTracker",
+    // XIVLauncher from Flathub, which is also what the Steam Deck uses
+    ".var/app/dev.goats.xivlauncher/data/xlcore/pluginConfigs/SubmarineTracker",
+];
+#[cfg(target_os = "macos")]
+const SUBTRACKER_FOLDERS: &[&str] = &["Library/Application Support/XIV on Mac/dalamud/Config/pluginConfigs/SubmarineTracker"];
+
+pub const DB_FILE_NAME: &str = "submarine-sqlite.db";
+/// Points everything at this database instead of looking in the plugin folders, for a
+/// copy of someone's database or a test fixture
+pub const DB_PATH_VAR: &str = "SUB_RETURNS_DB";
+
+/// How long SQLite itself waits on a lock held by the plugin before giving up.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(2);
+/// And how many times we have another go after that.
+const READ_ATTEMPTS: u32 = 4;
+
+/// Which of the plugin's columns a particular database has, worked out when it's read.
+/// The names are the ones SubmarineTracker writes, and the return times' are the ones
+/// this has read from the start. There's no other spelling of any of them to fall back
+/// on until a plugin release is known to rename one, which then goes here with it.
+/// Routes, builds, EXP, condition and the FC's stock are optional, read as unknown
+/// from a database without them.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    /// SQLite's `user_version`, which the plugin bumps on migrations (0 if it never has)
+    pub user_version: i64,
+    sub_id: &'static str,
+    sub_fc_id: &'static str,
+    sub_name: &'static str,
+    return_time: &'static str,
+    fc_id: &'static str,
+    fc_tag: &'static str,
+    character_name: &'static str,
+    /// Older databases don't record routes
+    route: Option<&'static str>,
+    rank: Option<&'static str>,
+    /// Hull, stern, bow and bridge
+    parts: [Option<&'static str>; 4],
+    exp: Option<&'static str>,
+    next_exp: Option<&'static str>,
+    /// Hull, stern, bow and bridge again; only used if all four are there
+    condition: [Option<&'static str>; 4],
+    tanks: Option<&'static str>,
+    kits: Option<&'static str>,
+}
+
+impl Schema {
+    pub fn detect(db: &Connection) -> anyhow::Result<Schema> {
+        let user_version: i64 = db.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        let submarine_columns = table_columns(db, "submarine")?;
+        let freecompany_columns = table_columns(db, "freecompany")?;
+        if submarine_columns.is_empty() || freecompany_columns.is_empty() {
+            bail!("This doesn't look like a SubmarineTracker database (no submarine/freecompany tables)");
+        }
+
+        let mut missing = Vec::new();
+        let mut pick = |table: &str, columns: &[String], name: &'static str| {
+            if !columns.iter().any(|c| c == name) {
+                missing.push(format!("{table}.{name}"));
+            }
+            name
+        };
+        let optional =
+            |columns: &[String], name: &'static str| columns.iter().any(|c| c == name).then_some(name);
+        let schema = Schema {
+            user_version,
+            sub_id: pick("submarine", &submarine_columns, "SubmarineId"),
+            sub_fc_id: pick("submarine", &submarine_columns, "FreeCompanyId"),
+            sub_name: pick("submarine", &submarine_columns, "Name"),
+            return_time: pick("submarine", &submarine_columns, "Return"),
+            fc_id: pick("freecompany", &freecompany_columns, "FreeCompanyId"),
+            fc_tag: pick("freecompany", &freecompany_columns, "FreeCompanyTag"),
+            character_name: pick("freecompany", &freecompany_columns, "CharacterName"),
+            route: optional(&submarine_columns, "Points"),
+            rank: optional(&submarine_columns, "Rank"),
+            parts: [
+                optional(&submarine_columns, "Hull"),
+                optional(&submarine_columns, "Stern"),
+                optional(&submarine_columns, "Bow"),
+                optional(&submarine_columns, "Bridge"),
+            ],
+            exp: optional(&submarine_columns, "CExp"),
+            next_exp: optional(&submarine_columns, "NExp"),
+            condition: [
+                optional(&submarine_columns, "HullDurability"),
+                optional(&submarine_columns, "SternDurability"),
+                optional(&submarine_columns, "BowDurability"),
+                optional(&submarine_columns, "BridgeDurability"),
+            ],
+            tanks: optional(&freecompany_columns, "Tanks"),
+            kits: optional(&freecompany_columns, "Kits"),
+        };
+        if !missing.is_empty() {
+            bail!(
+                "Unsupported SubmarineTracker database schema (version {user_version}, missing {}), please update sub-returns",
+                missing.join(", ")
+            );
+        }
+        Ok(schema)
+    }
+
+    /// Sets one sub's return time; parameters are (unix seconds, sub id).
+    pub fn update_return_sql(&self) -> String {
+        format!(
+            "UPDATE submarine SET {return_time} = ?1 WHERE {sub_id} = ?2",
+            return_time = self.return_time,
+            sub_id = self.sub_id,
+        )
+    }
+
+    fn submarine_query(&self) -> String {
+        format!(
+            "
+    SELECT
+        submarine.{sub_id} AS id,
+        submarine.{sub_name} AS name,
+        submarine.{return_time} AS return_time,
+        freecompany.{fc_tag} AS tag,
+        freecompany.{character_name} AS character_name,
+        {route} AS route,
+        {rank} AS rank,
+        {hull} AS hull,
+        {stern} AS stern,
+        {bow} AS bow,
+        {bridge} AS bridge,
+        {exp} AS exp,
+        {next_exp} AS next_exp,
+        {condition} AS condition,
+        {tanks} AS tanks,
+        {kits} AS kits
+    FROM submarine
+    JOIN freecompany
+    ON submarine.{sub_fc_id} = freecompany.{fc_id}
+    ORDER BY return_time ASC
+    ",
+            sub_id = self.sub_id,
+            sub_name = self.sub_name,
+            return_time = self.return_time,
+            fc_tag = self.fc_tag,
+            character_name = self.character_name,
+            sub_fc_id = self.sub_fc_id,
+            fc_id = self.fc_id,
+            route = self.route.map_or("NULL".to_string(), |route| format!("submarine.{route}")),
+            rank = number(self.rank),
+            hull = number(self.parts[0]),
+            stern = number(self.parts[1]),
+            bow = number(self.parts[2]),
+            bridge = number(self.parts[3]),
+            exp = number(self.exp),
+            next_exp = number(self.next_exp),
+            // One column, so a missing part reads as a missing whole
+            condition = match self.condition {
+                [Some(hull), Some(stern), Some(bow), Some(bridge)] => format!(
+                    "submarine.{hull} || ',' || submarine.{stern} || ',' || submarine.{bow} || ',' || submarine.{bridge}"
+                ),
+                _ => "NULL".to_string(),
+            },
+            tanks = self.tanks.map_or("NULL".to_string(), |tanks| format!("freecompany.{tanks}")),
+            kits = self.kits.map_or("NULL".to_string(), |kits| format!("freecompany.{kits}")),
+        )
+    }
+}
+
+/// An optional numeric column, reading as 0 when it's missing or null
+fn number(column: Option<&str>) -> String {
+    column.map_or("0".to_string(), |column| format!("COALESCE(submarine.{column}, 0)"))
+}
+
+/// Column names of `table`, empty if it doesn't exist.
+pub fn table_columns(db: &Connection, table: &str) -> anyhow::Result<Vec<String>> {
+    let mut stmt = db.prepare_cached("SELECT name FROM pragma_table_info(?1)")?;
+    let columns = stmt
+        .query_map([table], |row| row.get(0))?
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(columns)
+}
+
+/// Every place the plugin's folder is known to live on this platform, most likely first.
+pub fn candidate_plugin_folders() -> Vec<PathBuf> {
+    let Some(user_dirs) = directories::UserDirs::new() else {
+        return Vec::new();
+    };
+    SUBTRACKER_FOLDERS
+        .iter()
+        .map(|folder| user_dirs.home_dir().join(folder))
+        .collect()
+}
+
+/// The first candidate that actually has a database in it, falling back to the most
+/// likely location so error messages point somewhere sensible. [`DB_PATH_VAR`] beats
+/// all of them.
+pub fn find_db() -> Option<PathBuf> {
+    if let Some(path) = env::var_os(DB_PATH_VAR) {
+        return Some(PathBuf::from(path));
+    }
+    let candidates: Vec<PathBuf> = candidate_plugin_folders()
+        .into_iter()
+        .map(|folder| folder.join(DB_FILE_NAME))
+        .collect();
+    candidates
+        .iter()
+        .find(|path| path.is_file())
+        .or(candidates.first())
+        .cloned()
+}
+
+/// The latest snapshot from each agent, shared between the collector's HTTP thread
+/// and the daemon loop reading it as a source.
+pub type Snapshots = Arc<Mutex<HashMap<String, Vec<SubInfo>>>>;
+
+/// Whether `folder` has per-FC JSON files, from plugin releases before the database,
+/// to read subs from
+pub fn has_json_files(folder: &Path) -> bool {
+    json_store::has_fc_files(folder)
+}
+
+/// Where submarine data is read from: the plugin's sqlite database, or failing that
+/// the JSON files older versions of the plugin wrote.
+pub struct SubSource {
+    /// Only set when several sources are configured, to tell their subs apart
+    pub label: Option<String>,
+    kind: SourceKind,
+    /// What the database was last found to look like, with its `schema_version` then,
+    /// which SQLite bumps on any change to the tables
+    schema: RefCell<Option<(i64, Schema)>>,
+}
+
+enum SourceKind {
+    Sqlite(Connection),
+    Json(PathBuf),
+    Remote(RefCell<RemoteDb>),
+    Collector(Snapshots),
+}
+
+impl SubSource {
+    /// Whichever plugin folder on this machine has data in it.
+    pub fn open() -> anyhow::Result<SubSource> {
+        if env::var_os(DB_PATH_VAR).is_none() && !find_db().is_some_and(|path| path.is_file()) {
+            let json_folder = candidate_plugin_folders()
+                .into_iter()
+                .find(|folder| json_store::has_fc_files(folder));
+            if let Some(folder) = json_folder {
+                return Ok(SubSource::new(None, SourceKind::Json(folder)));
+            }
+        }
+        Ok(SubSource::new(None, SourceKind::Sqlite(open_db(None)?)))
+    }
+
+    /// `path` can be the plugin folder or the database inside it.
+    pub fn open_path(path: &Path, label: Option<String>) -> anyhow::Result<SubSource> {
+        if let Some(url) = path.to_str().filter(|path| RemoteDb::is_remote(path)) {
+            let cache_name = format!("{}.db", cache_name(label.as_deref().unwrap_or("remote")));
+            let remote = RemoteDb::new(url, data_dir().join("remote").join(cache_name))?;
+            return Ok(SubSource::new(label, SourceKind::Remote(RefCell::new(remote))));
+        }
+        let db_path = if path.is_dir() { path.join(DB_FILE_NAME) } else { path.to_path_buf() };
+        let kind = if !db_path.is_file() && path.is_dir() && json_store::has_fc_files(path) {
+            SourceKind::Json(path.to_path_buf())
+        } else {
+            SourceKind::Sqlite(open_db_at(&db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?)
+        };
+        Ok(SubSource::new(label, kind))
+    }
+
+    /// A database that's already open, such as an in-memory one
+    pub fn from_connection(db: Connection, label: Option<String>) -> SubSource {
+        SubSource::new(label, SourceKind::Sqlite(db))
+    }
+
+    /// Subs sent in by agents; each agent's name labels its subs.
+    pub fn collector(snapshots: Snapshots) -> SubSource {
+        SubSource::new(None, SourceKind::Collector(snapshots))
+    }
+
+    fn new(label: Option<String>, kind: SourceKind) -> SubSource {
+        SubSource { label, kind, schema: RefCell::default() }
+    }
+
+    /// `db`'s schema, worked out again only when its tables have changed, since the
+    /// daemon reads every time the plugin writes
+    fn schema(&self, db: &Connection) -> anyhow::Result<Schema> {
+        let version: i64 = db.query_row("PRAGMA schema_version", [], |row| row.get(0))?;
+        if let Some((seen, schema)) = &*self.schema.borrow() {
+            if *seen == version {
+                return Ok(schema.clone());
+            }
+        }
+        let schema = Schema::detect(db)?;
+        *self.schema.borrow_mut() = Some((version, schema.clone()));
+        Ok(schema)
+    }
+
+    /// Runs `read` on the source's sqlite database, `None` for sources without one
+    /// (JSON files and collectors only have return times).
+    pub fn with_sqlite<T>(&self, read: impl FnOnce(&Connection) -> anyhow::Result<T>) -> anyhow::Result<Option<T>> {
+        match &self.kind {
+            SourceKind::Sqlite(db) => read(db).map(Some),
+            SourceKind::Remote(remote) => read(remote.borrow_mut().connection()?).map(Some),
+            SourceKind::Json(_) | SourceKind::Collector(_) => Ok(None),
+        }
+    }
+
+    /// Closes the database, reporting what a plain drop would swallow.
+    pub fn close(self) -> anyhow::Result<()> {
+        if let SourceKind::Sqlite(db) = self.kind {
+            db.close().map_err(|(_, e)| e)?;
+        }
+        Ok(())
+    }
+}
+
+/// Everything listed under `[[sources]]`, or the auto-detected plugin folder if the
+/// config doesn't list any.
+pub fn open_sources(sources: &[SourceConfig]) -> anyhow::Result<Vec<SubSource>> {
+    if sources.is_empty() {
+        return Ok(vec![SubSource::open()?]);
+    }
+    sources
+        .iter()
+        .map(|source| {
+            SubSource::open_path(&source.path, Some(source.label.clone()))
+                .with_context(|| format!("Couldn't open the '{}' source", source.label))
+        })
+        .collect()
+}
+
+/// `label` made safe to name a file with: letters, digits and underscores are kept and
+/// any other byte is written as `-` and its hex, so no two labels end up with one file
+fn cache_name(label: &str) -> String {
+    label
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'_' => char::from(byte).to_string(),
+            _ => format!("-{byte:02x}"),
+        })
+        .collect()
+}
+
+/// The local sqlite databases behind `sources`, for commands that write to them.
+pub fn db_paths(sources: &[SourceConfig]) -> Vec<PathBuf> {
+    local_dbs(sources).into_iter().map(|(_, path)| path).collect()
+}
+
+/// The same with each source's label, none for the auto-detected one
+pub fn local_dbs(sources: &[SourceConfig]) -> Vec<(Option<String>, PathBuf)> {
+    if sources.is_empty() {
+        return find_db().into_iter().map(|path| (None, path)).collect();
+    }
+    sources
+        .iter()
+        .filter(|source| !source.path.to_str().is_some_and(RemoteDb::is_remote))
+        .map(|source| {
+            let path = if source.path.is_dir() { source.path.join(DB_FILE_NAME) } else { source.path.clone() };
+            (Some(source.label.clone()), path)
+        })
+        .collect()
+}
+
+/// Modification times of each database and its write-ahead log, which is where the
+/// plugin's writes land first
+pub fn modified_times(db_paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    db_paths
+        .iter()
+        .flat_map(|path| {
+            let mut wal = path.clone().into_os_string();
+            wal.push("-wal");
+            [path.clone(), PathBuf::from(wal)]
+        })
+        .map(|path| fs::metadata(path).and_then(|m| m.modified()).ok())
+        .collect()
+}
+
+/// The auto-detected database, read-only unless `flags` says otherwise.
+pub fn open_db(flags: Option<rusqlite::OpenFlags>) -> anyhow::Result<Connection> {
+    let sub_db_file = find_db().context("Couldn't find your home directory")?;
+    open_db_at(&sub_db_file, flags.unwrap_or(rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY))
+}
+
+/// There's no database where one was expected, told apart from other failures so the
+/// exit code can say so
+#[derive(Debug)]
+pub struct DbNotFound(pub PathBuf);
+
+impl fmt::Display for DbNotFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No SubmarineTracker database at {}", self.0.display())
+    }
+}
+
+impl std::error::Error for DbNotFound {}
+
+/// The database at `sub_db_file`, failing with [`DbNotFound`] if there isn't one and
+/// `flags` don't ask for it to be created.
+pub fn open_db_at(sub_db_file: &Path, flags: rusqlite::OpenFlags) -> anyhow::Result<Connection> {
+    if !flags.contains(rusqlite::OpenFlags::SQLITE_OPEN_CREATE) && !sub_db_file.exists() {
+        return Err(DbNotFound(sub_db_file.to_path_buf()).into());
+    }
+    let db = Connection::open_with_flags(sub_db_file, flags)
+        .with_context(|| format!("Couldn't open {}", sub_db_file.display()))?;
+    db.busy_timeout(BUSY_TIMEOUT)?;
+    Ok(db)
+}
+
+/// Errors that go away once SubmarineTracker finishes whatever write it's in the
+/// middle of. `SQLITE_READONLY_RECOVERY` is what a read-only WAL reader sees while the
+/// plugin's connection is recovering the log after the game crashed.
+fn is_transient(error: &rusqlite::Error) -> bool {
+    const SQLITE_READONLY_RECOVERY: std::ffi::c_int = 264;
+    match error {
+        rusqlite::Error::SqliteFailure(e, _) => {
+            matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+                || e.extended_code == SQLITE_READONLY_RECOVERY
+        }
+        _ => false,
+    }
+}
+
+/// Every sub in `source`, in whatever order it keeps them.
+pub fn get_submarine_info(source: &SubSource) -> anyhow::Result<Vec<SubInfo>> {
+    let mut subs = match &source.kind {
+        SourceKind::Sqlite(db) => read_with_schema(db, &source.schema(db)?)?,
+        SourceKind::Json(folder) => json_store::read_folder(folder)?,
+        SourceKind::Remote(remote) => {
+            let mut remote = remote.borrow_mut();
+            let db = remote.connection()?;
+            read_with_schema(db, &source.schema(db)?)?
+        }
+        SourceKind::Collector(snapshots) => {
+            // A panic mid-insert leaves the map as it was, so it's still good to read
+            let snapshots = snapshots.lock().unwrap_or_else(PoisonError::into_inner);
+            let mut subs = Vec::new();
+            for (agent, agent_subs) in snapshots.iter() {
+                subs.extend(agent_subs.iter().cloned().map(|mut sub| {
+                    sub.source = Some(agent.clone());
+                    sub
+                }));
+            }
+            return Ok(subs);
+        }
+    };
+    for sub in &mut subs {
+        sub.source.clone_from(&source.label);
+    }
+    Ok(subs)
+}
+
+/// Subs from every source merged into one list, soonest return first.
+pub fn get_all_submarine_info(sources: &[SubSource]) -> anyhow::Result<Vec<SubInfo>> {
+    let mut subs = Vec::new();
+    for source in sources {
+        subs.extend(get_submarine_info(source)?);
+    }
+    subs.sort_by_key(|sub| sub.return_time);
+    Ok(subs)
+}
+
+/// Subs from an open SubmarineTracker database of any schema version it knows,
+/// retrying for a moment while the plugin holds a lock.
+pub fn read_sqlite(db: &Connection) -> anyhow::Result<Vec<SubInfo>> {
+    read_with_schema(db, &Schema::detect(db)?)
+}
+
+fn read_with_schema(db: &Connection, schema: &Schema) -> anyhow::Result<Vec<SubInfo>> {
+    let mut attempt = 1;
+    loop {
+        match query_submarine_info(db, schema) {
+            Err(e) if attempt < READ_ATTEMPTS && is_transient(&e) => {
+                tracing::debug!(attempt, error = %e, "database busy, retrying");
+                thread::sleep(Duration::from_millis(250) * attempt);
+                attempt += 1;
+            }
+            result => return result.context("Couldn't read submarines from the database"),
+        }
+    }
+}
+
+fn query_submarine_info(db: &Connection, schema: &Schema) -> rusqlite::Result<Vec<SubInfo>> {
+    // The daemon reads with the same query every time the database changes
+    let mut stmt = db.prepare_cached(&schema.submarine_query())?;
+    let rows = stmt
+        .query_map([], |row| {
+            let timestamp: i64 = row.get(2)?;
+            Ok(SubInfo {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                return_time: Utc.timestamp_opt(timestamp, 0).single().unwrap(),
+                tag: row.get(3)?,
+                character_name: row.get(4)?,
+                source: None,
+                route: parse_route(row.get::<_, Option<String>>(5)?.as_deref().unwrap_or_default()),
+                rank: row.get(6)?,
+                parts: [row.get(7)?, row.get(8)?, row.get(9)?, row.get(10)?],
+                exp: row.get(11)?,
+                next_exp: row.get(12)?,
+                condition: row.get::<_, Option<String>>(13)?.and_then(|stored| parse_condition(&stored)),
+                tanks: row.get(14)?,
+                kits: row.get(15)?,
+                estimated: false,
+            })
+        })?;
+    let mut subs = Vec::new();
+    for row in rows {
+        match row {
+            Ok(sub) => subs.push(sub),
+            // A lock mid-read would otherwise look like a short list of subs
+            Err(e) if is_transient(&e) => return Err(e),
+            Err(_) => {}
+        }
+    }
+    Ok(subs)
+}
+
+/// Sector ids from however the plugin stored them, a JSON array or a list of numbers
+pub fn parse_route(stored: &str) -> Vec<u32> {
+    stored
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|sector| sector.parse().ok())
+        .collect()
+}
+
+/// The four parts' condition from `hull,stern,bow,bridge`
+fn parse_condition(stored: &str) -> Option<[u32; 4]> {
+    let parts: Vec<u32> = stored.split(',').map(|part| part.trim().parse().ok()).collect::<Option<_>>()?;
+    parts.try_into().ok()
+}
+
+/// One submarine and where it's at, as any source reports it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SubInfo {
+    /// The plugin's id for it, only unique within one source
+    pub id: i64,
+    pub name: String,
+    pub return_time: DateTime<Utc>,
+    /// The tag of the FC it belongs to
+    pub tag: String,
+    /// The character the plugin last saw the FC's subs from
+    pub character_name: String,
+    /// Label of the source this came from, when more than one is configured
+    pub source: Option<String>,
+    /// Sector ids of the voyage it's on, in order
+    #[serde(default)]
+    pub route: Vec<u32>,
+    /// 0 if the database doesn't say
+    #[serde(default)]
+    pub rank: u32,
+    /// Part ids of the hull, stern, bow and bridge, 0 for any it doesn't say
+    #[serde(default)]
+    pub parts: [u32; 4],
+    /// EXP into the current rank, and what the next one takes
+    #[serde(default)]
+    pub exp: u32,
+    #[serde(default)]
+    pub next_exp: u32,
+    /// Condition of the hull, stern, bow and bridge as the game stores it, where 30000
+    /// is 100%. None if the database doesn't say.
+    #[serde(default)]
+    pub condition: Option<[u32; 4]>,
+    /// The FC's ceruleum tanks and repair kits, the same on all of its subs
+    #[serde(default)]
+    pub tanks: Option<u32>,
+    #[serde(default)]
+    pub kits: Option<u32>,
+    /// `return_time` was worked out from the route, the database's was missing or stale
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+impl SubInfo {
+    /// Unique across sources, unlike `id`. Unlabelled subs keep the bare id so state
+    /// saved before sources existed still matches up.
+    pub fn key(&self) -> String {
+        match &self.source {
+            Some(label) => format!("{label}/{id}", id = self.id),
+            None => self.id.to_string(),
+        }
+    }
+
+    /// Who the sub belongs to, e.g. `Alpha Beta «ABC»` or `Alpha Beta «ABC» @ Alt`.
+    pub fn owner(&self) -> String {
+        let owner = format!("{char_name} «{tag}»", char_name = self.character_name, tag = self.tag);
+        match &self.source {
+            Some(label) => format!("{owner} @ {label}"),
+            None => owner,
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use zbus::{
+    blocking::{connection, Connection},
+    fdo::ObjectManager,
+    interface,
+    object_server::SignalEmitter,
+    zvariant::Value,
+};
+
+use crate::db::SubInfo;
+
+pub const NAME: &str = "com.submarinereturns";
+const PATH: &str = "/com/submarinereturns";
+const DAEMON_INTERFACE: &str = "com.submarinereturns.Daemon";
+const SUB_INTERFACE: &str = "com.submarinereturns.Sub";
+
+/// The root object: what's back next, and a signal as each sub comes in
+struct Daemon {
+    next_return: i64,
+    next_sub: String,
+}
+
+#[interface(name = "com.submarinereturns.Daemon")]
+impl Daemon {
+    /// Unix time of the next return, 0 with nothing out
+    #[zbus(property)]
+    fn next_return(&self) -> i64 {
+        self.next_return
+    }
+
+    /// Name of the sub that's back next, empty with nothing out
+    #[zbus(property)]
+    fn next_sub(&self) -> &str {
+        &self.next_sub
+    }
+
+    #[zbus(signal)]
+    async fn returned(emitter: &SignalEmitter<'_>, sub: &str, owner: &str, return_time: i64) -> zbus::Result<()>;
+}
+
+/// One sub, at `/com/submarinereturns/subs/<key>`
+#[derive(Clone, PartialEq)]
+struct Sub {
+    id: i64,
+    name: String,
+    owner: String,
+    return_time: i64,
+    returned: bool,
+}
+
+#[interface(name = "com.submarinereturns.Sub")]
+impl Sub {
+    #[zbus(property)]
+    fn id(&self) -> i64 {
+        self.id
+    }
+
+    #[zbus(property)]
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    #[zbus(property)]
+    fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    /// Unix time it's back, or was
+    #[zbus(property)]
+    fn return_time(&self) -> i64 {
+        self.return_time
+    }
+
+    #[zbus(property)]
+    fn returned(&self) -> bool {
+        self.returned
+    }
+}
+
+/// `com.submarinereturns` on the session bus, kept in step with the daemon's subs so
+/// desktop widgets can show them without polling anything. The subs are listed
+/// through the standard ObjectManager at the root.
+pub struct Bus {
+    connection: Connection,
+    next: (i64, String),
+    /// What's been published, by object path
+    subs: HashMap<String, Sub>,
+}
+
+impl Bus {
+    pub fn start() -> anyhow::Result<Self> {
+        let connection = connection::Builder::session()?
+            .name(NAME)?
+            .serve_at(PATH, Daemon { next_return: 0, next_sub: String::new() })?
+            .serve_at(PATH, ObjectManager)?
+            .build()?;
+        Ok(Bus { connection, next: (0, String::new()), subs: HashMap::new() })
+    }
+
+    /// Publishes `subs` as they are at `now`, sending the property changes, objects
+    /// added and removed, and a `Returned` for each that's come in since last time
+    pub fn update(&mut self, subs: &[SubInfo], now: DateTime<Utc>) -> anyhow::Result<()> {
+        let server = self.connection.object_server();
+        let mut current = HashMap::new();
+        for sub in subs {
+            let path = format!("{PATH}/subs/{}", escape(&sub.key()));
+            let published = Sub {
+                id: sub.id,
+                name: sub.name.clone(),
+                owner: sub.owner(),
+                return_time: sub.return_time.timestamp(),
+                returned: sub.return_time <= now,
+            };
+            match self.subs.get(&path) {
+                None => {
+                    server.at(path.as_str(), published.clone())?;
+                }
+                Some(old) if *old != published => {
+                    if published.returned && !old.returned {
+                        self.connection.emit_signal(
+                            None::<&str>,
+                            PATH,
+                            DAEMON_INTERFACE,
+                            "Returned",
+                            &(&published.name, &published.owner, published.return_time),
+                        )?;
+                    }
+                    *server.interface::<_, Sub>(path.as_str())?.get_mut() = published.clone();
+                    self.properties_changed(
+                        &path,
+                        SUB_INTERFACE,
+                        HashMap::from([
+                            ("Name", Value::from(published.name.as_str())),
+                            ("Owner", Value::from(published.owner.as_str())),
+                            ("ReturnTime", Value::from(published.return_time)),
+                            ("Returned", Value::from(published.returned)),
+                        ]),
+                    )?;
+                }
+                Some(_) => {}
+            }
+            current.insert(path, published);
+        }
+        for path in self.subs.keys().filter(|path| !current.contains_key(*path)) {
+            server.remove::<Sub, _>(path.as_str())?;
+        }
+        self.subs = current;
+
+        let next = subs
+            .iter()
+            .filter(|sub| sub.return_time > now)
+            .min_by_key(|sub| sub.return_time)
+            .map_or((0, String::new()), |sub| (sub.return_time.timestamp(), sub.name.clone()));
+        if next != self.next {
+            {
+                let daemon = server.interface::<_, Daemon>(PATH)?;
+                let mut daemon = daemon.get_mut();
+                daemon.next_return = next.0;
+                daemon.next_sub = next.1.clone();
+            }
+            self.properties_changed(
+                PATH,
+                DAEMON_INTERFACE,
+                HashMap::from([("NextReturn", Value::from(next.0)), ("NextSub", Value::from(next.1.as_str()))]),
+            )?;
+            self.next = next;
+        }
+        Ok(())
+    }
+
+    fn properties_changed(&self, path: &str, interface: &str, changed: HashMap<&str, Value>) -> anyhow::Result<()> {
+        self.connection.emit_signal(
+            None::<&str>,
+            path,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            &(interface, changed, Vec::<&str>::new()),
+        )?;
+        Ok(())
+    }
+}
+
+/// A sub's key as an object path element, which can only have letters, digits and
+/// underscores: anything else becomes `_` and its hex, like systemd does
+fn escape(key: &str) -> String {
+    key.bytes()
+        .map(|byte| match byte {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' => (byte as char).to_string(),
+            _ => format!("_{byte:02x}"),
+        })
+        .collect()
+}
@@ -0,0 +1,232 @@
+#[cfg(target_os = "linux")]
+use std::sync::{Mutex, Once, PoisonError};
+use std::{sync::mpsc::Sender, thread};
+
+use notify_rust::Notification;
+use tracing::debug;
+
+use crate::{config::NotificationConfig, db::SubInfo};
+
+/// The AppUserModelID toasts are sent as, registered by `register_app` so Windows
+/// shows them under this app's name and keeps them in the Action Center
+#[cfg(windows)]
+const APP_ID: &str = "TyroneSudeium.SubReturns";
+
+/// A notification with everything but the text filled in
+pub fn notification(summary: &str, body: &str) -> Notification {
+    let mut notification = Notification::new();
+    notification.summary(summary).body(body).icon("dialog-information");
+    #[cfg(windows)]
+    notification.app_id(APP_ID);
+    notification
+}
+
+/// Gets the platform ready to show notifications. Called once at startup, since
+/// neither registration can be redone while running.
+pub fn init(config: &NotificationConfig) -> anyhow::Result<()> {
+    #[cfg(windows)]
+    register_app_id()?;
+    #[cfg(target_os = "macos")]
+    if let Some(app) = &config.macos_app {
+        notify_rust::set_application(app).map_err(|e| anyhow::anyhow!("Couldn't send notifications as {app}: {e:?}"))?;
+        NATIVE.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    #[cfg(not(target_os = "macos"))]
+    let _ = config;
+    Ok(())
+}
+
+/// Without this Windows doesn't know the app id, and toasts from an unknown one are
+/// dropped. It's one value under HKCU, so doing it every start is fine.
+#[cfg(windows)]
+fn register_app_id() -> anyhow::Result<()> {
+    use std::{os::windows::process::CommandExt, process::Command};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let key = format!(r"HKCU\Software\Classes\AppUserModelId\{APP_ID}");
+    let status = Command::new("reg")
+        .args(["add", &key, "/v", "DisplayName", "/t", "REG_SZ", "/f", "/d", "Submarine Returns"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("reg add {key} failed");
+    }
+    Ok(())
+}
+
+/// Whether macOS notifications go through Notification Center as `macos_app`. An
+/// unbundled binary (which this is) otherwise gets nothing shown at all.
+#[cfg(target_os = "macos")]
+static NATIVE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Shows `notification` with `sound`, a name from the platform's sound theme (or
+/// `none` for silence). Left out, the platform decides.
+pub fn show(notification: &mut Notification, sound: Option<&str>) -> anyhow::Result<()> {
+    #[cfg(target_os = "macos")]
+    if !NATIVE.load(std::sync::atomic::Ordering::Relaxed) {
+        return show_with_osascript(notification, sound);
+    }
+    set_sound(notification, sound);
+    notification.show()?;
+    Ok(())
+}
+
+fn set_sound(notification: &mut Notification, sound: Option<&str>) {
+    match sound {
+        None => {}
+        // Toasts and Notification Center are already silent without a sound
+        #[cfg(all(unix, not(target_os = "macos")))]
+        Some(name) if name.eq_ignore_ascii_case("none") => {
+            notification.hint(notify_rust::Hint::SuppressSound(true));
+        }
+        #[cfg(not(all(unix, not(target_os = "macos"))))]
+        Some(name) if name.eq_ignore_ascii_case("none") => {}
+        Some(name) => {
+            notification.sound_name(name);
+        }
+    }
+}
+
+/// `display notification` always works, it just shows up as Script Editor
+#[cfg(target_os = "macos")]
+fn show_with_osascript(notification: &Notification, sound: Option<&str>) -> anyhow::Result<()> {
+    let script = match sound {
+        Some(name) if !name.eq_ignore_ascii_case("none") => {
+            "display notification (item 2 of argv) with title (item 1 of argv) sound name (item 3 of argv)"
+        }
+        _ => "display notification (item 2 of argv) with title (item 1 of argv)",
+    };
+    // Passed as arguments so nothing in the text needs AppleScript escaping
+    let status = std::process::Command::new("osascript")
+        .args(["-e", "on run argv", "-e", script, "-e", "end run", "--"])
+        .args([&notification.summary, &notification.body, sound.unwrap_or_default()])
+        .status()?;
+    if !status.success() {
+        anyhow::bail!("osascript couldn't show the notification");
+    }
+    Ok(())
+}
+
+/// Something picked from the buttons on a return notification, handed back to the
+/// daemon loop
+pub enum Action {
+    /// Remind again in an hour, by sub key
+    Snooze(Vec<String>),
+    /// Never notify about this sub again
+    Mute(SubInfo),
+}
+
+/// Shows a return notification for `subs` with snooze and mute buttons, and sends
+/// whatever's picked to `actions`. On Linux one thread watches the bus for every
+/// notification's buttons, since a notification can sit unanswered in the history for
+/// good; Windows says when a toast goes away, so there each one has a thread that
+/// waits on it.
+pub fn show_return(
+    notification: &mut Notification,
+    sound: Option<&str>,
+    subs: &[&SubInfo],
+    actions: &Sender<Action>,
+) -> anyhow::Result<()> {
+    notification.action("snooze", "Snooze 1h");
+    // Muting a whole group from one button would be a surprise
+    let single = match subs {
+        [sub] => Some((*sub).clone()),
+        _ => None,
+    };
+    if single.is_some() {
+        notification.action("mute", "Mute this sub");
+    }
+    // No buttons without Notification Center
+    #[cfg(target_os = "macos")]
+    if !NATIVE.load(std::sync::atomic::Ordering::Relaxed) {
+        return show_with_osascript(notification, sound);
+    }
+    set_sound(notification, sound);
+    let keys: Vec<String> = subs.iter().map(|sub| sub.key()).collect();
+    #[cfg(target_os = "linux")]
+    {
+        WATCHING.call_once(|| {
+            thread::spawn(|| {
+                if let Err(e) = watch_actions() {
+                    tracing::warn!(error = format!("{e:#}"), "stopped watching for notification buttons");
+                }
+            });
+        });
+        let id = notification.show()?.id();
+        let shown = Shown { id, keys, single, actions: actions.clone() };
+        SHOWN.lock().unwrap_or_else(PoisonError::into_inner).push(shown);
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let handle = notification.show()?;
+        let actions = actions.clone();
+        thread::spawn(move || {
+            handle.wait_for_action(|action| {
+                debug!(action, "notification action");
+                let picked = match (action, single) {
+                    ("snooze", _) => Action::Snooze(keys),
+                    ("mute", Some(sub)) => Action::Mute(sub),
+                    _ => return,
+                };
+                // The daemon going away first is fine
+                let _ = actions.send(picked);
+            });
+        });
+    }
+    Ok(())
+}
+
+/// A return notification that's still up, and what its buttons are for
+#[cfg(target_os = "linux")]
+struct Shown {
+    id: u32,
+    keys: Vec<String>,
+    single: Option<SubInfo>,
+    actions: Sender<Action>,
+}
+
+#[cfg(target_os = "linux")]
+static SHOWN: Mutex<Vec<Shown>> = Mutex::new(Vec::new());
+#[cfg(target_os = "linux")]
+static WATCHING: Once = Once::new();
+
+/// Picks buttons off the bus for `SHOWN`'s notifications, and forgets each one once
+/// it's been answered or closed
+#[cfg(target_os = "linux")]
+fn watch_actions() -> anyhow::Result<()> {
+    let connection = zbus::blocking::Connection::session()?;
+    let rule = zbus::MatchRule::builder()
+        .msg_type(zbus::message::Type::Signal)
+        .interface("org.freedesktop.Notifications")?
+        .build();
+    for message in zbus::blocking::MessageIterator::for_match_rule(rule, &connection, None)? {
+        let message = message?;
+        let header = message.header();
+        // Anything else sent on the interface isn't ours to make sense of
+        let (id, action) = match header.member().map(|member| member.as_str()) {
+            Some("ActionInvoked") => match message.body().deserialize::<(u32, String)>() {
+                Ok((id, action)) => (id, Some(action)),
+                Err(_) => continue,
+            },
+            Some("NotificationClosed") => match message.body().deserialize::<(u32, u32)>() {
+                Ok((id, _reason)) => (id, None),
+                Err(_) => continue,
+            },
+            _ => continue,
+        };
+        let mut shown = SHOWN.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(index) = shown.iter().position(|shown| shown.id == id) else {
+            continue;
+        };
+        let Shown { keys, single, actions, .. } = shown.swap_remove(index);
+        debug!(action, "notification action");
+        let picked = match (action.as_deref(), single) {
+            (Some("snooze"), _) => Action::Snooze(keys),
+            (Some("mute"), Some(sub)) => Action::Mute(sub),
+            _ => continue,
+        };
+        // The daemon going away first is fine
+        let _ = actions.send(picked);
+    }
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! What changed between two reads of the databases, as the plugin sees the game
+
+use chrono::{DateTime, Utc};
+
+use crate::db::SubInfo;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    /// A character or FC that wasn't in any database before, by `SubInfo::owner`
+    NewOwner(String),
+    OwnerGone(String),
+    /// Registered, or turned up with a new character
+    NewSub(SubInfo),
+    Gone(SubInfo),
+    /// Renamed in the game, with its old name
+    Renamed { sub: SubInfo, from: String },
+    /// Collected and sent out again, with when it was back from the last voyage
+    Dispatched { sub: SubInfo, previous: DateTime<Utc> },
+    /// A different return for a voyage that's still out, with the old one: `--update`,
+    /// or the plugin putting right a time it had wrong
+    Retimed { sub: SubInfo, previous: DateTime<Utc> },
+}
+
+/// Everything about `new` that's different from `old` at `now`, owners first and then
+/// subs in `new`'s order. Subs that come back in between aren't a change to the
+/// database, so aren't here.
+pub fn changes(old: &[SubInfo], new: &[SubInfo], now: DateTime<Utc>) -> Vec<Change> {
+    let mut changes = Vec::new();
+    let owners = |subs: &[SubInfo]| {
+        let mut owners: Vec<String> = Vec::new();
+        for sub in subs {
+            if !owners.contains(&sub.owner()) {
+                owners.push(sub.owner());
+            }
+        }
+        owners
+    };
+    let (old_owners, new_owners) = (owners(old), owners(new));
+    changes.extend(new_owners.iter().filter(|owner| !old_owners.contains(owner)).cloned().map(Change::NewOwner));
+    changes.extend(old_owners.iter().filter(|owner| !new_owners.contains(owner)).cloned().map(Change::OwnerGone));
+
+    for sub in new {
+        let Some(before) = old.iter().find(|before| before.key() == sub.key()) else {
+            changes.push(Change::NewSub(sub.clone()));
+            continue;
+        };
+        if before.name != sub.name {
+            changes.push(Change::Renamed { sub: sub.clone(), from: before.name.clone() });
+        }
+        if before.return_time == sub.return_time {
+            continue;
+        }
+        let previous = before.return_time;
+        // A sub still out can't be sent anywhere, so a later return on one that was
+        // back is a new voyage and anything else is the same one
+        if previous <= now && sub.return_time > now {
+            changes.push(Change::Dispatched { sub: sub.clone(), previous });
+        } else {
+            changes.push(Change::Retimed { sub: sub.clone(), previous });
+        }
+    }
+    let gone = old.iter().filter(|sub| !new.iter().any(|current| current.key() == sub.key()));
+    changes.extend(gone.cloned().map(Change::Gone));
+    changes
+}
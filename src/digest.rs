@@ -0,0 +1,47 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{db::SubInfo, i18n::fill, timefmt::TimeDisplay};
+
+/// Title and body for the daily rundown of what's due back in the next 24 hours, and
+/// how long the subs that are back have sat there between them over the last 24. Only
+/// the last day counts, so a sub nobody collects doesn't pile up hours every digest.
+pub fn message(subs: &[SubInfo], now: DateTime<Utc>, display: &TimeDisplay) -> (String, String) {
+    let due: Vec<&SubInfo> = subs
+        .iter()
+        .filter(|sub| sub.return_time > now && sub.return_time <= now + TimeDelta::days(1))
+        .collect();
+    let text = display.text();
+    let waiting: Vec<&SubInfo> = subs.iter().filter(|sub| sub.return_time <= now).collect();
+    let day_ago = now - TimeDelta::days(1);
+    let idle = waiting.iter().fold(TimeDelta::zero(), |idle, sub| idle + (now - sub.return_time.max(day_ago)));
+    let idle = (!waiting.is_empty()).then(|| {
+        fill(text.digest_idle, &[("count", &waiting.len().to_string()), ("hours", &idle.num_hours().to_string())])
+    });
+    if due.is_empty() {
+        let body = match idle {
+            Some(idle) => format!("{}\n{idle}", text.digest_none_body),
+            None => text.digest_none_body.to_string(),
+        };
+        return (text.digest_none_title.to_string(), body);
+    }
+    let title = fill(text.digest_title, &[("count", &due.len().to_string())]);
+    let mut body = due
+        .iter()
+        .map(|sub| {
+            fill(
+                text.digest_line,
+                &[
+                    ("name", &sub.name),
+                    ("owner", &sub.owner()),
+                    ("time", &display.notification(sub.return_time)),
+                    ("relative", &display.relative(sub.return_time, now)),
+                ],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Some(idle) = idle {
+        body = format!("{body}\n{idle}");
+    }
+    (title, body)
+}
@@ -0,0 +1,313 @@
+use std::{
+    io,
+    net::TcpStream,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context};
+use chrono::Utc;
+use reqwest::blocking::Client;
+use serde_json::{json, Value};
+use tracing::{debug, error, info, warn};
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::{
+    config::DiscordConfig,
+    db::SubInfo,
+    i18n::{fill, Messages},
+    notifier::Notifier,
+};
+
+const API: &str = "https://discord.com/api/v10";
+const GATEWAY: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+/// How long to wait before connecting again after losing the gateway
+const RECONNECT_DELAY: Duration = Duration::from_secs(30);
+/// Discord's limit on a message's length
+const MAX_MESSAGE: usize = 2000;
+/// Close codes that mean connecting again won't help: a bad token, or a gateway
+/// version or intents it'll never accept
+const FATAL_CLOSE_CODES: [u16; 5] = [4004, 4010, 4012, 4013, 4014];
+
+/// Announcements in a Discord channel, through its webhook or posted by the bot
+pub struct Discord {
+    config: DiscordConfig,
+    client: Client,
+}
+
+impl Discord {
+    pub fn new(config: DiscordConfig) -> Self {
+        Discord { config, client: Client::new() }
+    }
+}
+
+impl Notifier for Discord {
+    fn name(&self) -> &str {
+        "discord"
+    }
+
+    fn send(&mut self, title: &str, body: &str) -> anyhow::Result<()> {
+        // Sub and character names are the players' own, so nothing in them gets to ping anyone
+        let content = json!({ "content": format!("**{title}**\n{body}"), "allowed_mentions": {"parse": []} });
+        let request = match (&self.config.webhook, &self.config.token, &self.config.channel) {
+            (Some(webhook), _, _) => self.client.post(webhook),
+            (None, Some(token), Some(channel)) => self
+                .client
+                .post(format!("{API}/channels/{channel}/messages"))
+                .header("Authorization", format!("Bot {token}")),
+            _ => bail!("[discord] needs a webhook, or a token and a channel to announce in"),
+        };
+        request.json(&content).send()?.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// The bot's slash commands, answered from whatever the daemon last read
+pub struct Bot {
+    subs: Arc<Mutex<Vec<SubInfo>>>,
+}
+
+impl Bot {
+    /// Connects with `token` on a thread of its own, reconnecting whenever the gateway
+    /// goes away, until the token turns out to be no good. It answers in `text` for as
+    /// long as it runs: reloading the config doesn't change its language.
+    pub fn start(token: String, guild: Option<String>, text: &'static Messages) -> Self {
+        let subs = Arc::new(Mutex::new(Vec::new()));
+        let bot_subs = subs.clone();
+        thread::spawn(move || {
+            let client = Client::new();
+            loop {
+                match run_gateway(&client, &token, guild.as_deref(), &bot_subs, text) {
+                    Ok(()) => return,
+                    Err(e) => warn!(error = format!("{e:#}"), "lost the Discord gateway, reconnecting"),
+                }
+                thread::sleep(RECONNECT_DELAY);
+            }
+        });
+        Bot { subs }
+    }
+
+    pub fn update(&self, subs: &[SubInfo]) {
+        if let Ok(mut current) = self.subs.lock() {
+            *current = subs.to_vec();
+        }
+    }
+}
+
+/// One session on the gateway: identify, heartbeat, and answer interactions. Returns
+/// Ok only when it shouldn't be tried again.
+fn run_gateway(
+    client: &Client,
+    token: &str,
+    guild: Option<&str>,
+    subs: &Mutex<Vec<SubInfo>>,
+    text: &'static Messages,
+) -> anyhow::Result<()> {
+    let (mut socket, _) = tungstenite::connect(GATEWAY).context("Couldn't connect to the Discord gateway")?;
+    let Event::Payload(hello) = read_event(&mut socket)? else {
+        bail!("The gateway didn't say hello");
+    };
+    let interval = Duration::from_millis(hello["d"]["heartbeat_interval"].as_u64().context("No heartbeat interval")?);
+    // Short reads, so heartbeats go out on time between events
+    match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => stream.set_read_timeout(Some(Duration::from_secs(1)))?,
+        MaybeTlsStream::NativeTls(stream) => stream.get_ref().set_read_timeout(Some(Duration::from_secs(1)))?,
+        _ => {}
+    }
+    // Interactions come without any gateway intents
+    send(
+        &mut socket,
+        json!({"op": 2, "d": {"token": token, "intents": 0, "properties": {
+            "os": std::env::consts::OS, "browser": "sub-returns", "device": "sub-returns"
+        }}}),
+    )?;
+
+    let mut sequence = Value::Null;
+    let mut next_heartbeat = Instant::now() + interval;
+    let mut acked = true;
+    loop {
+        if Instant::now() >= next_heartbeat {
+            if !acked {
+                bail!("The gateway stopped answering heartbeats");
+            }
+            send(&mut socket, json!({"op": 1, "d": sequence}))?;
+            acked = false;
+            next_heartbeat = Instant::now() + interval;
+        }
+        let event = match read_event(&mut socket)? {
+            Event::Payload(event) => event,
+            Event::Nothing => continue,
+            Event::Closed(Some(code), reason) if FATAL_CLOSE_CODES.contains(&code) => {
+                error!(code, reason, "Discord refused the bot, check [discord] token; not connecting again");
+                return Ok(());
+            }
+            Event::Closed(code, reason) => bail!("The gateway closed the connection ({code:?} {reason})"),
+        };
+        if !event["s"].is_null() {
+            sequence = event["s"].clone();
+        }
+        match event["op"].as_u64() {
+            Some(0) => match event["t"].as_str() {
+                Some("READY") => {
+                    let application = event["d"]["application"]["id"].as_str().context("READY without an application")?;
+                    register_commands(client, token, application, guild)?;
+                    info!(user = event["d"]["user"]["username"].as_str(), "Discord bot ready");
+                }
+                Some("INTERACTION_CREATE") => {
+                    if let Err(e) = answer(client, &event["d"], subs, text) {
+                        warn!(error = format!("{e:#}"), "couldn't answer a Discord command");
+                    }
+                }
+                _ => {}
+            },
+            // The gateway wants a heartbeat now
+            Some(1) => next_heartbeat = Instant::now(),
+            Some(7) => bail!("Discord asked for a reconnect"),
+            Some(9) => bail!("Discord invalidated the session"),
+            Some(11) => acked = true,
+            _ => {}
+        }
+    }
+}
+
+enum Event {
+    Payload(Value),
+    /// Nothing came before the read timed out
+    Nothing,
+    /// With the close code and reason, if it gave them
+    Closed(Option<u16>, String),
+}
+
+fn read_event(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> anyhow::Result<Event> {
+    match socket.read() {
+        Ok(Message::Text(text)) => {
+            debug!(event = %text, "Discord gateway event");
+            Ok(Event::Payload(serde_json::from_str(&text)?))
+        }
+        Ok(Message::Close(frame)) => Ok(match frame {
+            Some(frame) => Event::Closed(Some(frame.code.into()), frame.reason.to_string()),
+            None => Event::Closed(None, String::new()),
+        }),
+        Ok(_) => Ok(Event::Nothing),
+        Err(tungstenite::Error::Io(e)) if matches!(e.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+            Ok(Event::Nothing)
+        }
+        Err(tungstenite::Error::ConnectionClosed | tungstenite::Error::AlreadyClosed) => {
+            Ok(Event::Closed(None, String::new()))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn send(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, payload: Value) -> anyhow::Result<()> {
+    socket.send(Message::text(payload.to_string()))?;
+    Ok(())
+}
+
+/// `/subs`, `/next` and `/when`. In one server they're there straight away; everywhere
+/// the bot is, they can take a while to show up the first time.
+fn register_commands(client: &Client, token: &str, application: &str, guild: Option<&str>) -> anyhow::Result<()> {
+    let url = match guild {
+        Some(guild) => format!("{API}/applications/{application}/guilds/{guild}/commands"),
+        None => format!("{API}/applications/{application}/commands"),
+    };
+    let commands = json!([
+        {"name": "subs", "description": "When every sub is back", "type": 1},
+        {"name": "next", "description": "The next sub to return", "type": 1},
+        {"name": "when", "description": "When a character's subs are back", "type": 1, "options": [
+            {"type": 3, "name": "character", "description": "The character's name", "required": true}
+        ]},
+    ]);
+    client
+        .put(url)
+        .header("Authorization", format!("Bot {token}"))
+        .json(&commands)
+        .send()?
+        .error_for_status()
+        .context("Couldn't register the slash commands")?;
+    Ok(())
+}
+
+fn answer(client: &Client, interaction: &Value, subs: &Mutex<Vec<SubInfo>>, text: &'static Messages) -> anyhow::Result<()> {
+    // Only slash commands
+    if interaction["type"].as_u64() != Some(2) {
+        return Ok(());
+    }
+    let mut subs = subs.lock().map_err(|_| anyhow!("Sub list poisoned"))?.clone();
+    subs.sort_by_key(|sub| sub.return_time);
+    let data = &interaction["data"];
+    let content = match data["name"].as_str() {
+        Some("subs") => list(&subs, text),
+        Some("next") => next(&subs, text),
+        Some("when") => {
+            let character = data["options"][0]["value"].as_str().unwrap_or_default();
+            when(&subs, character, text)
+        }
+        _ => return Ok(()),
+    };
+    let id = interaction["id"].as_str().context("Interaction without an id")?;
+    let token = interaction["token"].as_str().context("Interaction without a token")?;
+    client
+        .post(format!("{API}/interactions/{id}/{token}/callback"))
+        .json(&json!({"type": 4, "data": {"content": truncate(content), "allowed_mentions": {"parse": []}}}))
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// A Discord timestamp, which each reader sees in their own timezone, and how far off it is
+fn timestamp(sub: &SubInfo) -> String {
+    let unix = sub.return_time.timestamp();
+    format!("<t:{unix}:f> (<t:{unix}:R>)")
+}
+
+/// Each character's subs and when they're back, for `/subs` and `share`
+pub fn list(subs: &[SubInfo], text: &Messages) -> String {
+    if subs.is_empty() {
+        return text.no_subs.to_string();
+    }
+    let mut owners: Vec<String> = Vec::new();
+    for sub in subs {
+        if !owners.contains(&sub.owner()) {
+            owners.push(sub.owner());
+        }
+    }
+    owners
+        .iter()
+        .map(|owner| {
+            let lines: Vec<String> = subs
+                .iter()
+                .filter(|sub| sub.owner() == *owner)
+                .map(|sub| format!("{}: {}", sub.name, timestamp(sub)))
+                .collect();
+            format!("**{owner}**\n{}", lines.join("\n"))
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn next(subs: &[SubInfo], text: &Messages) -> String {
+    match subs.iter().find(|sub| sub.return_time > Utc::now()) {
+        Some(sub) => fill(text.next, &[("name", &sub.name), ("owner", &sub.owner()), ("when", &timestamp(sub))]),
+        None => text.all_returned.to_string(),
+    }
+}
+
+fn when(subs: &[SubInfo], character: &str, text: &Messages) -> String {
+    let theirs: Vec<&SubInfo> =
+        subs.iter().filter(|sub| sub.character_name.eq_ignore_ascii_case(character.trim())).collect();
+    let (Some(first), Some(last)) = (theirs.first(), theirs.last()) else {
+        return fill(text.no_subs_for, &[("character", character)]);
+    };
+    format!("**{}**: {}", first.owner(), fill(text.summary, &[("first", &timestamp(first)), ("last", &timestamp(last))]))
+}
+
+fn truncate(content: String) -> String {
+    if content.chars().count() <= MAX_MESSAGE {
+        return content;
+    }
+    let mut cut: String = content.chars().take(MAX_MESSAGE - 1).collect();
+    cut.push('…');
+    cut
+}
@@ -0,0 +1,205 @@
+use std::{path::Path, time::Duration};
+
+use chrono_tz::Tz;
+use reqwest::blocking::Client;
+use rusqlite::{Connection, OpenFlags};
+
+use crate::{
+    config::Config,
+    config_path,
+    db::{self, DB_FILE_NAME},
+    local_timezone,
+};
+
+/// Result of a single check. Failures carry a hint telling the user what to do next.
+enum Check {
+    Ok(String),
+    Warn(String),
+    Fail(String, String),
+}
+
+impl Check {
+    fn print(&self) {
+        match self {
+            Check::Ok(message) => println!("[ ok ] {message}"),
+            Check::Warn(message) => println!("[warn] {message}"),
+            Check::Fail(message, hint) => {
+                println!("[FAIL] {message}");
+                println!("       {hint}");
+            }
+        }
+    }
+}
+
+/// Runs every check and prints the results, failing if anything needs fixing.
+pub fn run() -> anyhow::Result<()> {
+    let config = Config::load(&config_path()).unwrap_or_default();
+    let mut checks = Vec::new();
+    // With sources configured, the plugin's own folder only matters if one points there
+    if config.sources.is_empty() {
+        checks.extend(check_plugin_folders());
+    }
+    checks.extend(check_sources(&config));
+    checks.push(check_timezone());
+    checks.push(check_config());
+    checks.push(check_bridge(&config));
+    #[cfg(all(unix, not(target_os = "macos")))]
+    checks.push(check_desktop_notifications());
+
+    for check in &checks {
+        check.print();
+    }
+    let failures = checks.iter().filter(|c| matches!(c, Check::Fail(..))).count();
+    if failures > 0 {
+        anyhow::bail!("{failures} check(s) failed");
+    }
+    println!("Everything looks good!");
+    Ok(())
+}
+
+fn check_plugin_folders() -> Vec<Check> {
+    let folders = db::candidate_plugin_folders();
+    if folders.is_empty() {
+        return vec![Check::Fail(
+            "Couldn't work out your home directory".to_string(),
+            "Make sure HOME (or your user profile on Windows) is set".to_string(),
+        )];
+    }
+    let found: Vec<Check> = folders
+        .iter()
+        .filter(|folder| folder.is_dir())
+        .map(|folder| Check::Ok(format!("Found the SubmarineTracker folder at {}", folder.display())))
+        .collect();
+    if !found.is_empty() {
+        return found;
+    }
+    let searched: Vec<String> = folders.iter().map(|f| f.display().to_string()).collect();
+    vec![Check::Fail(
+        format!("No SubmarineTracker folder in any of: {}", searched.join(", ")),
+        "Install the SubmarineTracker plugin in Dalamud and log in to a character with submarines at least once"
+            .to_string(),
+    )]
+}
+
+/// Each database the daemon reads, the auto-detected one without any `[[sources]]`.
+/// Remote sources are only listed, they're checked when they're read.
+fn check_sources(config: &Config) -> Vec<Check> {
+    let local = db::local_dbs(&config.sources);
+    let mut checks: Vec<Check> = config
+        .sources
+        .iter()
+        .filter(|source| !local.iter().any(|(label, _)| label.as_ref() == Some(&source.label)))
+        .map(|source| Check::Ok(format!("'{}' is read from {}, not checked here", source.label, source.path.display())))
+        .collect();
+    for (label, path) in &local {
+        checks.extend(check_database(label.as_deref(), path));
+    }
+    checks
+}
+
+fn check_database(label: Option<&str>, path: &Path) -> Vec<Check> {
+    // Which source it is, when there's more than one
+    let source = label.map_or(String::new(), |label| format!("'{label}': "));
+    if !path.is_file() {
+        let folder = path.parent().unwrap_or(path);
+        if db::has_json_files(folder) {
+            return vec![Check::Warn(format!(
+                "{source}No {DB_FILE_NAME}, reading the older per-FC JSON files in {} instead",
+                folder.display()
+            ))];
+        }
+        return vec![Check::Fail(
+            format!("{source}No {DB_FILE_NAME} at {}", path.display()),
+            "SubmarineTracker creates it the first time it sees your submarines, open the submarine panel in game"
+                .to_string(),
+        )];
+    }
+    let db = match Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(db) => db,
+        Err(e) => {
+            return vec![Check::Fail(
+                format!("{source}Couldn't open {}: {e}", path.display()),
+                "Check the file's permissions and that it isn't corrupt".to_string(),
+            )]
+        }
+    };
+
+    let mut checks = vec![Check::Ok(format!("{source}Opened {}", path.display()))];
+    match db::Schema::detect(&db) {
+        Ok(schema) => checks.push(Check::Ok(format!(
+            "{source}Database schema version {} is supported",
+            schema.user_version
+        ))),
+        Err(e) => {
+            checks.push(Check::Fail(
+                format!("{source}{e:#}"),
+                "Your SubmarineTracker version may be newer than this tool supports, check for an update".to_string(),
+            ));
+            return checks;
+        }
+    }
+
+    match db::read_sqlite(&db) {
+        Ok(subs) if subs.is_empty() => {
+            checks.push(Check::Warn(format!("{source}The database has no submarines in it yet")))
+        }
+        Ok(subs) => checks.push(Check::Ok(format!("{source}Read {} submarines", subs.len()))),
+        Err(e) => checks.push(Check::Fail(
+            format!("{source}Couldn't read submarines: {e}"),
+            "The database may be locked or corrupt, try again with the game closed".to_string(),
+        )),
+    }
+    checks
+}
+
+fn check_timezone() -> Check {
+    let Some(tz_name) = local_timezone() else {
+        return Check::Fail(
+            "Couldn't detect your timezone".to_string(),
+            "Set TZ to your IANA timezone, e.g. TZ=Europe/Berlin".to_string(),
+        );
+    };
+    match tz_name.parse::<Tz>() {
+        Ok(_) => Check::Ok(format!("Timezone is {tz_name}")),
+        Err(_) => Check::Fail(
+            format!("'{tz_name}' isn't a timezone name this tool understands"),
+            "Set TZ to your IANA timezone, e.g. TZ=Europe/Berlin".to_string(),
+        ),
+    }
+}
+
+fn check_config() -> Check {
+    let path = config_path();
+    match Config::load(&path) {
+        Ok(_) if path.is_file() => Check::Ok(format!("Loaded config from {}", path.display())),
+        Ok(_) => Check::Ok(format!("No config at {}, using defaults", path.display())),
+        Err(e) => Check::Fail(format!("{e:#}"), "Fix the config file or move it aside".to_string()),
+    }
+}
+
+fn check_bridge(config: &Config) -> Check {
+    let client = Client::builder().timeout(Duration::from_secs(5)).build();
+    // Any HTTP response at all means the bridge is there, even if it rejects a bare GET
+    match client.and_then(|client| client.get(&config.bridge.url).send()) {
+        Ok(response) => Check::Ok(format!(
+            "Pushover bridge at {} answered ({})",
+            config.bridge.url,
+            response.status()
+        )),
+        Err(e) => Check::Fail(
+            format!("Couldn't reach the Pushover bridge at {}: {e}", config.bridge.url),
+            "Check the bridge is running and that [bridge] url in your config is right".to_string(),
+        ),
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn check_desktop_notifications() -> Check {
+    match notify_rust::get_server_information() {
+        Ok(info) => Check::Ok(format!("Desktop notifications go to {} {}", info.name, info.version)),
+        Err(e) => Check::Fail(
+            format!("No desktop notification server: {e}"),
+            "The daemon needs to run inside your desktop session to show notifications".to_string(),
+        ),
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::TimeDelta;
+
+/// Parses compact durations like `36h30m`, `2d`, `90s` or `5m`. Units can repeat and
+/// come in any order; a bare number isn't accepted since the unit would be a guess.
+pub fn parse_duration(s: &str) -> Option<TimeDelta> {
+    let mut total = TimeDelta::zero();
+    let mut digits = String::new();
+    for c in s.trim().chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let amount: i64 = digits.parse().ok()?;
+        digits.clear();
+        total += match c.to_ascii_lowercase() {
+            'd' => TimeDelta::try_days(amount)?,
+            'h' => TimeDelta::try_hours(amount)?,
+            'm' => TimeDelta::try_minutes(amount)?,
+            's' => TimeDelta::try_seconds(amount)?,
+            _ => return None,
+        };
+    }
+    if !digits.is_empty() || s.trim().is_empty() {
+        return None;
+    }
+    Some(total)
+}
+
+/// Renders a duration for people, to the two largest units: `2d 4h`, `3h 12m`, `5m`.
+/// The sign is dropped; callers say whether it's "in" or "ago".
+pub fn format_duration(delta: TimeDelta) -> String {
+    let secs = delta.num_seconds().abs();
+    let (days, hours, mins) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else if mins > 0 {
+        format!("{mins}m")
+    } else {
+        format!("{secs}s")
+    }
+}
+
+/// A `parse_duration` string in the config file, like `lead_times = ["30m", "5m"]`,
+/// or on the command line
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ConfigDuration(pub TimeDelta);
+
+impl std::str::FromStr for ConfigDuration {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_duration(s)
+            .map(ConfigDuration)
+            .ok_or_else(|| format!("invalid duration {s:?}, expected something like 30m or 1h30m"))
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ConfigDuration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,63 @@
+use std::io::Write;
+
+use chrono::{DateTime, Local, TimeDelta, Timelike, Utc};
+use clap::ValueEnum;
+
+use crate::{
+    db::SubInfo,
+    template::{self, Template},
+    timefmt::TimeDisplay,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Scheduler {
+    /// crontab lines, to add with `crontab -e`
+    Cron,
+    /// `at` jobs, as a script to pipe to `sh`
+    At,
+}
+
+/// `--emit`: an entry for `scheduler` per sub still out, running `command` when it's
+/// back. Times are the machine's local time, which is what cron and at go by, rounded
+/// up to the minute so nothing fires early.
+pub fn write(
+    out: &mut dyn Write,
+    subs: &[SubInfo],
+    scheduler: Scheduler,
+    command: &Template,
+    display: &TimeDisplay,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    if let Scheduler::Cron = scheduler {
+        // A crontab line has no year, so it would go off again this time next year
+        writeln!(out, "# {}", display.text().cron_once)?;
+    }
+    for sub in subs.iter().filter(|sub| sub.return_time > now) {
+        let command = command.render(&template::sub_context(sub, display, now))?;
+        let when = minute_after(sub.return_time);
+        match scheduler {
+            // cron turns any % in the command into a newline unless it's escaped
+            Scheduler::Cron => {
+                writeln!(out, "# {} ({})", sub.name, sub.owner())?;
+                writeln!(out, "{} {}", when.format("%M %H %d %m *"), command.replace('%', "\\%"))?;
+            }
+            Scheduler::At => writeln!(
+                out,
+                "echo '{}' | at -t {}",
+                command.replace('\'', "'\\''"),
+                when.format("%Y%m%d%H%M")
+            )?,
+        }
+    }
+    Ok(())
+}
+
+fn minute_after(time: DateTime<Utc>) -> DateTime<Local> {
+    let local = time.with_timezone(&Local);
+    let whole = local.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(local);
+    if whole < local {
+        whole + TimeDelta::minutes(1)
+    } else {
+        whole
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::Utc;
+
+use crate::db::SubInfo;
+
+/// Narrows a set of subs down. Each list is OR'd within itself and AND'd with the
+/// others; an empty list doesn't filter anything.
+#[derive(Debug, Default, Clone)]
+pub struct SubFilter {
+    /// Sub names (case-insensitive) or ids
+    pub subs: Vec<String>,
+    /// Character names, matched case-insensitively on any part of the name
+    pub characters: Vec<String>,
+    /// Free Company tags, case-insensitive
+    pub fcs: Vec<String>,
+    /// `Some(true)` for only subs that are back, `Some(false)` for only ones still out
+    pub returned: Option<bool>,
+}
+
+impl SubFilter {
+    pub fn is_empty(&self) -> bool {
+        self.subs.is_empty() && self.characters.is_empty() && self.fcs.is_empty() && self.returned.is_none()
+    }
+
+    pub fn matches(&self, sub: &SubInfo) -> bool {
+        let sub_matches = self.subs.is_empty()
+            || self.subs.iter().any(|wanted| {
+                wanted.parse::<i64>() == Ok(sub.id) || wanted.eq_ignore_ascii_case(&sub.name)
+            });
+        let character = sub.character_name.to_lowercase();
+        let character_matches = self.characters.is_empty()
+            || self
+                .characters
+                .iter()
+                .any(|wanted| character.contains(&wanted.to_lowercase()));
+        let fc_matches =
+            self.fcs.is_empty() || self.fcs.iter().any(|wanted| wanted.eq_ignore_ascii_case(&sub.tag));
+        let returned_matches = self.returned.is_none_or(|returned| returned == (sub.return_time <= Utc::now()));
+        sub_matches && character_matches && fc_matches && returned_matches
+    }
+}
@@ -0,0 +1,277 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, bail, Context};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use reqwest::{blocking::Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use tracing::{debug, info};
+
+use crate::{
+    calendar::{self, Calendar, EVENT_LENGTH},
+    config::GoogleConfig,
+    data_dir,
+    db::SubInfo,
+    i18n::Messages,
+    private_file,
+};
+
+const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const CALENDARS_URL: &str = "https://www.googleapis.com/calendar/v3/calendars";
+/// Just enough to manage events, not to read the rest of the account
+const SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+/// How long signing in in the browser can take
+const SIGN_IN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// A Google calendar, through the Calendar API. Each sub's event has an id made from
+/// its key, so it's found again to be moved instead of being added a second time.
+pub struct Google<'a> {
+    config: &'a GoogleConfig,
+    client: Client,
+    access_token: String,
+}
+
+/// Both a successful token response and a failed one, which only has `error`
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+    refresh_token: Option<String>,
+    error: Option<String>,
+}
+
+/// Kept in the data dir so signing in is only needed once
+#[derive(Serialize, Deserialize)]
+struct SavedToken {
+    refresh_token: String,
+}
+
+impl<'a> Google<'a> {
+    /// Signs in with the token saved last time, or if there isn't one (or it's been
+    /// revoked) in a browser
+    pub fn connect(config: &'a GoogleConfig) -> anyhow::Result<Self> {
+        let client = Client::builder().timeout(Duration::from_secs(30)).build()?;
+        let path = token_path();
+        // Saved by a version that didn't keep it private
+        if let Ok(file) = fs::File::open(&path) {
+            private_file::restrict(&file)?;
+        }
+        let saved: Option<SavedToken> = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok());
+        if let Some(saved) = saved {
+            match refresh(&client, config, &saved.refresh_token)? {
+                Some(access_token) => return Ok(Google { config, client, access_token }),
+                None => info!("saved Google sign-in no longer works, signing in again"),
+            }
+        }
+
+        let refresh_token = sign_in(&client, config)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        private_file::write(&path, &serde_json::to_string(&SavedToken { refresh_token: refresh_token.clone() })?)?;
+        let access_token = refresh(&client, config, &refresh_token)?.context("Google wouldn't take its own token")?;
+        Ok(Google { config, client, access_token })
+    }
+
+    fn calendar_id(&self) -> &str {
+        self.config.calendar.as_deref().unwrap_or("primary")
+    }
+
+    /// `events`, or one event in it
+    fn events_url(&self, event: Option<&str>) -> Url {
+        let mut url = Url::parse(CALENDARS_URL).expect("calendar API URL is valid");
+        {
+            let mut path = url.path_segments_mut().expect("calendar API URL has a path");
+            path.push(self.calendar_id()).push("events");
+            if let Some(event) = event {
+                path.push(event);
+            }
+        }
+        url
+    }
+}
+
+impl Calendar for Google<'_> {
+    fn name(&self) -> &str {
+        self.calendar_id()
+    }
+
+    fn put(&mut self, sub: &SubInfo, _now: DateTime<Utc>, text: &Messages) -> anyhow::Result<()> {
+        let key = sub.key();
+        let id = event_id(&key);
+        let event = json!({
+            "id": id,
+            "summary": calendar::summary(sub, text),
+            "description": calendar::description(sub, text),
+            "start": {"dateTime": sub.return_time.to_rfc3339()},
+            "end": {"dateTime": (sub.return_time + EVENT_LENGTH).to_rfc3339()},
+            // Updating an event that was deleted by hand brings it back
+            "status": "confirmed",
+            "extendedProperties": {"private": {"subReturnsKey": key}},
+        });
+        debug!(id, sub = sub.name, "updating event");
+        let response = self
+            .client
+            .put(self.events_url(Some(&id)))
+            .bearer_auth(&self.access_token)
+            .json(&event)
+            .send()
+            .context("Couldn't reach Google Calendar")?;
+        let response = if response.status() == StatusCode::NOT_FOUND {
+            debug!(id, sub = sub.name, "adding event");
+            self.client
+                .post(self.events_url(None))
+                .bearer_auth(&self.access_token)
+                .json(&event)
+                .send()
+                .context("Couldn't reach Google Calendar")?
+        } else {
+            response
+        };
+        if !response.status().is_success() {
+            let status = response.status();
+            let error = response.text().unwrap_or_default();
+            bail!("Google Calendar wouldn't take {}'s event: {status} {error}", sub.name);
+        }
+        Ok(())
+    }
+
+    fn delete(&mut self, key: &str) -> anyhow::Result<()> {
+        let id = event_id(key);
+        debug!(id, "deleting event");
+        let response = self
+            .client
+            .delete(self.events_url(Some(&id)))
+            .bearer_auth(&self.access_token)
+            .send()
+            .context("Couldn't reach Google Calendar")?;
+        // Already gone is as good as deleted
+        let status = response.status();
+        if !status.is_success() && status != StatusCode::NOT_FOUND && status != StatusCode::GONE {
+            bail!("Google Calendar wouldn't delete event {id}: {status}");
+        }
+        Ok(())
+    }
+}
+
+fn token_path() -> PathBuf {
+    data_dir().join("google-token.json")
+}
+
+/// Event ids can only have 0-9 and a-v in them, so the key goes in as hex
+fn event_id(key: &str) -> String {
+    let hex: String = key.bytes().map(|byte| format!("{byte:02x}")).collect();
+    format!("subreturns{hex}")
+}
+
+/// The loopback flow Google has desktop apps use: its sign-in page is opened in a
+/// browser, which it then sends back to a port here with a code to trade for tokens.
+/// Returns the refresh token.
+fn sign_in(client: &Client, config: &GoogleConfig) -> anyhow::Result<String> {
+    let server = tiny_http::Server::http("127.0.0.1:0")
+        .map_err(|e| anyhow!("{e}"))
+        .context("Couldn't listen for Google to send the browser back")?;
+    let port = server.server_addr().to_ip().context("Listening somewhere without a port")?.port();
+    let redirect_uri = format!("http://127.0.0.1:{port}");
+    // PKCE, so the code is no use to anything else that sees it go past
+    let verifier = random_token()?;
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(&verifier));
+    let state = random_token()?;
+    let url = Url::parse_with_params(
+        AUTH_URL,
+        [
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", &redirect_uri),
+            ("response_type", "code"),
+            ("scope", SCOPE),
+            ("code_challenge", &challenge),
+            ("code_challenge_method", "S256"),
+            ("state", &state),
+            ("access_type", "offline"),
+            // Otherwise signing in again doesn't come with a refresh token
+            ("prompt", "consent"),
+        ],
+    )?;
+    println!("To let sub-returns manage your calendar, open this in a browser on this machine:\n\n{url}\n");
+
+    let deadline = Instant::now() + SIGN_IN_TIMEOUT;
+    let code = loop {
+        let Some(request) = server.recv_timeout(deadline.saturating_duration_since(Instant::now()))? else {
+            bail!("Nobody signed in, run it again to have another go");
+        };
+        let query = Url::parse(&format!("{redirect_uri}{}", request.url()))?;
+        let param = |name: &str| query.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.into_owned());
+        // Anything else the browser asks for, like a favicon
+        if param("state").as_deref() != Some(state.as_str()) {
+            let _ = request.respond(tiny_http::Response::empty(404));
+            continue;
+        }
+        let reply = |text: &str| tiny_http::Response::from_string(text);
+        match (param("code"), param("error")) {
+            (Some(code), _) => {
+                let _ = request.respond(reply("Signed in to sub-returns, you can close this tab."));
+                break code;
+            }
+            (None, error) => {
+                let _ = request.respond(reply("Not signed in, sub-returns can't manage your calendar."));
+                match error.as_deref() {
+                    Some("access_denied") => bail!("Access to the calendar was turned down"),
+                    error => bail!("Google wouldn't sign in: {}", error.unwrap_or("no code")),
+                }
+            }
+        }
+    };
+
+    let response: TokenResponse = client
+        .post(TOKEN_URL)
+        .form(&[
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+            ("code", code.as_str()),
+            ("code_verifier", verifier.as_str()),
+            ("redirect_uri", redirect_uri.as_str()),
+            ("grant_type", "authorization_code"),
+        ])
+        .send()
+        .context("Couldn't reach Google to sign in")?
+        .json()?;
+    match response.error {
+        None => response.refresh_token.context("Google signed in but didn't send a refresh token"),
+        Some(error) => bail!("Google wouldn't sign in: {error}"),
+    }
+}
+
+/// 32 random bytes, URL-safe
+fn random_token() -> anyhow::Result<String> {
+    let mut bytes = [0; 32];
+    getrandom::getrandom(&mut bytes).map_err(|e| anyhow!("Couldn't get random bytes: {e}"))?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// A fresh access token, or None if the refresh token has been revoked or run out
+fn refresh(client: &Client, config: &GoogleConfig, refresh_token: &str) -> anyhow::Result<Option<String>> {
+    let response: TokenResponse = client
+        .post(TOKEN_URL)
+        .form(&[
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+            ("refresh_token", refresh_token),
+            ("grant_type", "refresh_token"),
+        ])
+        .send()
+        .context("Couldn't reach Google to sign in")?
+        .json()?;
+    match (response.access_token, response.error.as_deref()) {
+        (Some(token), _) => Ok(Some(token)),
+        (None, Some("invalid_grant")) => Ok(None),
+        (None, error) => bail!("Google wouldn't sign in: {}", error.unwrap_or("no access token")),
+    }
+}
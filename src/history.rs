@@ -0,0 +1,74 @@
+use std::io::Write;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    db::{SubInfo, SubSource},
+    i18n::fill,
+    loot,
+    timefmt::TimeDisplay,
+    voyage_log,
+};
+
+enum Event {
+    /// Sent out, back at this time
+    Dispatched(DateTime<Utc>),
+    Returned,
+}
+
+/// `history`: returns and dispatches of `subs` in the last `since`, oldest first.
+/// Dispatches come from the daemon's voyage log, and returns from that and the
+/// plugin's loot history, so a voyage either one knows about shows up.
+pub fn write(
+    out: &mut dyn Write,
+    sources: &[SubSource],
+    subs: &[SubInfo],
+    since: TimeDelta,
+    display: &TimeDisplay,
+) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let from = now - since;
+    let keys: Vec<String> = subs.iter().map(|sub| sub.key()).collect();
+    let mut events: Vec<(DateTime<Utc>, String, String, Event)> = Vec::new();
+    for dispatch in voyage_log::read()? {
+        if !keys.contains(&dispatch.key) {
+            continue;
+        }
+        if dispatch.seen >= from {
+            let event = Event::Dispatched(dispatch.return_time);
+            events.push((dispatch.seen, dispatch.owner.clone(), dispatch.sub.clone(), event));
+        }
+        if dispatch.return_time >= from && dispatch.return_time <= now {
+            events.push((dispatch.return_time, dispatch.owner, dispatch.sub, Event::Returned));
+        }
+    }
+    for voyage in loot::read_all(sources)? {
+        let Some(sub) = subs.iter().find(|sub| sub.key() == voyage.key) else {
+            continue;
+        };
+        // The log and the plugin put the same return a moment apart at most
+        let logged = events.iter().any(|(time, owner, name, event)| {
+            matches!(event, Event::Returned)
+                && *owner == sub.owner()
+                && *name == sub.name
+                && (*time - voyage.returned).abs() < TimeDelta::minutes(1)
+        });
+        if voyage.returned >= from && !logged {
+            events.push((voyage.returned, sub.owner(), sub.name.clone(), Event::Returned));
+        }
+    }
+    events.sort_by_key(|(time, ..)| *time);
+
+    let text = display.text();
+    if events.is_empty() {
+        writeln!(out, "{}", fill(text.history_none, &[("time", &display.short(from))]))?;
+    }
+    for (time, owner, sub, event) in events {
+        let what = match event {
+            Event::Dispatched(back) => fill(text.history_sent, &[("time", &display.short(back))]),
+            Event::Returned => text.history_returned.to_string(),
+        };
+        writeln!(out, "{}  {owner} {sub} {what}", display.short(time))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,519 @@
+//! The listing's and notifications' text in each of the game client's languages. Text
+//! with `{name}` in it is filled in with [`fill`]; the notification defaults are
+//! Handlebars templates like the ones in `[notifications]`.
+
+use std::env;
+
+use clap::ValueEnum;
+use serde::Deserialize;
+
+#[derive(Deserialize, ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    #[default]
+    En,
+    De,
+    Fr,
+    Ja,
+}
+
+impl Language {
+    /// From the messages locale (`de_DE.UTF-8` is German), English if it's none of
+    /// the others
+    pub fn detect() -> Language {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|var| env::var(var).ok())
+            .find(|value| !value.is_empty())
+            .unwrap_or_default();
+        match locale.get(..2) {
+            Some("de") => Language::De,
+            Some("fr") => Language::Fr,
+            Some("ja") => Language::Ja,
+            _ => Language::En,
+        }
+    }
+
+    pub fn messages(self) -> &'static Messages {
+        match self {
+            Language::En => &EN,
+            Language::De => &DE,
+            Language::Fr => &FR,
+            Language::Ja => &JA,
+        }
+    }
+}
+
+/// Replaces each `{name}` in `text` with its value
+pub fn fill(text: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(text.to_string(), |text, (name, value)| text.replace(&format!("{{{name}}}"), value))
+}
+
+pub struct Messages {
+    pub months: [&'static str; 12],
+    pub months_short: [&'static str; 12],
+    pub am_pm: [&'static str; 2],
+    /// Whether none takes the singular, as in French. Text that's counted has a form for
+    /// one and one for more, picked by [`Messages::plural`].
+    pub zero_is_singular: bool,
+    /// `{time}` is a duration
+    pub returns_in: &'static str,
+    pub returned_ago: &'static str,
+    pub estimated: &'static str,
+
+    pub returned_title: &'static str,
+    pub returned_body: &'static str,
+    pub lead_title: &'static str,
+    pub lead_body: &'static str,
+    pub nag_title: &'static str,
+    pub nag_body: &'static str,
+    pub overdue_title: &'static str,
+    pub overdue_body: &'static str,
+    pub voyage_title: &'static str,
+    pub voyage_body: &'static str,
+    pub all_home_title: &'static str,
+    pub all_home_body: &'static str,
+
+    pub digest_none_title: &'static str,
+    pub digest_none_body: &'static str,
+    pub digest_title: &'static str,
+    pub digest_line: &'static str,
+    pub digest_idle: &'static str,
+    pub quiet_title: &'static str,
+    pub quiet_line: &'static str,
+    pub away_title: &'static str,
+    /// Hull, stern, bow and bridge
+    pub parts: [&'static str; 4],
+    pub repair_title: &'static str,
+    pub repair_body: &'static str,
+    pub low_tanks_title: &'static str,
+    pub low_tanks_body: &'static str,
+    pub stale_title: &'static str,
+    pub stale_body: &'static str,
+
+    /// Sub, character, FC, returns, rank, build, EXP, rank up, condition, tanks, kits
+    /// and route
+    pub table_header: [&'static str; 12],
+    pub next: &'static str,
+    pub all_returned: &'static str,
+    /// In place of the time left for a sub that's back, in `--oneline`
+    pub back: &'static str,
+    /// Heading `share` puts on the schedule, and when it's from in the picture
+    pub share_title: &'static str,
+    pub share_as_of: &'static str,
+    pub summary: &'static str,
+    pub everyone: &'static str,
+    pub no_subs: &'static str,
+    /// `/when` for a character with none
+    pub no_subs_for: &'static str,
+    /// `routes` for a sub the database has no voyage for
+    pub no_route: &'static str,
+
+    /// `plan`: each play session's heading, then how long each sub waits for it
+    pub plan_session: &'static str,
+    pub plan_no_wait: &'static str,
+    pub plan_idle: &'static str,
+    pub plan_unplanned: &'static str,
+    pub plan_flagged: &'static str,
+    pub history_none: &'static str,
+    pub history_sent: &'static str,
+    pub history_returned: &'static str,
+    /// `{sectors}` is the most visited ones, each with its visits
+    pub stats_none: &'static str,
+    pub stats_voyages: [&'static str; 2],
+    pub stats_gil: &'static str,
+    pub stats_uptime: &'static str,
+    pub stats_sectors: &'static str,
+    /// A calendar event's title, and what its description says of an estimated return
+    pub event_summary: &'static str,
+    pub event_estimated: &'static str,
+    /// Heads the `--emit cron` lines
+    pub cron_once: &'static str,
+
+    /// The listing's `--details`, e.g. "rank 50 WSUC, 1200/5000 exp, 2 voyages to rank
+    /// 51, hull at 80%", and the voyages to the next rank in the table
+    pub rank: &'static str,
+    pub exp: &'static str,
+    pub to_rank: [&'static str; 2],
+    pub to_next_rank: [&'static str; 2],
+    pub part_condition: &'static str,
+    pub voyages: [&'static str; 2],
+    /// What each character's FC has left, after its name in `--details`
+    pub tanks: [&'static str; 2],
+    pub kits: [&'static str; 2],
+    /// `loot`: each voyage, then each sub, character and everyone's gil a day
+    pub loot_back: &'static str,
+    pub loot_procs: [&'static str; 2],
+    pub loot_worth: &'static str,
+    pub loot_daily: &'static str,
+    pub loot_none: &'static str,
+}
+
+impl Messages {
+    /// The one of `forms` that goes with `count`
+    pub fn plural(&self, forms: [&'static str; 2], count: usize) -> &'static str {
+        if count == 1 || (count == 0 && self.zero_is_singular) {
+            forms[0]
+        } else {
+            forms[1]
+        }
+    }
+}
+
+const EN: Messages = Messages {
+    months: [
+        "January", "February", "March", "April", "May", "June", "July", "August", "September", "October", "November",
+        "December",
+    ],
+    months_short: ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"],
+    am_pm: ["AM", "PM"],
+    zero_is_singular: false,
+    returns_in: "in {time}",
+    returned_ago: "returned {time} ago",
+    estimated: "estimated",
+
+    returned_title: "{{sub}}{{#if others}} (+{{others}}){{/if}} returned",
+    returned_body: "{{sub}} ({{owner}}){{#if others}} + {{others}} others{{/if}} returned on {{return_time}}",
+    lead_title: "{{sub}} back in {{lead}}",
+    lead_body: "{{sub}} ({{owner}}) returns on {{return_time}}",
+    nag_title: "{{sub}} is still waiting",
+    nag_body: "{{sub}} ({{owner}}) has been back since {{return_time}}",
+    overdue_title: "{{sub}} is overdue",
+    overdue_body: "{{sub}} ({{owner}}) has been waiting to be collected since {{return_time}}",
+    voyage_title: "{{sub}}{{#if others}} (+{{others}}){{/if}} sent out",
+    voyage_body: "{{sub}} ({{owner}}){{#if others}} + {{others}} others{{/if}} sent out, back on {{return_time}}",
+    all_home_title: "All of {{character}}'s subs are back",
+    all_home_body: "{{count}} subs ({{owner}}) back, the last on {{return_time}}",
+
+    digest_none_title: "No submarines due today",
+    digest_none_body: "Nothing is due back in the next 24 hours",
+    digest_title: "{count} submarine(s) due in the next 24 hours",
+    digest_line: "{name} ({owner}) {time}, {relative}",
+    digest_idle: "{count} submarine(s) waiting to be collected, {hours}h idle between them in the last day",
+    quiet_title: "{count} submarine(s) returned during quiet hours",
+    quiet_line: "{name} ({owner}) at {time}",
+    away_title: "While you were away, {count} submarine(s) returned",
+    parts: ["hull", "stern", "bow", "bridge"],
+    repair_title: "{sub} will need repairs",
+    repair_body: "{owner}'s {sub} will be back with its {part} at about {condition}% condition, repair it before \
+                  sending it out again",
+    low_tanks_title: "{owner} is low on ceruleum",
+    low_tanks_body: "{have} tanks left, and sending everyone out again takes {need}",
+    stale_title: "Submarine data looks stale",
+    stale_body: "The most recent return was {time} ago, SubmarineTracker may not be updating (plugin disabled, or \
+                 the game not launched)",
+
+    table_header: [
+        "Sub", "Character", "FC", "Returns", "Rank", "Build", "EXP", "Rank up", "Condition", "Tanks", "Kits", "Route",
+    ],
+    next: "{name} ({owner}) returns {when}",
+    all_returned: "All submarines have returned",
+    back: "returned",
+    share_title: "Submarine returns",
+    share_as_of: "As of {time}",
+    summary: "first back {first}, all back {last}",
+    everyone: "Everyone",
+    no_subs: "No submarines found",
+    no_subs_for: "No submarines for {character}",
+    no_route: "no route recorded",
+
+    plan_session: "{start} to {end}:",
+    plan_no_wait: "no wait",
+    plan_idle: "idle {time}",
+    plan_unplanned: "{name} ({owner}) isn't back in time for any play window in the next two weeks",
+    plan_flagged: "(!) idle more than {time}",
+    history_none: "Nothing came back or went out since {time}",
+    history_sent: "sent out, back {time}",
+    history_returned: "returned",
+    stats_none: "no voyages recorded",
+    stats_voyages: ["{count} voyage ({per_week} a week)", "{count} voyages ({per_week} a week)"],
+    stats_gil: ", about {gil} gil each",
+    stats_uptime: ", out {percent}% of the time",
+    stats_sectors: "; mostly {sectors}",
+    event_summary: "{name} returns",
+    event_estimated: "Estimated, the database doesn't know this voyage yet",
+    cron_once: "Each fires once a year, so replace these after sending the subs out again",
+
+    rank: "rank {rank}",
+    exp: "{exp}/{next} exp",
+    to_rank: ["{count} voyage to rank {rank}", "{count} voyages to rank {rank}"],
+    to_next_rank: ["{count} voyage to the next rank", "{count} voyages to the next rank"],
+    part_condition: "{part} at {condition}%",
+    voyages: ["{count} voyage", "{count} voyages"],
+    tanks: ["{count} tank", "{count} tanks"],
+    kits: ["{count} kit", "{count} kits"],
+    loot_back: "Back {time}, {route}",
+    loot_procs: [", {count} surveillance proc", ", {count} surveillance procs"],
+    loot_worth: ", worth {gil} gil",
+    loot_daily: "about {gil} gil a day",
+    loot_none: "No voyages recorded",
+};
+
+const DE: Messages = Messages {
+    months: [
+        "Januar", "Februar", "März", "April", "Mai", "Juni", "Juli", "August", "September", "Oktober", "November",
+        "Dezember",
+    ],
+    months_short: ["Jan.", "Feb.", "März", "Apr.", "Mai", "Juni", "Juli", "Aug.", "Sept.", "Okt.", "Nov.", "Dez."],
+    am_pm: ["AM", "PM"],
+    zero_is_singular: false,
+    returns_in: "in {time}",
+    returned_ago: "seit {time} zurück",
+    estimated: "geschätzt",
+
+    returned_title: "{{sub}}{{#if others}} (+{{others}}){{/if}} ist zurück",
+    returned_body: "{{sub}} ({{owner}}){{#if others}} und {{others}} weitere{{/if}} zurückgekehrt am {{return_time}}",
+    lead_title: "{{sub}} in {{lead}} zurück",
+    lead_body: "{{sub}} ({{owner}}) kehrt am {{return_time}} zurück",
+    nag_title: "{{sub}} wartet noch",
+    nag_body: "{{sub}} ({{owner}}) ist seit {{return_time}} zurück",
+    overdue_title: "{{sub}} ist überfällig",
+    overdue_body: "{{sub}} ({{owner}}) wartet seit {{return_time}} darauf, abgeholt zu werden",
+    voyage_title: "{{sub}}{{#if others}} (+{{others}}){{/if}} ausgesandt",
+    voyage_body: "{{sub}} ({{owner}}){{#if others}} und {{others}} weitere{{/if}} ausgesandt, zurück am {{return_time}}",
+    all_home_title: "Alle U-Boote von {{character}} sind zurück",
+    all_home_body: "{{count}} U-Boote ({{owner}}) zurück, das letzte am {{return_time}}",
+
+    digest_none_title: "Heute sind keine U-Boote fällig",
+    digest_none_body: "In den nächsten 24 Stunden kommt nichts zurück",
+    digest_title: "{count} U-Boot(e) in den nächsten 24 Stunden fällig",
+    digest_line: "{name} ({owner}) {time}, {relative}",
+    digest_idle: "{count} U-Boot(e) warten aufs Abholen, zusammen {hours} Std. untätig am letzten Tag",
+    quiet_title: "{count} U-Boot(e) während der Ruhezeit zurückgekehrt",
+    quiet_line: "{name} ({owner}) am {time}",
+    away_title: "Während du weg warst, sind {count} U-Boot(e) zurückgekehrt",
+    parts: ["Rumpf", "Heck", "Bug", "Brücke"],
+    repair_title: "{sub} muss repariert werden",
+    repair_body: "{sub} ({owner}) kommt mit {part} bei etwa {condition}% Zustand zurück, vor dem nächsten Aussenden \
+                  reparieren",
+    low_tanks_title: "{owner} hat wenig Ceruleum",
+    low_tanks_body: "Noch {have} Tanks, alle erneut auszusenden braucht {need}",
+    stale_title: "U-Boot-Daten scheinen veraltet",
+    stale_body: "Die letzte Rückkehr war vor {time}, SubmarineTracker aktualisiert womöglich nicht (Plugin \
+                 deaktiviert oder Spiel nicht gestartet)",
+
+    table_header: [
+        "U-Boot", "Charakter", "FC", "Rückkehr", "Rang", "Bauart", "EXP", "Rangaufstieg", "Zustand", "Tanks", "Kits",
+        "Route",
+    ],
+    next: "{name} ({owner}) kehrt zurück: {when}",
+    all_returned: "Alle U-Boote sind zurück",
+    back: "zurück",
+    share_title: "Rückkehr der U-Boote",
+    share_as_of: "Stand: {time}",
+    summary: "erstes zurück {first}, alle zurück {last}",
+    everyone: "Alle",
+    no_subs: "Keine U-Boote gefunden",
+    no_subs_for: "Keine U-Boote für {character}",
+    no_route: "keine Route gespeichert",
+
+    plan_session: "{start} bis {end}:",
+    plan_no_wait: "keine Wartezeit",
+    plan_idle: "{time} untätig",
+    plan_unplanned: "{name} ({owner}) ist in den nächsten zwei Wochen zu keiner Spielzeit rechtzeitig zurück",
+    plan_flagged: "(!) mehr als {time} untätig",
+    history_none: "Seit {time} ist nichts zurückgekehrt oder ausgesandt worden",
+    history_sent: "ausgesandt, zurück {time}",
+    history_returned: "zurückgekehrt",
+    stats_none: "keine Reisen aufgezeichnet",
+    stats_voyages: ["{count} Reise ({per_week} pro Woche)", "{count} Reisen ({per_week} pro Woche)"],
+    stats_gil: ", etwa {gil} Gil pro Reise",
+    stats_uptime: ", {percent}% der Zeit unterwegs",
+    stats_sectors: "; meist {sectors}",
+    event_summary: "{name} kehrt zurück",
+    event_estimated: "Geschätzt, die Datenbank kennt diese Reise noch nicht",
+    cron_once: "Jede löst einmal im Jahr aus, also nach dem erneuten Aussenden der U-Boote ersetzen",
+
+    rank: "Rang {rank}",
+    exp: "{exp}/{next} EXP",
+    to_rank: ["{count} Reise bis Rang {rank}", "{count} Reisen bis Rang {rank}"],
+    to_next_rank: ["{count} Reise bis zum nächsten Rang", "{count} Reisen bis zum nächsten Rang"],
+    part_condition: "{part} bei {condition}%",
+    voyages: ["{count} Reise", "{count} Reisen"],
+    tanks: ["{count} Tank", "{count} Tanks"],
+    kits: ["{count} Kit", "{count} Kits"],
+    loot_back: "Zurück {time}, {route}",
+    loot_procs: [", {count} Erkundungsbonus", ", {count} Erkundungsboni"],
+    loot_worth: ", Wert {gil} Gil",
+    loot_daily: "etwa {gil} Gil am Tag",
+    loot_none: "Keine Reisen aufgezeichnet",
+};
+
+const FR: Messages = Messages {
+    months: [
+        "janvier", "février", "mars", "avril", "mai", "juin", "juillet", "août", "septembre", "octobre", "novembre",
+        "décembre",
+    ],
+    months_short: [
+        "janv.", "févr.", "mars", "avr.", "mai", "juin", "juil.", "août", "sept.", "oct.", "nov.", "déc.",
+    ],
+    am_pm: ["AM", "PM"],
+    zero_is_singular: true,
+    returns_in: "dans {time}",
+    returned_ago: "revenu il y a {time}",
+    estimated: "estimé",
+
+    returned_title: "{{sub}}{{#if others}} (+{{others}}){{/if}} est revenu",
+    returned_body: "{{sub}} ({{owner}}){{#if others}} + {{others}} autres{{/if}} de retour le {{return_time}}",
+    lead_title: "{{sub}} de retour dans {{lead}}",
+    lead_body: "{{sub}} ({{owner}}) revient le {{return_time}}",
+    nag_title: "{{sub}} attend toujours",
+    nag_body: "{{sub}} ({{owner}}) est de retour depuis le {{return_time}}",
+    overdue_title: "{{sub}} est en retard",
+    overdue_body: "{{sub}} ({{owner}}) attend d'être récupéré depuis le {{return_time}}",
+    voyage_title: "{{sub}}{{#if others}} (+{{others}}){{/if}} envoyé",
+    voyage_body: "{{sub}} ({{owner}}){{#if others}} + {{others}} autres{{/if}} envoyé, de retour le {{return_time}}",
+    all_home_title: "Tous les sous-marins de {{character}} sont revenus",
+    all_home_body: "{{count}} sous-marins ({{owner}}) revenus, le dernier le {{return_time}}",
+
+    digest_none_title: "Aucun sous-marin attendu aujourd'hui",
+    digest_none_body: "Rien ne revient dans les prochaines 24 heures",
+    digest_title: "{count} sous-marin(s) attendu(s) dans les prochaines 24 heures",
+    digest_line: "{name} ({owner}) {time}, {relative}",
+    digest_idle: "{count} sous-marin(s) à récupérer, {hours} h d'inactivité au total sur la dernière journée",
+    quiet_title: "{count} sous-marin(s) revenu(s) pendant les heures calmes",
+    quiet_line: "{name} ({owner}) le {time}",
+    away_title: "Pendant votre absence, {count} sous-marin(s) sont revenus",
+    parts: ["coque", "poupe", "proue", "passerelle"],
+    repair_title: "{sub} aura besoin de réparations",
+    repair_body: "{sub} ({owner}) reviendra avec la {part} à environ {condition} % d'état, réparez-la avant de le \
+                  renvoyer",
+    low_tanks_title: "{owner} manque de céruleum",
+    low_tanks_body: "Plus que {have} réservoirs, et tout renvoyer en demande {need}",
+    stale_title: "Les données des sous-marins semblent obsolètes",
+    stale_body: "Le dernier retour date d'il y a {time}, SubmarineTracker ne se met peut-être plus à jour (plugin \
+                 désactivé ou jeu non lancé)",
+
+    table_header: [
+        "Sous-marin", "Personnage", "CL", "Retour", "Rang", "Config.", "EXP", "Rang suivant", "État", "Réservoirs",
+        "Kits", "Itinéraire",
+    ],
+    next: "{name} ({owner}) revient le {when}",
+    all_returned: "Tous les sous-marins sont revenus",
+    back: "revenu",
+    share_title: "Retour des sous-marins",
+    share_as_of: "Au {time}",
+    summary: "premier retour {first}, tous revenus {last}",
+    everyone: "Tout le monde",
+    no_subs: "Aucun sous-marin trouvé",
+    no_subs_for: "Aucun sous-marin pour {character}",
+    no_route: "aucun itinéraire enregistré",
+
+    plan_session: "{start} à {end} :",
+    plan_no_wait: "sans attente",
+    plan_idle: "inactif {time}",
+    plan_unplanned: "{name} ({owner}) ne sera revenu à temps pour aucune session de jeu des deux prochaines semaines",
+    plan_flagged: "(!) inactif plus de {time}",
+    history_none: "Rien n'est revenu ni parti depuis le {time}",
+    history_sent: "envoyé, de retour le {time}",
+    history_returned: "revenu",
+    stats_none: "aucun voyage enregistré",
+    stats_voyages: ["{count} voyage ({per_week} par semaine)", "{count} voyages ({per_week} par semaine)"],
+    stats_gil: ", environ {gil} gils chacun",
+    stats_uptime: ", en mer {percent} % du temps",
+    stats_sectors: " ; surtout {sectors}",
+    event_summary: "Retour de {name}",
+    event_estimated: "Estimé, la base de données ne connaît pas encore ce voyage",
+    cron_once: "Chacune se déclenche une fois par an, remplacez-les après avoir renvoyé les sous-marins",
+
+    rank: "rang {rank}",
+    exp: "{exp}/{next} EXP",
+    to_rank: ["{count} voyage avant le rang {rank}", "{count} voyages avant le rang {rank}"],
+    to_next_rank: ["{count} voyage avant le rang suivant", "{count} voyages avant le rang suivant"],
+    part_condition: "{part} à {condition} %",
+    voyages: ["{count} voyage", "{count} voyages"],
+    tanks: ["{count} réservoir", "{count} réservoirs"],
+    kits: ["{count} kit", "{count} kits"],
+    loot_back: "Revenu le {time}, {route}",
+    loot_procs: [", {count} bonus de reconnaissance", ", {count} bonus de reconnaissance"],
+    loot_worth: ", d'une valeur de {gil} gils",
+    loot_daily: "environ {gil} gils par jour",
+    loot_none: "Aucun voyage enregistré",
+};
+
+const JA: Messages = Messages {
+    months: ["1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月"],
+    months_short: ["1月", "2月", "3月", "4月", "5月", "6月", "7月", "8月", "9月", "10月", "11月", "12月"],
+    am_pm: ["午前", "午後"],
+    zero_is_singular: false,
+    returns_in: "あと{time}",
+    returned_ago: "{time}前に帰還",
+    estimated: "推定",
+
+    returned_title: "{{sub}}{{#if others}} (+{{others}}){{/if}}が帰還しました",
+    returned_body: "{{sub}}（{{owner}}）{{#if others}}ほか{{others}}隻{{/if}}が{{return_time}}に帰還しました",
+    lead_title: "{{sub}}はあと{{lead}}で帰還",
+    lead_body: "{{sub}}（{{owner}}）は{{return_time}}に帰還します",
+    nag_title: "{{sub}}が待機中です",
+    nag_body: "{{sub}}（{{owner}}）は{{return_time}}から帰還済みです",
+    overdue_title: "{{sub}}が長時間放置されています",
+    overdue_body: "{{sub}}（{{owner}}）は{{return_time}}から回収待ちです",
+    voyage_title: "{{sub}}{{#if others}} (+{{others}}){{/if}}が出航しました",
+    voyage_body: "{{sub}}（{{owner}}）{{#if others}}ほか{{others}}隻{{/if}}が出航しました。帰還は{{return_time}}",
+    all_home_title: "{{character}}の潜水艦がすべて帰還しました",
+    all_home_body: "{{count}}隻（{{owner}}）が帰還、最後は{{return_time}}",
+
+    digest_none_title: "今日帰還する潜水艦はありません",
+    digest_none_body: "24時間以内に帰還する潜水艦はありません",
+    digest_title: "24時間以内に{count}隻が帰還予定",
+    digest_line: "{name}（{owner}）{time}、{relative}",
+    digest_idle: "回収待ちの潜水艦{count}隻、過去24時間で合計{hours}時間待機中",
+    quiet_title: "おやすみ時間中に{count}隻が帰還しました",
+    quiet_line: "{name}（{owner}）{time}",
+    away_title: "不在の間に{count}隻が帰還しました",
+    parts: ["船体", "船尾", "船首", "艦橋"],
+    repair_title: "{sub}は修理が必要になります",
+    repair_body: "{sub}（{owner}）は{part}の耐久が約{condition}%で帰還します。再出航の前に修理してください",
+    low_tanks_title: "{owner}の青燐水が不足しています",
+    low_tanks_body: "残り{have}バレル、全艦を再出航させるには{need}バレル必要です",
+    stale_title: "潜水艦のデータが古いようです",
+    stale_body: "最後の帰還は{time}前です。SubmarineTrackerが更新されていない可能性があります（プラグインが無効、\
+                 またはゲーム未起動）",
+
+    table_header: [
+        "潜水艦", "キャラクター", "FC", "帰還", "ランク", "構成", "EXP", "ランクアップ", "耐久", "青燐水", "修理材", "航路",
+    ],
+    next: "{name}（{owner}）の帰還: {when}",
+    all_returned: "すべての潜水艦が帰還しました",
+    back: "帰還",
+    share_title: "潜水艦の帰還予定",
+    share_as_of: "{time}時点",
+    summary: "最初の帰還 {first}、全艦帰還 {last}",
+    everyone: "全員",
+    no_subs: "潜水艦が見つかりません",
+    no_subs_for: "{character}の潜水艦が見つかりません",
+    no_route: "航路の記録なし",
+
+    plan_session: "{start}〜{end}:",
+    plan_no_wait: "待ちなし",
+    plan_idle: "{time}待機",
+    plan_unplanned: "{name}（{owner}）は今後2週間のどのプレイ時間にも帰還が間に合いません",
+    plan_flagged: "(!) {time}以上待機",
+    history_none: "{time}以降、帰還も出航もありません",
+    history_sent: "出航、帰還は{time}",
+    history_returned: "帰還",
+    stats_none: "航海の記録なし",
+    stats_voyages: ["航海{count}回（週{per_week}回）", "航海{count}回（週{per_week}回）"],
+    stats_gil: "、1回あたり約{gil}ギル",
+    stats_uptime: "、航海中{percent}%",
+    stats_sectors: "。主な行き先: {sectors}",
+    event_summary: "{name}が帰還",
+    event_estimated: "推定。データベースはまだこの航海を把握していません",
+    cron_once: "どれも年に一度実行されるため、再出航したら置き換えてください",
+
+    rank: "ランク{rank}",
+    exp: "EXP {exp}/{next}",
+    to_rank: ["ランク{rank}まで航海{count}回", "ランク{rank}まで航海{count}回"],
+    to_next_rank: ["次のランクまで航海{count}回", "次のランクまで航海{count}回"],
+    part_condition: "{part}{condition}%",
+    voyages: ["{count}回", "{count}回"],
+    tanks: ["青燐水{count}", "青燐水{count}"],
+    kits: ["修理材{count}", "修理材{count}"],
+    loot_back: "{time}帰還、{route}",
+    loot_procs: ["、偵察ボーナス{count}回", "、偵察ボーナス{count}回"],
+    loot_worth: "、{gil}ギル相当",
+    loot_daily: "1日あたり約{gil}ギル",
+    loot_none: "航海の記録なし",
+};
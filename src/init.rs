@@ -0,0 +1,114 @@
+use std::{
+    fs,
+    io::{self, BufRead, IsTerminal, Write},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+
+use crate::{
+    config::Config,
+    db::{self, DB_FILE_NAME},
+    private_file,
+};
+
+/// `config init`: writes a starting config to `path`, with the database it found and
+/// whichever notifications are wanted. Asks on a terminal; otherwise it's desktop
+/// notifications only, which need nothing set up. Only desktop and bridge pushes are
+/// asked about, the other ways of notifying are left to the README.
+pub fn run(path: &Path, force: bool) -> anyhow::Result<()> {
+    if path.exists() && !force {
+        bail!("{} already exists, pass --force to replace it", path.display());
+    }
+    let interactive = io::stdin().is_terminal();
+
+    let found = db::find_db().filter(|db| db.is_file());
+    match &found {
+        Some(db) => println!("Found the SubmarineTracker database at {}", db.display()),
+        None => {
+            println!("Couldn't find a SubmarineTracker database, looked in:");
+            for folder in db::candidate_plugin_folders() {
+                println!("  {}", folder.join(DB_FILE_NAME).display());
+            }
+            println!("Set the path under [[sources]] in the config once you know where it is.");
+        }
+    }
+
+    let desktop = !interactive || ask_yes_no("Desktop notifications on this machine?", true)?;
+    let push = interactive && ask_yes_no("Push notifications to your phone through a Pushover bridge?", false)?;
+    let bridge = if push {
+        let url = ask("Bridge URL, e.g. http://server:1414/message_queue.json (blank for the built-in one):")?;
+        let psk = if url.is_empty() { String::new() } else { ask("The PSK you gave the bridge:")? };
+        (!url.is_empty()).then_some((url, psk))
+    } else {
+        None
+    };
+    if interactive {
+        println!("XMPP, Discord and KDE Connect aren't asked about here, the README has how to set them up.");
+    }
+
+    let mut config = String::from(
+        "# sub-returns settings. Everything is optional, the README has the rest of what\n\
+         # can go here.\n\n",
+    );
+    let source = found.map_or("/path/to/submarine-sqlite.db".to_string(), |db| db.display().to_string());
+    config += &format!(
+        "# Only needed with more than one game install, or a database somewhere unusual;\n\
+         # otherwise the usual places are searched.\n\
+         # [[sources]]\n\
+         # label = \"Main\"\n\
+         # path = {}\n\n",
+        quote(&source)
+    );
+    config += &format!(
+        "[notifications]\n\
+         desktop = {desktop}\n\
+         push = {push}\n\
+         # Heads-ups before a sub is back\n\
+         # lead_times = [\"30m\"]\n\
+         # Keep reminding this often until a returned sub is sent out again\n\
+         # nag_interval = \"1h\"\n\
+         # A rundown of the next day's returns every morning\n\
+         # digest_time = \"09:00\"\n"
+    );
+    if let Some((url, psk)) = bridge {
+        config += &format!("\n[bridge]\nurl = {}\npsk = {}\n", quote(&url), quote(&psk));
+    }
+    config += "\n# No notifications overnight, just a catch-up in the morning\n\
+               # [quiet_hours]\n\
+               # start = \"23:00\"\n\
+               # end = \"08:00\"\n";
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    // The bridge PSK can end up in here
+    private_file::write(path, &config).with_context(|| format!("Couldn't write {}", path.display()))?;
+    // Anything typed in goes through TOML quoting, but better to find out now than
+    // when the daemon next starts
+    Config::load(path).context("The new config doesn't load, this is a bug")?;
+    println!("Wrote {}", path.display());
+    Ok(())
+}
+
+fn quote(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+fn ask(question: &str) -> anyhow::Result<String> {
+    print!("{question} ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(answer.trim().to_string())
+}
+
+fn ask_yes_no(question: &str, default: bool) -> anyhow::Result<bool> {
+    let choices = if default { "[Y/n]" } else { "[y/N]" };
+    let answer = ask(&format!("{question} {choices}"))?.to_lowercase();
+    Ok(match answer.as_str() {
+        "y" | "yes" => true,
+        "n" | "no" => false,
+        _ => default,
+    })
+}
@@ -0,0 +1,173 @@
+use std::{
+    env, fmt, fs,
+    io::{self, BufRead, BufReader, Write},
+    path::PathBuf,
+    str::FromStr,
+    sync::mpsc::{self, Sender},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context};
+use clap::ValueEnum;
+use interprocess::local_socket::{
+    prelude::*, GenericFilePath, GenericNamespaced, Listener, ListenerOptions, Name, Stream,
+};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::data_dir;
+
+/// Commands the running daemon accepts over its control socket, one per line.
+/// Every command is answered with a single line of JSON.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq)]
+pub enum ControlCommand {
+    /// Stop sending desktop and push notifications
+    Pause,
+    /// Start sending notifications again
+    Resume,
+    /// Re-read config.toml
+    ReloadConfig,
+    /// Print the daemon's notification bookkeeping as JSON
+    DumpState,
+    /// Summarise what the daemon is up to
+    Status,
+}
+
+impl fmt::Display for ControlCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = self.to_possible_value().expect("no skipped variants");
+        f.write_str(name.get_name())
+    }
+}
+
+impl FromStr for ControlCommand {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        <ControlCommand as ValueEnum>::from_str(s.trim(), true).map_err(|_| anyhow!("Unknown command '{}'", s.trim()))
+    }
+}
+
+pub struct ControlRequest {
+    pub command: ControlCommand,
+    reply: Sender<Value>,
+}
+
+impl ControlRequest {
+    pub fn reply(self, response: Value) {
+        // The client may have given up waiting, which is its problem
+        let _ = self.reply.send(response);
+    }
+}
+
+/// Named pipe on Windows, a socket file elsewhere: in the runtime dir when there is one,
+/// since that's per user and cleared at logout, otherwise the data dir. Either way each
+/// user's daemon gets its own, so `ctl` never reaches somebody else's.
+fn socket_name() -> io::Result<Name<'static>> {
+    if cfg!(windows) {
+        // Pipe names are machine-wide
+        let user = env::var("USERNAME").unwrap_or_default();
+        return format!("sub-returns-{user}.sock").to_ns_name::<GenericNamespaced>();
+    }
+    socket_path()?.into_os_string().to_fs_name::<GenericFilePath>()
+}
+
+fn socket_path() -> io::Result<PathBuf> {
+    match env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => Ok(PathBuf::from(dir).join("sub-returns.sock")),
+        None => {
+            let dir = data_dir();
+            fs::create_dir_all(&dir)?;
+            Ok(dir.join("daemon.sock"))
+        }
+    }
+}
+
+/// The socket is only ever ours to use, and says so in its permissions where the OS lets us
+fn listen() -> io::Result<Listener> {
+    // The instance lock guarantees nobody else owns the name, so a leftover is ours to take
+    let name = socket_name()?;
+    let options = || ListenerOptions::new().name(name.clone()).try_overwrite(true);
+    #[cfg(unix)]
+    {
+        use interprocess::os::unix::local_socket::ListenerOptionsExt;
+        use std::os::unix::fs::PermissionsExt;
+
+        match options().mode(0o600).create_sync() {
+            Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+                let listener = options().create_sync()?;
+                fs::set_permissions(socket_path()?, fs::Permissions::from_mode(0o600))?;
+                Ok(listener)
+            }
+            result => result,
+        }
+    }
+    #[cfg(not(unix))]
+    options().create_sync()
+}
+
+/// Whether whoever connected is running as us. The permissions should already see to
+/// that, but not every OS honours them on sockets.
+#[cfg(unix)]
+fn is_ours(conn: &Stream) -> bool {
+    // SAFETY: geteuid has no preconditions and can't fail
+    let uid = unsafe { libc::geteuid() };
+    conn.peer_creds().ok().and_then(|creds| creds.euid()) == Some(uid)
+}
+
+/// Named pipes only let their creator and administrators write to them by default
+#[cfg(not(unix))]
+fn is_ours(_conn: &Stream) -> bool {
+    true
+}
+
+/// Starts accepting control connections on a background thread. Requests are handed to
+/// the daemon loop through `requests`, which answers them between iterations.
+pub fn serve(requests: Sender<ControlRequest>) -> io::Result<()> {
+    let listener = listen()?;
+    thread::spawn(move || {
+        for conn in listener.incoming().filter_map(Result::ok) {
+            if !is_ours(&conn) {
+                warn!("refused a control connection from another user");
+                continue;
+            }
+            // Each on its own thread, so a client that never sends its line holds up nobody else
+            let requests = requests.clone();
+            thread::spawn(move || handle(conn, &requests));
+        }
+    });
+    Ok(())
+}
+
+fn handle(conn: Stream, requests: &Sender<ControlRequest>) {
+    let _ = conn.set_recv_timeout(Some(Duration::from_secs(5)));
+    let mut conn = BufReader::new(conn);
+    let mut line = String::new();
+    if conn.read_line(&mut line).is_err() {
+        return;
+    }
+    let response = match line.parse::<ControlCommand>() {
+        Ok(command) => {
+            let (reply, response) = mpsc::channel();
+            if requests.send(ControlRequest { command, reply }).is_err() {
+                return;
+            }
+            response
+                .recv_timeout(Duration::from_secs(5))
+                .unwrap_or_else(|_| json!({"ok": false, "error": "daemon didn't respond"}))
+        }
+        Err(e) => json!({"ok": false, "error": e.to_string()}),
+    };
+    let _ = writeln!(conn.get_mut(), "{}", response);
+}
+
+/// Sends one command to the running daemon and returns its response.
+pub fn send(command: ControlCommand) -> anyhow::Result<Value> {
+    let stream = Stream::connect(socket_name()?).context("Couldn't reach the daemon, is it running?")?;
+    let mut conn = BufReader::new(stream);
+    writeln!(conn.get_mut(), "{}", command)?;
+    let mut line = String::new();
+    conn.read_line(&mut line)?;
+    serde_json::from_str(&line).context("Garbled response from the daemon")
+}
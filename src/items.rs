@@ -0,0 +1,64 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use reqwest::blocking::Client;
+use tracing::warn;
+
+use crate::{data_dir, xivapi};
+
+/// Item names by id, looked up on XIVAPI the first time they're needed and kept on
+/// disk after that, since they only change with game patches.
+pub struct ItemNames {
+    path: PathBuf,
+    names: HashMap<u32, String>,
+}
+
+impl ItemNames {
+    pub fn load() -> Self {
+        let path = data_dir().join("item-names.json");
+        let names = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        ItemNames { path, names }
+    }
+
+    /// Looks up whichever of `ids` aren't known yet. Failing just leaves them as ids.
+    pub fn fetch(&mut self, client: &Client, ids: impl IntoIterator<Item = u32>) {
+        let mut missing: Vec<u32> = ids.into_iter().filter(|id| !self.names.contains_key(id)).collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return;
+        }
+        match fetch_names(client, &missing) {
+            Ok(found) => {
+                self.names.extend(found);
+                if let Err(e) = self.save() {
+                    warn!(error = format!("{e:#}"), "couldn't save item names");
+                }
+            }
+            Err(e) => warn!(error = format!("{e:#}"), "couldn't look up item names"),
+        }
+    }
+
+    pub fn name(&self, id: u32) -> String {
+        self.names.get(&id).cloned().unwrap_or_else(|| format!("item {id}"))
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.names)?)?;
+        Ok(())
+    }
+}
+
+fn fetch_names(client: &Client, ids: &[u32]) -> anyhow::Result<HashMap<u32, String>> {
+    let rows = xivapi::fetch_rows(client, "Item", &["Name"], ids)?;
+    let names = rows
+        .into_iter()
+        .filter_map(|(id, fields)| Some((id, fields["Name"].as_str()?.to_string())))
+        .collect();
+    Ok(names)
+}
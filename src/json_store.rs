@@ -0,0 +1,116 @@
+use std::{fs, path::Path};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde_json::Value;
+
+use crate::db::SubInfo;
+
+/// Reads the per-FC JSON files older SubmarineTracker releases (and some forks) keep
+/// in the plugin folder instead of the sqlite database. Files that don't look like FC
+/// data are skipped rather than treated as errors, since the folder holds other JSON too.
+pub fn read_folder(folder: &Path) -> anyhow::Result<Vec<SubInfo>> {
+    let mut subs = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(fc) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+        else {
+            continue;
+        };
+        let fc_key = path.file_stem().unwrap_or_default().to_string_lossy();
+        subs.extend(parse_fc(&fc_key, &fc));
+    }
+    subs.sort_by_key(|sub| sub.return_time);
+    Ok(subs)
+}
+
+/// True if the folder has at least one file `read_folder` would get subs out of.
+pub fn has_fc_files(folder: &Path) -> bool {
+    read_folder(folder).is_ok_and(|subs| !subs.is_empty())
+}
+
+fn parse_fc(fc_key: &str, fc: &Value) -> Vec<SubInfo> {
+    let character_name = string_field(fc, &["CharacterName", "Character", "Name"]);
+    let tag = string_field(fc, &["FreeCompanyTag", "Tag"]);
+    let count = |names: &[&str]| field(fc, names).and_then(Value::as_u64).map(|count| count as u32);
+    let tanks = count(&["Tanks", "CeruleumTanks"]);
+    let kits = count(&["Kits", "RepairKits"]);
+    let Some(submarines) = field(fc, &["Submarines", "Subs"]).and_then(Value::as_array) else {
+        return Vec::new();
+    };
+    submarines
+        .iter()
+        .enumerate()
+        .filter_map(|(slot, sub)| {
+            Some(SubInfo {
+                // There's no submarine id in these files, but an FC's slots don't move
+                id: stable_id(fc_key, slot),
+                name: string_field(sub, &["Name"]),
+                return_time: return_time(field(sub, &["Return", "ReturnTime"])?)?,
+                tag: tag.clone(),
+                character_name: character_name.clone(),
+                source: None,
+                route: field(sub, &["Points", "Route"])
+                    .and_then(Value::as_array)
+                    .map(|points| points.iter().filter_map(|p| p.as_u64()).map(|p| p as u32).collect())
+                    .unwrap_or_default(),
+                rank: number_field(sub, &["Rank"]),
+                parts: [
+                    number_field(sub, &["Hull", "HullId"]),
+                    number_field(sub, &["Stern", "SternId"]),
+                    number_field(sub, &["Bow", "BowId"]),
+                    number_field(sub, &["Bridge", "BridgeId"]),
+                ],
+                exp: number_field(sub, &["CExp", "CurrentExp"]),
+                next_exp: number_field(sub, &["NExp", "NextLevelExp"]),
+                condition: ["Hull", "Stern", "Bow", "Bridge"]
+                    .map(|part| field(sub, &[&format!("{part}Durability"), &format!("{part}Condition")]))
+                    .into_iter()
+                    .map(|value| value.and_then(Value::as_u64).map(|value| value as u32))
+                    .collect::<Option<Vec<u32>>>()
+                    .and_then(|condition| condition.try_into().ok()),
+                tanks,
+                kits,
+                estimated: false,
+            })
+        })
+        .collect()
+}
+
+fn field<'a>(value: &'a Value, names: &[&str]) -> Option<&'a Value> {
+    names.iter().find_map(|name| value.get(name))
+}
+
+fn string_field(value: &Value, names: &[&str]) -> String {
+    field(value, names)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+fn number_field(value: &Value, names: &[&str]) -> u32 {
+    field(value, names).and_then(Value::as_u64).unwrap_or_default() as u32
+}
+
+/// Unix seconds in most versions, an RFC 3339 string in a few.
+fn return_time(value: &Value) -> Option<DateTime<Utc>> {
+    match value {
+        Value::Number(n) => Utc.timestamp_opt(n.as_i64()?, 0).single(),
+        Value::String(s) => DateTime::parse_from_rfc3339(s).ok().map(|t| t.with_timezone(&Utc)),
+        _ => None,
+    }
+}
+
+// FNV-1a, so ids survive across builds (the daemon persists state keyed on them)
+fn stable_id(fc_key: &str, slot: usize) -> i64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in fc_key.bytes().chain(slot.to_le_bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    (hash >> 1) as i64
+}
@@ -0,0 +1,53 @@
+use std::{io, process::Command};
+
+use anyhow::{bail, Context};
+
+use crate::{config::KdeConnectConfig, notifier::Notifier};
+
+/// Pings on phones paired with KDE Connect, through its `kdeconnect-cli`: straight
+/// over the local network, with no server in between
+pub struct KdeConnect {
+    config: KdeConnectConfig,
+}
+
+impl KdeConnect {
+    pub fn new(config: KdeConnectConfig) -> Self {
+        KdeConnect { config }
+    }
+}
+
+impl Notifier for KdeConnect {
+    fn name(&self) -> &str {
+        "kdeconnect"
+    }
+
+    /// Just the body, a ping has no title of its own
+    fn send(&mut self, _title: &str, body: &str) -> anyhow::Result<()> {
+        let devices = if self.config.devices.is_empty() {
+            let listed = cli(&["--list-available", "--id-only"])?;
+            listed.lines().map(str::trim).filter(|id| !id.is_empty()).map(String::from).collect()
+        } else {
+            self.config.devices.clone()
+        };
+        if devices.is_empty() {
+            bail!("No paired device is reachable");
+        }
+        for device in &devices {
+            cli(&["--device", device, "--ping-msg", body]).with_context(|| format!("Couldn't ping {device}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// What `kdeconnect-cli` printed, or what it complained about if it failed
+fn cli(args: &[&str]) -> anyhow::Result<String> {
+    let output = match Command::new("kdeconnect-cli").args(args).output() {
+        Ok(output) => output,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => bail!("kdeconnect-cli isn't installed"),
+        Err(e) => return Err(e.into()),
+    };
+    if !output.status.success() {
+        bail!("kdeconnect-cli failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
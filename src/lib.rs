@@ -0,0 +1,51 @@
+//! Reading [SubmarineTracker](https://github.com/Infiziert90/SubmarineTracker)'s data
+//! and working out when subs are back, the part of `sub-returns` worth embedding in
+//! something else:
+//!
+//! ```no_run
+//! let source = sub_returns::db::SubSource::open()?;
+//! for sub in sub_returns::db::get_submarine_info(&source)? {
+//!     println!("{} ({}) is back at {}", sub.name, sub.owner(), sub.return_time);
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use std::{env, path::PathBuf};
+
+use iana_time_zone::get_timezone;
+
+pub mod config;
+pub mod db;
+pub mod diff;
+pub mod duration;
+pub mod filter;
+pub mod i18n;
+mod json_store;
+mod remote;
+pub mod schedule;
+pub mod timefmt;
+
+fn project_dirs() -> Option<directories::ProjectDirs> {
+    directories::ProjectDirs::from("", "", "sub-returns")
+}
+
+/// Where sub-returns keeps its state and caches
+pub fn data_dir() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.data_dir().to_path_buf())
+        .unwrap_or_else(env::temp_dir)
+}
+
+/// The config file sub-returns reads, which doesn't have to exist
+pub fn config_path() -> PathBuf {
+    project_dirs()
+        .map(|dirs| dirs.config_dir().join("config.toml"))
+        .unwrap_or_else(|| PathBuf::from("config.toml"))
+}
+
+/// The local timezone's IANA name
+pub fn local_timezone() -> Option<String> {
+    // first check for TZ since upstream doesn't
+    let env_tz = env::var("TZ").ok();
+    env_tz.or(get_timezone().ok())
+}
@@ -0,0 +1,538 @@
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde_json::json;
+use unicode_width::UnicodeWidthStr;
+
+use crate::{
+    config::GroupOrder,
+    db::SubInfo,
+    i18n::{fill, Messages},
+    parts, progress, repairs, resources, routes,
+    sectors::{self, Sectors},
+    template::{sub_context, Template},
+    theme::Theme,
+    timefmt::TimeDisplay,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// One row per sub, with columns for the character and FC
+    Table,
+    Json,
+    /// Return times as Unix seconds, tab-separated from the rest; with `--next`, just
+    /// the number
+    Unix,
+    /// A line of JSON for a waybar custom module, counting down to the next return in
+    /// the configured language
+    Waybar,
+}
+
+/// Which listing to show
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum View {
+    #[default]
+    List,
+    Next,
+    Summary,
+    /// A line of subs and time left per character
+    Oneline,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum SortKey {
+    /// Soonest back first
+    #[default]
+    Return,
+    Name,
+    Character,
+}
+
+/// Everything that decides how the listing looks
+pub struct Output {
+    pub view: View,
+    pub display: TimeDisplay,
+    pub format: OutputFormat,
+    pub theme: Theme,
+    pub group_order: GroupOrder,
+    pub sort: SortKey,
+    pub reverse: bool,
+    /// Replaces the usual layout with one rendered line per sub
+    pub template: Option<Template>,
+    /// Add each sub's route to the list and table
+    pub show_route: bool,
+    /// Add each sub's rank, build, EXP and condition, and its FC's tanks and kits, to
+    /// the list and table
+    pub show_details: bool,
+    /// For working out voyages to the next rank
+    pub sectors: Sectors,
+    /// Add a bar for how far along each sub's voyage is to the list and table
+    pub show_progress: bool,
+    /// For the bars
+    pub departures: progress::Departures,
+}
+
+pub fn write(out: &mut dyn Write, subs: Vec<SubInfo>, output: &Output) -> anyhow::Result<()> {
+    if output.format == OutputFormat::Waybar {
+        return write_waybar(out, &subs, output);
+    }
+    match output.view {
+        View::List => write_list(out, subs, output),
+        View::Next => write_next(out, &subs, output),
+        View::Summary => write_summary(out, &subs, output),
+        View::Oneline => write_oneline(out, subs, output),
+    }
+}
+
+pub fn write_list(out: &mut dyn Write, mut subs: Vec<SubInfo>, output: &Output) -> anyhow::Result<()> {
+    sort_subs(&mut subs, output.sort);
+    if output.reverse {
+        subs.reverse();
+    }
+    if output.format == OutputFormat::Json {
+        writeln!(out, "{}", serde_json::to_string_pretty(&subs)?)?;
+        return Ok(());
+    }
+    if let Some(template) = &output.template {
+        let now = Utc::now();
+        for sub in &subs {
+            writeln!(out, "{}", template.render(&sub_context(sub, &output.display, now))?)?;
+        }
+        return Ok(());
+    }
+    if output.format == OutputFormat::Table {
+        return write_table(out, &subs, output);
+    }
+    if output.format == OutputFormat::Unix {
+        for sub in &subs {
+            writeln!(out, "{}\t{}\t{}", sub.return_time.timestamp(), sub.name, sub.owner())?;
+        }
+        return Ok(());
+    }
+    let longest_name = subs.iter().map(|s| s.name.width()).max().unwrap_or(0);
+    let subs_by_char = group_by_owner(subs, output);
+    let now = Utc::now();
+    let text = output.display.text();
+    for (char, subs) in subs_by_char {
+        let stock = match subs.first().and_then(|sub| resources::describe(sub, text)) {
+            Some(stock) if output.show_details => format!(" ({stock})"),
+            _ => String::new(),
+        };
+        writeln!(out, "{char}{stock}:")?;
+        for sub in subs {
+            let padding = " ".repeat(longest_name - sub.name.width());
+            let when = format!(
+                "{} ({}{})",
+                output.display.long(sub.return_time),
+                output.display.relative(sub.return_time, now),
+                if sub.estimated { format!(", {}", text.estimated) } else { String::new() }
+            );
+            let urgency = output.theme.urgency(sub.return_time, now);
+            let when = output.theme.paint(urgency, &when);
+            let route = match routes::describe(&sub.route) {
+                route if output.show_route && !route.is_empty() => format!(" [{route}]"),
+                _ => String::new(),
+            };
+            let details = if output.show_details {
+                format!(" {}", details(&sub, &output.sectors, text))
+            } else {
+                String::new()
+            };
+            let marker = output.theme.marker(urgency);
+            let bar =
+                if output.show_progress { format!("{} ", progress_bar(&sub, output, now)) } else { String::new() };
+            writeln!(out, "  {marker}{name}:{padding} {bar}{when}{details}{route}", name = sub.name)?;
+        }
+    }
+    Ok(())
+}
+
+/// How far along `sub` is, full once it's back and blank if it can't be told
+fn progress_bar(sub: &SubInfo, output: &Output, now: DateTime<Utc>) -> String {
+    match output.departures.get(sub) {
+        Some(departed) => output.theme.bar(progress::done(departed, sub.return_time, now)),
+        None if sub.return_time <= now => output.theme.bar(1.0),
+        None => " ".repeat(output.theme.bar(0.0).width()),
+    }
+}
+
+/// "rank 50 WSUC, 1200/5000 exp, 2 voyages to rank 51", with whatever the database
+/// didn't record left out
+fn details(sub: &SubInfo, sectors: &Sectors, text: &Messages) -> String {
+    let mut details = match sub.rank {
+        0 => parts::build_code(&sub.parts),
+        rank => format!("{} {}", fill(text.rank, &[("rank", &rank.to_string())]), parts::build_code(&sub.parts)),
+    };
+    if sub.next_exp > 0 {
+        let (exp, next) = (sub.exp.to_string(), sub.next_exp.to_string());
+        details += &format!(", {}", fill(text.exp, &[("exp", &exp), ("next", &next)]));
+    }
+    if let Some(voyages) = sectors::voyages_to_rank(sub, sectors) {
+        details += &format!(", {}", to_rank(sub, voyages, text));
+    }
+    if let Some((part, condition)) = repairs::worst_part(sub, text) {
+        let condition = format!("{condition:.0}");
+        details += &format!(", {}", fill(text.part_condition, &[("part", part), ("condition", &condition)]));
+    }
+    details
+}
+
+/// "2 voyages to rank 51"
+fn to_rank(sub: &SubInfo, voyages: u32, text: &Messages) -> String {
+    let count = voyages.to_string();
+    match sub.rank {
+        0 => fill(text.plural(text.to_next_rank, voyages as usize), &[("count", &count)]),
+        rank => {
+            let next = (rank + 1).to_string();
+            fill(text.plural(text.to_rank, voyages as usize), &[("count", &count), ("rank", &next)])
+        }
+    }
+}
+
+fn sort_subs(subs: &mut [SubInfo], key: SortKey) {
+    match key {
+        SortKey::Return => {
+            subs.sort_by(|a, b| a.return_time.cmp(&b.return_time).then_with(|| a.name.cmp(&b.name)))
+        }
+        SortKey::Name => subs.sort_by_key(|sub| (sub.name.to_lowercase(), sub.return_time)),
+        SortKey::Character => subs.sort_by_key(|sub| (sub.owner().to_lowercase(), sub.return_time)),
+    }
+}
+
+/// Subs grouped by character, in a stable order so the output can be diffed. Subs keep
+/// the order they came in within their character.
+fn group_by_owner(subs: Vec<SubInfo>, output: &Output) -> Vec<(String, Vec<SubInfo>)> {
+    let mut groups: Vec<(String, Vec<SubInfo>)> = Vec::new();
+    for sub in subs {
+        let owner = sub.owner();
+        match groups.iter_mut().find(|(existing, _)| *existing == owner) {
+            Some((_, group)) => group.push(sub),
+            None => groups.push((owner, vec![sub])),
+        }
+    }
+    if output.sort == SortKey::Character || output.group_order == GroupOrder::Alphabetical {
+        groups.sort_by_key(|(owner, _)| owner.to_lowercase());
+    } else {
+        groups.sort_by_key(|(_, group)| group.iter().map(|sub| sub.return_time).min());
+    }
+    if output.reverse {
+        groups.reverse();
+    }
+    groups
+}
+
+fn write_table(out: &mut dyn Write, subs: &[SubInfo], output: &Output) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let text = output.display.text();
+    let mut header = text.table_header[..4].to_vec();
+    header.push("");
+    if output.show_progress {
+        header.push("");
+    }
+    if output.show_details {
+        header.extend(&text.table_header[4..11]);
+    }
+    if output.show_route {
+        header.push(text.table_header[11]);
+    }
+    let header: Vec<String> = header.into_iter().map(String::from).collect();
+    let rows: Vec<Vec<String>> = subs
+        .iter()
+        .map(|sub| {
+            let character = match &sub.source {
+                Some(label) => format!("{} @ {label}", sub.character_name),
+                None => sub.character_name.clone(),
+            };
+            let marker = output.theme.marker(output.theme.urgency(sub.return_time, now));
+            let mut row = vec![
+                format!("{marker}{}", sub.name),
+                character,
+                format!("«{}»", sub.tag),
+                output.display.long(sub.return_time).trim_start().to_string(),
+                if sub.estimated {
+                    format!("{} ({})", output.display.relative(sub.return_time, now), text.estimated)
+                } else {
+                    output.display.relative(sub.return_time, now)
+                },
+            ];
+            if output.show_progress {
+                row.push(progress_bar(sub, output, now));
+            }
+            if output.show_details {
+                row.push(match sub.rank {
+                    0 => String::new(),
+                    rank => rank.to_string(),
+                });
+                row.push(parts::build_code(&sub.parts));
+                row.push(match sub.next_exp {
+                    0 => String::new(),
+                    next => format!("{}/{next}", sub.exp),
+                });
+                row.push(
+                    sectors::voyages_to_rank(sub, &output.sectors)
+                        .map_or(String::new(), |voyages| {
+                            fill(text.plural(text.voyages, voyages as usize), &[("count", &voyages.to_string())])
+                        }),
+                );
+                row.push(
+                    repairs::worst_part(sub, text)
+                        .map_or(String::new(), |(part, condition)| format!("{condition:.0}% ({part})")),
+                );
+                let count = |count: Option<u32>| count.map_or(String::new(), |count| count.to_string());
+                row.extend([count(sub.tanks), count(sub.kits)]);
+            }
+            if output.show_route {
+                row.push(routes::describe(&sub.route));
+            }
+            row
+        })
+        .collect();
+
+    let mut widths = vec![0; header.len()];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.width());
+        }
+    }
+    let pad = |cell: &str, width: usize| format!("{cell}{}", " ".repeat(width - cell.width()));
+
+    let borders = output.theme.borders();
+    let rule = |[left, between, right]: [char; 3], horizontal: char| {
+        let lines: Vec<String> = widths.iter().map(|width| horizontal.to_string().repeat(width + 2)).collect();
+        format!("{left}{}{right}", lines.join(&between.to_string()))
+    };
+    // Cells between lines, or two spaces apart without them
+    let line = |cells: &[String]| match borders {
+        Some(borders) => {
+            let vertical = borders.vertical;
+            format!("{vertical} {} {vertical}", cells.join(&format!(" {vertical} ")))
+        }
+        None => cells.join("  ").trim_end().to_string(),
+    };
+
+    let header: Vec<String> = header.iter().zip(&widths).map(|(cell, width)| pad(cell, *width)).collect();
+    if let Some(borders) = borders {
+        writeln!(out, "{}", rule(borders.top, borders.horizontal))?;
+    }
+    writeln!(out, "{}", line(&header))?;
+    if let Some(borders) = borders {
+        writeln!(out, "{}", rule(borders.middle, borders.horizontal))?;
+    }
+    for (sub, row) in subs.iter().zip(&rows) {
+        let urgency = output.theme.urgency(sub.return_time, now);
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .enumerate()
+            .map(|(column, (cell, width))| {
+                // Colour the times but not the padding, which is worked out on the plain
+                // text so escape codes don't throw the columns off
+                let padding = " ".repeat(width - cell.width());
+                if column == 3 || column == 4 {
+                    format!("{}{padding}", output.theme.paint(urgency, cell))
+                } else {
+                    format!("{cell}{padding}")
+                }
+            })
+            .collect();
+        writeln!(out, "{}", line(&cells))?;
+    }
+    if let Some(borders) = borders {
+        writeln!(out, "{}", rule(borders.bottom, borders.horizontal))?;
+    }
+    Ok(())
+}
+
+/// Per character, when the first sub is back and when they all are, then the same
+/// across everyone.
+pub fn write_summary(out: &mut dyn Write, subs: &[SubInfo], output: &Output) -> anyhow::Result<()> {
+    let mut owners: Vec<(String, DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    // Sorted by return time, so the first seen is the earliest
+    for sub in subs {
+        match owners.iter_mut().find(|(owner, _, _)| *owner == sub.owner()) {
+            Some((_, _, last)) => *last = sub.return_time,
+            None => owners.push((sub.owner(), sub.return_time, sub.return_time)),
+        }
+    }
+    let overall = subs
+        .first()
+        .zip(subs.last())
+        .map(|(first, last)| (first.return_time, last.return_time));
+
+    if output.format == OutputFormat::Json {
+        let characters: Vec<_> = owners
+            .iter()
+            .map(|(owner, first, last)| json!({"owner": owner, "first_return": first, "all_back": last}))
+            .collect();
+        let value = json!({
+            "characters": characters,
+            "first_return": overall.map(|(first, _)| first),
+            "all_back": overall.map(|(_, last)| last),
+        });
+        writeln!(out, "{}", serde_json::to_string_pretty(&value)?)?;
+        return Ok(());
+    }
+    let text = output.display.text();
+    if output.format == OutputFormat::Unix {
+        let everyone = overall.map(|(first, last)| (text.everyone.to_string(), first, last));
+        for (owner, first, last) in owners.iter().cloned().chain(everyone) {
+            writeln!(out, "{}\t{}\t{owner}", first.timestamp(), last.timestamp())?;
+        }
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let describe = |first: DateTime<Utc>, last: DateTime<Utc>| {
+        fill(
+            text.summary,
+            &[
+                ("first", &describe_time(first, now, &output.display)),
+                ("last", &describe_time(last, now, &output.display)),
+            ],
+        )
+    };
+    for (owner, first, last) in &owners {
+        writeln!(out, "{owner}: {}", describe(*first, *last))?;
+    }
+    match overall {
+        Some((first, last)) => writeln!(out, "{}: {}", text.everyone, describe(first, last))?,
+        None => writeln!(out, "{}", text.no_subs)?,
+    }
+    Ok(())
+}
+
+/// `Sub I 2h13m · Sub II 5h01m · Sub III returned` for each character, named first
+/// when there's more than one, for status lines with no room for more
+pub fn write_oneline(out: &mut dyn Write, mut subs: Vec<SubInfo>, output: &Output) -> anyhow::Result<()> {
+    sort_subs(&mut subs, output.sort);
+    if output.reverse {
+        subs.reverse();
+    }
+    let mut owners: Vec<String> = Vec::new();
+    for sub in &subs {
+        if !owners.contains(&sub.owner()) {
+            owners.push(sub.owner());
+        }
+    }
+    if owners.is_empty() {
+        writeln!(out, "{}", output.display.text().no_subs)?;
+    }
+    let now = Utc::now();
+    for owner in &owners {
+        let line: Vec<String> = subs
+            .iter()
+            .filter(|sub| sub.owner() == *owner)
+            .map(|sub| {
+                let urgency = output.theme.urgency(sub.return_time, now);
+                let left = if sub.return_time <= now {
+                    output.display.text().back.to_string()
+                } else {
+                    compact_duration(sub.return_time - now)
+                };
+                format!("{} {}", sub.name, output.theme.paint(urgency, &left))
+            })
+            .collect();
+        let line = line.join(output.theme.separator());
+        if owners.len() > 1 {
+            writeln!(out, "{owner}: {line}")?;
+        } else {
+            writeln!(out, "{line}")?;
+        }
+    }
+    Ok(())
+}
+
+/// `2h13m`, `1d04h` or `13m`, rounded up so a sub still out never says 0m
+fn compact_duration(left: chrono::TimeDelta) -> String {
+    let mins = (left.num_seconds() + 59) / 60;
+    let (days, hours, mins) = (mins / 1440, mins % 1440 / 60, mins % 60);
+    if days > 0 {
+        format!("{days}d{hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h{mins:02}m")
+    } else {
+        format!("{mins}m")
+    }
+}
+
+fn describe_time(time: DateTime<Utc>, now: DateTime<Utc>, display: &TimeDisplay) -> String {
+    format!("{} ({})", display.short(time), display.relative(time, now))
+}
+
+/// waybar's `{text, tooltip, class}`: the next sub back and how long until it is, with
+/// every sub in the tooltip. The class (and `alt`, for format-icons) is `returned`
+/// while any sub is home, `out` otherwise.
+fn write_waybar(out: &mut dyn Write, subs: &[SubInfo], output: &Output) -> anyhow::Result<()> {
+    let now = Utc::now();
+    // Already sorted by return time
+    let text = match subs.iter().find(|sub| sub.return_time > now) {
+        Some(sub) => format!("{} {}", sub.name, output.display.relative(sub.return_time, now)),
+        None => output.display.text().all_returned.to_string(),
+    };
+    let tooltip: Vec<String> = subs
+        .iter()
+        .map(|sub| format!("{} ({}): {}", sub.name, sub.owner(), output.display.relative(sub.return_time, now)))
+        .collect();
+    let class = if subs.iter().any(|sub| sub.return_time <= now) { "returned" } else { "out" };
+    // waybar takes Pango markup in both
+    let value = json!({
+        "text": escape_markup(&text),
+        "tooltip": escape_markup(&tooltip.join("\n")),
+        "class": class,
+        "alt": class,
+    });
+    writeln!(out, "{value}")?;
+    Ok(())
+}
+
+fn escape_markup(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Just the soonest sub that's still out, for prompts and status bars.
+pub fn write_next(out: &mut dyn Write, subs: &[SubInfo], output: &Output) -> anyhow::Result<()> {
+    let now = Utc::now();
+    // Already sorted by return time
+    let next = subs.iter().find(|sub| sub.return_time > now);
+    match output.format {
+        OutputFormat::Json => {
+            let value = next.map(|sub| {
+                json!({
+                    "name": sub.name,
+                    "character": sub.character_name,
+                    "tag": sub.tag,
+                    "source": sub.source,
+                    "return_time": sub.return_time,
+                    "relative": output.display.relative(sub.return_time, now),
+                })
+            });
+            writeln!(out, "{}", serde_json::to_string_pretty(&value)?)?;
+        }
+        // Nothing at all once everything's back, rather than a number that's already
+        // passed
+        OutputFormat::Unix => {
+            if let Some(sub) = next {
+                writeln!(out, "{}", sub.return_time.timestamp())?;
+            }
+        }
+        OutputFormat::Waybar => write_waybar(out, subs, output)?,
+        OutputFormat::Text | OutputFormat::Table => match next {
+            Some(sub) => {
+                let when = format!(
+                    "{}, {}",
+                    output.display.long(sub.return_time).trim_start(),
+                    output.display.relative(sub.return_time, now)
+                );
+                let when = output.theme.paint(output.theme.urgency(sub.return_time, now), &when);
+                let line =
+                    fill(output.display.text().next, &[("name", &sub.name), ("owner", &sub.owner()), ("when", &when)]);
+                writeln!(out, "{line}")?;
+            }
+            None => writeln!(out, "{}", output.display.text().all_returned)?,
+        },
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use std::{
+    fs::{self, File, OpenOptions, TryLockError},
+    path::{Path, PathBuf},
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::bail;
+
+/// Held for as long as the daemon runs so a second `--daemon` can't double-notify.
+/// The OS drops the lock if the process dies, so there's no stale pidfile cleanup to do.
+pub struct InstanceLock {
+    _file: File,
+    pid_path: PathBuf,
+}
+
+impl InstanceLock {
+    pub fn acquire(dir: &Path, replace: bool) -> anyhow::Result<Self> {
+        fs::create_dir_all(dir)?;
+        // The pid lives in its own file because Windows won't let anyone read a locked one
+        let pid_path = dir.join("daemon.pid");
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(dir.join("daemon.lock"))?;
+
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let pid = fs::read_to_string(&pid_path).unwrap_or_default();
+                let pid = pid.trim();
+                if !replace {
+                    bail!("The daemon is already running (pid {pid}). Stop it first, or pass --replace to take over");
+                }
+                terminate(pid)?;
+                wait_for_lock(&file)?;
+            }
+            Err(TryLockError::Error(e)) => return Err(e.into()),
+        }
+
+        fs::write(&pid_path, std::process::id().to_string())?;
+        Ok(InstanceLock { _file: file, pid_path })
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.pid_path);
+    }
+}
+
+fn terminate(pid: &str) -> anyhow::Result<()> {
+    if pid.is_empty() {
+        bail!("The daemon is already running but its pid is unknown, stop it manually");
+    }
+    // A console process, or one that let go of its console for --hidden, only stops for
+    // /F. It can't save anything on the way out, so it's left with what the last pass saved.
+    #[cfg(windows)]
+    let status = Command::new("taskkill").args(["/F", "/PID", pid]).status()?;
+    #[cfg(not(windows))]
+    let status = Command::new("kill").args(["-TERM", pid]).status()?;
+    if !status.success() {
+        bail!("Couldn't stop the running daemon (pid {pid})");
+    }
+    Ok(())
+}
+
+fn wait_for_lock(file: &File) -> anyhow::Result<()> {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while Instant::now() < deadline {
+        if file.try_lock().is_ok() {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+    bail!("Timed out waiting for the running daemon to exit")
+}
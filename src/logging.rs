@@ -0,0 +1,63 @@
+use std::{env, io, path::Path};
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::{filter::LevelFilter, fmt, prelude::*, EnvFilter};
+
+/// Sets up the global subscriber. `RUST_LOG` wins if set; otherwise each `--verbose`
+/// raises our own crate's level by one step above the default (warnings in release
+/// builds, debug output in debug builds). Under systemd events go straight to the
+/// journal with proper priorities and fields instead of stderr.
+///
+/// The returned guard flushes the log file when dropped, so keep it alive.
+pub fn init(verbosity: u8, log_file: Option<&Path>) -> anyhow::Result<Option<WorkerGuard>> {
+    let filter = match env::var("RUST_LOG") {
+        Ok(directives) => EnvFilter::try_new(directives)?,
+        Err(_) => {
+            let default = if cfg!(debug_assertions) { 3 } else { 1 };
+            let level = match default + verbosity {
+                0 => LevelFilter::ERROR,
+                1 => LevelFilter::WARN,
+                2 => LevelFilter::INFO,
+                3 => LevelFilter::DEBUG,
+                _ => LevelFilter::TRACE,
+            };
+            EnvFilter::new(format!("warn,sub_returns={level}"))
+        }
+    };
+
+    // Rolled daily, so the file never grows without bound and old ones can just be deleted
+    let (file_layer, guard) = match log_file {
+        Some(path) => {
+            let dir = path.parent().unwrap_or(Path::new("."));
+            let name = path.file_name().unwrap_or("sub-returns.log".as_ref());
+            let (writer, guard) = tracing_appender::non_blocking(tracing_appender::rolling::daily(dir, name));
+            (Some(fmt::layer().with_ansi(false).with_writer(writer)), Some(guard))
+        }
+        None => (None, None),
+    };
+
+    let journald_layer = journald_layer();
+    let stderr_layer = journald_layer
+        .is_none()
+        .then(|| fmt::layer().with_writer(io::stderr));
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(journald_layer)
+        .with(stderr_layer)
+        .with(file_layer)
+        .init();
+    Ok(guard)
+}
+
+#[cfg(target_os = "linux")]
+fn journald_layer() -> Option<tracing_journald::Layer> {
+    // JOURNAL_STREAM is how systemd tells us stderr is already going to the journal
+    env::var_os("JOURNAL_STREAM")?;
+    tracing_journald::layer().ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn journald_layer() -> Option<tracing_subscriber::layer::Identity> {
+    None
+}
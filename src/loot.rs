@@ -0,0 +1,233 @@
+use std::io::Write;
+
+use chrono::{DateTime, TimeZone, Utc};
+use reqwest::blocking::Client;
+use rusqlite::Connection;
+
+use crate::{
+    db::{table_columns, SubInfo, SubSource},
+    i18n::fill,
+    items::ItemNames,
+    market::{self, Prices},
+    routes,
+    timefmt::TimeDisplay,
+};
+
+pub struct Item {
+    pub id: u32,
+    pub count: u32,
+    pub hq: bool,
+}
+
+/// What one sector of a voyage brought back
+pub struct SectorLoot {
+    pub sector: u32,
+    pub items: Vec<Item>,
+    pub surveillance_proc: bool,
+}
+
+/// One voyage's results, newest first in anything returned from here
+pub struct Voyage {
+    /// `SubInfo::key` of the sub that went
+    pub key: String,
+    pub returned: DateTime<Utc>,
+    pub sectors: Vec<SectorLoot>,
+}
+
+impl Voyage {
+    pub fn items(&self) -> impl Iterator<Item = &Item> {
+        self.sectors.iter().flat_map(|sector| &sector.items)
+    }
+
+    /// What it all goes for, counting anything nobody's selling as nothing
+    pub fn value(&self, prices: &Prices) -> u64 {
+        self.items()
+            .map(|item| prices.price(item.id, item.hq).unwrap_or(0) * u64::from(item.count))
+            .sum()
+    }
+}
+
+/// Prices on `market` for everything in `voyages`, if there's a market to price on
+pub fn prices(client: &Client, market: Option<&str>, voyages: &[Voyage]) -> Option<Prices> {
+    let market = market?;
+    let mut prices = Prices::load(market);
+    prices.fetch(client, voyages.iter().flat_map(|voyage| voyage.items().map(|item| item.id)));
+    Some(prices)
+}
+
+/// Every voyage recorded in `sources`, newest first
+pub fn read_all(sources: &[SubSource]) -> anyhow::Result<Vec<Voyage>> {
+    let mut voyages = Vec::new();
+    for source in sources {
+        if let Some(found) = source.with_sqlite(read)? {
+            voyages.extend(found.into_iter().map(|mut voyage| {
+                if let Some(label) = &source.label {
+                    voyage.key = format!("{label}/{}", voyage.key);
+                }
+                voyage
+            }));
+        }
+    }
+    voyages.sort_by_key(|voyage| std::cmp::Reverse(voyage.returned));
+    Ok(voyages)
+}
+
+/// For the commands built on loot history: says on stderr, once, that there's none to
+/// go on when none of `sources` has any, rather than leaving them to look empty
+pub fn warn_without_history(sources: &[SubSource]) {
+    let recorded = sources
+        .iter()
+        .any(|source| matches!(source.with_sqlite(|db| Ok(!table_columns(db, "loot")?.is_empty())), Ok(Some(true))));
+    if !recorded {
+        eprintln!("No loot history to go on, it's recorded by newer SubmarineTracker versions");
+    }
+}
+
+/// `db`'s voyages, none if it's from a SubmarineTracker too old to keep loot
+fn read(db: &Connection) -> anyhow::Result<Vec<Voyage>> {
+    let columns = table_columns(db, "loot")?;
+    if columns.is_empty() {
+        return Ok(Vec::new());
+    }
+    // Newest spelling first; anything missing reads as zero
+    let column = |names: &[&str]| {
+        names
+            .iter()
+            .find(|name| columns.iter().any(|c| c == *name))
+            .map_or("0".to_string(), |name| format!("\"{name}\""))
+    };
+    let sql = format!(
+        "SELECT {sub}, {when}, {sector}, {primary}, {primary_count}, {primary_hq}, {additional}, \
+         {additional_count}, {additional_hq}, {primary_surv} + {additional_surv}
+         FROM loot ORDER BY 2 DESC, rowid ASC",
+        sub = column(&["SubmarineId", "Submarine"]),
+        when = column(&["Return", "Date"]),
+        sector = column(&["Sector"]),
+        primary = column(&["Primary"]),
+        primary_count = column(&["PrimaryCount"]),
+        primary_hq = column(&["PrimaryHQ"]),
+        additional = column(&["Additional"]),
+        additional_count = column(&["AdditionalCount"]),
+        additional_hq = column(&["AdditionalHQ"]),
+        primary_surv = column(&["PrimarySurvProc"]),
+        additional_surv = column(&["AdditionalSurvProc"]),
+    );
+    let mut stmt = db.prepare(&sql)?;
+    let mut rows = stmt.query([])?;
+    let mut voyages: Vec<Voyage> = Vec::new();
+    while let Some(row) = rows.next()? {
+        let key = row.get::<_, i64>(0)?.to_string();
+        let returned = Utc.timestamp_opt(row.get(1)?, 0).single().unwrap_or_default();
+        let mut items = Vec::new();
+        for (id, count, hq) in [(3, 4, 5), (6, 7, 8)] {
+            let id: i64 = row.get(id)?;
+            if id > 0 {
+                items.push(Item {
+                    id: id as u32,
+                    count: row.get::<_, i64>(count)?.max(1) as u32,
+                    hq: row.get::<_, i64>(hq)? != 0,
+                });
+            }
+        }
+        let sector = SectorLoot {
+            sector: row.get::<_, i64>(2)? as u32,
+            items,
+            surveillance_proc: row.get::<_, i64>(9)? > 0,
+        };
+        match voyages.last_mut() {
+            Some(voyage) if voyage.key == key && voyage.returned == returned => voyage.sectors.push(sector),
+            _ => voyages.push(Voyage { key, returned, sectors: vec![sector] }),
+        }
+    }
+    Ok(voyages)
+}
+
+/// Voyages further back than this don't count towards gil a day
+const RATE_VOYAGES: usize = 10;
+
+/// `loot`: the last few voyages of each sub in `subs`, by character. With a `market`
+/// (a world or data centre) each voyage is priced at its market board's going rate.
+pub fn write(
+    out: &mut dyn Write,
+    sources: &[SubSource],
+    subs: &[SubInfo],
+    last: usize,
+    market: Option<&str>,
+    display: &TimeDisplay,
+) -> anyhow::Result<()> {
+    let voyages = read_all(sources)?;
+    let client = Client::new();
+    let mut names = ItemNames::load();
+    names.fetch(&client, voyages.iter().flat_map(|voyage| voyage.items().map(|item| item.id)));
+    let prices = prices(&client, market, &voyages);
+    let value = |voyage: &Voyage| prices.as_ref().map(|prices| voyage.value(prices));
+    // Worth of every voyage but the oldest, over the time from its return to the newest
+    // one's, since that's what was brought in over that stretch
+    let per_day = |history: &[&Voyage]| {
+        let history = &history[..history.len().min(RATE_VOYAGES)];
+        let [newest, .., oldest] = history else {
+            return None;
+        };
+        let span = (newest.returned - oldest.returned).num_seconds();
+        let earned: u64 = history[..history.len() - 1].iter().map(|voyage| value(voyage).unwrap_or(0)).sum();
+        (span > 0).then(|| earned * 86_400 / span as u64)
+    };
+    let text = display.text();
+    let daily = |gil: u64| fill(text.loot_daily, &[("gil", &market::gil(gil))]);
+    let rate = |per_day: Option<u64>| match (&prices, per_day) {
+        (Some(_), Some(gil)) => format!(" ({})", daily(gil)),
+        _ => String::new(),
+    };
+
+    let mut owners: Vec<String> = subs.iter().map(|sub| sub.owner()).collect();
+    owners.sort();
+    owners.dedup();
+    let mut everyone = 0;
+    for owner in &owners {
+        let owned: Vec<(&SubInfo, Vec<&Voyage>)> = subs
+            .iter()
+            .filter(|sub| sub.owner() == *owner)
+            .map(|sub| (sub, voyages.iter().filter(|voyage| voyage.key == sub.key()).collect()))
+            .collect();
+        let rates: Vec<u64> = owned.iter().filter_map(|(_, history)| per_day(history)).collect();
+        let owner_rate = (!rates.is_empty()).then(|| rates.iter().sum::<u64>());
+        everyone += owner_rate.unwrap_or(0);
+        writeln!(out, "{owner}{}:", rate(owner_rate))?;
+        for (sub, history) in &owned {
+            writeln!(out, "  {}{}:", sub.name, rate(per_day(history)))?;
+            for voyage in history.iter().take(last) {
+                let procs = voyage.sectors.iter().filter(|sector| sector.surveillance_proc).count();
+                let procs = match procs {
+                    0 => String::new(),
+                    n => fill(text.plural(text.loot_procs, n), &[("count", &n.to_string())]),
+                };
+                let worth =
+                    value(voyage).map_or(String::new(), |gil| fill(text.loot_worth, &[("gil", &market::gil(gil))]));
+                let route: Vec<u32> = voyage.sectors.iter().map(|sector| sector.sector).collect();
+                let back = fill(
+                    text.loot_back,
+                    &[("time", &display.short(voyage.returned)), ("route", &routes::describe(&route))],
+                );
+                writeln!(out, "    {back}{procs}{worth}")?;
+                for sector in &voyage.sectors {
+                    let items: Vec<String> = sector
+                        .items
+                        .iter()
+                        .map(|item| {
+                            let hq = if item.hq { " (HQ)" } else { "" };
+                            format!("{} ×{}{hq}", names.name(item.id), item.count)
+                        })
+                        .collect();
+                    writeln!(out, "      {}: {}", routes::sector_letter(sector.sector), items.join(", "))?;
+                }
+            }
+            if history.is_empty() {
+                writeln!(out, "    {}", text.loot_none)?;
+            }
+        }
+    }
+    if prices.is_some() && owners.len() > 1 {
+        writeln!(out, "{}: {}", text.everyone, daily(everyone))?;
+    }
+    Ok(())
+}
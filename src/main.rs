@@ -1,274 +1,600 @@
-use std::{
-    collections::HashMap, env, path::{Path, PathBuf}, time::Duration
-};
+use std::{path::PathBuf, process::ExitCode};
 
-use anyhow::Context;
-use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
-use chrono_tz::{OffsetName, Tz};
-use clap::Parser;
-use iana_time_zone::get_timezone;
-use rusqlite::Connection;
-use reqwest::blocking::Client;
-use serde_json::{
-    json,
-    Value
-};
+use chrono::{DateTime, Utc};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use serde_json::Value;
 
-macro_rules! debug_println {
-    ($($arg:tt)*) => (if ::std::cfg!(debug_assertions) { ::std::println!($($arg)*); })
-}
-
-#[cfg(target_os = "windows")]
-const SUBTRACKER_FOLDER: &str = r#"AppData\Roaming\XIVLauncher\pluginConfigs\SubmarineTracker"#;
+mod backup;
+mod caldav;
+mod calendar;
+mod completions;
+mod daemon;
 #[cfg(target_os = "linux")]
-const SUBTRACKER_FOLDER: &str = ".xlcore/pluginConfigs/SubmarineTracker";
+mod dbus;
+mod desktop;
+mod digest;
+mod discord;
+mod doctor;
+mod emit;
+mod google;
+mod history;
+mod image;
+mod init;
+mod items;
+mod ipc;
+mod kdeconnect;
+mod list;
+mod loot;
+mod lock;
+mod logging;
+mod market;
+mod notifier;
+mod outbox;
+mod parts;
+mod plan;
+mod predict;
+mod presence;
+mod private_file;
+mod progress;
+mod quiet;
+mod repairs;
+mod resources;
+mod routes;
+mod sectors;
+mod self_update;
+mod service;
+mod share;
+mod spool;
+mod state;
+mod stats;
+mod sync;
+mod system;
+mod systemd;
+mod template;
+mod theme;
+mod update;
+mod voyage_log;
+mod watch;
+mod xivapi;
+mod xmpp;
+
+use sub_returns::{config, config_path, data_dir, db, diff, duration, filter, i18n, local_timezone, schedule, timefmt};
+
+use config::Config;
+use db::get_all_submarine_info;
+use ipc::ControlCommand;
 
 #[derive(Parser, Debug)]
 #[command(version)]
 struct LaunchArgs {
     #[arg(short, long)]
     daemon: bool,
-    #[arg(short, long)]
+    /// Log more detail, repeat for even more (RUST_LOG overrides this)
+    #[arg(short, long, action = clap::ArgAction::Count, global = true)]
+    verbose: u8,
+    /// Also write the daemon's log here, rolled over daily
+    #[arg(long, requires = "daemon")]
+    log_file: Option<PathBuf>,
+    /// Stop an already running daemon and take its place
+    #[arg(long, requires = "daemon")]
+    replace: bool,
+    /// Read the local databases and send them to a collector instead of notifying
+    #[arg(long, conflicts_with = "daemon")]
+    agent: bool,
+    /// Run the daemon on subs sent in by agents rather than local databases
+    #[arg(long, requires = "daemon")]
+    collector: bool,
+    /// Notify about returns this close together as one, e.g. 10m (defaults to 5m)
+    #[arg(long, value_name = "DURATION", requires = "daemon")]
+    group_window: Option<duration::ConfigDuration>,
+    /// Run a daemon for each user in /etc/sub-returns/users.toml, as them, instead of
+    /// for whoever's running this (Linux and macOS, usually as a system service)
+    #[arg(long, requires = "daemon", conflicts_with_all = ["collector", "replace"])]
+    system: bool,
+    /// Detach from the console window (Windows only)
+    #[arg(long, requires = "daemon")]
+    hidden: bool,
+    /// Set return times, as an FFXIV date (11/14/2024 16:59) or from now (+36h30m). With
+    /// --duration, it's when they were sent out instead (now-2h for two hours ago)
+    #[arg(short, long, value_name = "WHEN")]
     update: Option<String>,
+    /// How long the voyage --update gives the start of takes, e.g. 36h30m
+    #[arg(long, value_name = "DURATION", requires = "update")]
+    duration: Option<duration::ConfigDuration>,
+    /// Only update this sub, by name or id (repeatable)
+    #[arg(long = "sub", value_name = "NAME_OR_ID", requires = "update")]
+    subs: Vec<String>,
+    /// Only show, notify about or update this character's subs (repeatable, part of
+    /// the name is enough)
+    #[arg(long = "character", value_name = "NAME", global = true)]
+    characters: Vec<String>,
+    /// Only show, notify about or update subs in the Free Company with this tag
+    /// (repeatable)
+    #[arg(long = "fc", value_name = "TAG", global = true)]
+    fcs: Vec<String>,
+    /// Only subs that are already back
+    #[arg(long, conflicts_with = "daemon")]
+    returned: bool,
+    /// Only subs that are still out
+    #[arg(long, conflicts_with_all = ["daemon", "returned"])]
+    pending: bool,
+    /// Put back the return times the last --update changed
+    #[arg(long, conflicts_with = "update")]
+    undo_last_update: bool,
+    /// Show what --update would change without writing anything
+    #[arg(long, requires = "update")]
+    dry_run: bool,
+    /// Don't ask before --update writes
+    #[arg(short, long, requires = "update")]
+    yes: bool,
+    /// Only print the next sub to return
+    #[arg(long, conflicts_with = "daemon")]
+    next: bool,
+    /// Print when each character's subs will be back instead of every sub
+    #[arg(long, conflicts_with_all = ["daemon", "next"])]
+    summary: bool,
+    /// Print one line per character of each sub and how long until it's back, for
+    /// tmux status lines and MOTDs
+    #[arg(long, conflicts_with_all = ["daemon", "next", "summary", "format", "template", "diff"])]
+    oneline: bool,
+    /// How to print the listing
+    #[arg(long, value_enum, default_value_t)]
+    format: list::OutputFormat,
+    /// Keep the listing on screen, updating it as times tick down and the database
+    /// changes
+    #[arg(long, conflicts_with = "daemon")]
+    watch: bool,
+    /// Print what changes as the databases do: subs collected and sent out again with
+    /// their new returns, and new subs and FCs
+    #[arg(long, conflicts_with_all = ["daemon", "watch"])]
+    diff: bool,
+    /// What to order the listing by
+    #[arg(long, value_enum, default_value_t)]
+    sort: list::SortKey,
+    /// Flip the listing's order
+    #[arg(long)]
+    reverse: bool,
+    /// Print each sub with a Handlebars template, e.g. '{{character}} {{sub}} {{return_relative}}'.
+    /// Also has {{tag}}, {{owner}}, {{source}}, {{id}}, {{return_time}}, {{return_iso}},
+    /// {{return_unix}}, {{returned}}, {{estimated}}, {{route}}, {{rank}}, {{build}}, {{exp}} and {{next_exp}}
+    #[arg(long, conflicts_with_all = ["format", "next", "summary"])]
+    template: Option<String>,
+    /// Show the sectors each sub is running in the listing
+    #[arg(long)]
+    route: bool,
+    /// Show a bar for how far along each sub's voyage is in the listing
+    #[arg(long)]
+    progress: bool,
+    /// Show each sub's rank and build in the listing
+    #[arg(long)]
+    details: bool,
+    /// Colour the listing by how soon each sub is back
+    #[arg(long, value_enum, default_value_t, value_name = "WHEN")]
+    color: theme::ColorChoice,
+    /// strftime format for return times in the listing and notifications
+    #[arg(long, value_name = "FORMAT", global = true)]
+    time_format: Option<String>,
+    /// Show times on a 24-hour clock (otherwise it follows the locale)
+    #[arg(long = "24h", global = true)]
+    hour24: bool,
+    /// Show times in this timezone instead of the system's, e.g. Europe/Berlin
+    #[arg(long, value_name = "ZONE", global = true)]
+    tz: Option<String>,
+    /// Language for the listing and notifications (otherwise it follows the locale)
+    #[arg(long, value_enum, global = true)]
+    lang: Option<i18n::Language>,
+    /// Print scheduler entries that run --run at each sub's return, for running scripts
+    /// without the daemon
+    #[arg(long, value_enum, requires = "run", conflicts_with = "daemon")]
+    emit: Option<emit::Scheduler>,
+    /// The command for --emit, a Handlebars template with the same variables as
+    /// --template, e.g. 'notify.sh {{sub}}'. Variables come out shell-quoted.
+    #[arg(long, value_name = "COMMAND", requires = "emit")]
+    run: Option<String>,
+    /// Print a service definition for running the daemon and exit
+    #[arg(long, value_enum)]
+    generate: Option<Generate>,
+    #[command(subcommand)]
+    command: Option<Command>,
 }
 
-fn main_daemon() -> anyhow::Result<()> {
-    use notify_rust::Notification;
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum Generate {
+    SystemdUnit,
+    /// For `--daemon --system`
+    SystemdSystemUnit,
+    LaunchdPlist,
+}
 
-    // Not proud of this but it meets my needs ok
-    let bridge_psk: &'static str = env!("PUSHOVER_BRIDGE_PSK");
-    let bridge_url: &'static str = env!("PUSHOVER_BRIDGE_URL");
-    let client = Client::new();
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Start the daemon at login (a LaunchAgent on macOS, a startup entry on Windows)
+    InstallService,
+    /// Check that everything needed to find and notify about your subs is in place
+    Doctor,
+    /// Show whether the daemon is running and what it has scheduled
+    Status,
+    /// Show which sectors each sub is out running
+    Routes,
+    /// Show what the last few voyages brought back
+    Loot {
+        /// How many voyages per sub
+        #[arg(long, default_value_t = 3, value_name = "N")]
+        last: usize,
+        /// Price it all on this world's or data centre's market board
+        #[arg(long, value_name = "WORLD")]
+        prices: Option<String>,
+    },
+    /// Returns and dispatches over the last while, from the daemon's log and loot history
+    History {
+        /// How far back, e.g. 7d or 12h
+        #[arg(long, default_value = "7d", value_name = "DURATION")]
+        since: duration::ConfigDuration,
+    },
+    /// When to collect and send out each sub, given the play windows in the config
+    Plan {
+        /// Flag subs that would sit waiting longer than this
+        #[arg(long, default_value = "6h", value_name = "DURATION")]
+        idle_over: duration::ConfigDuration,
+    },
+    /// Voyages a week, gil, time out and favourite sectors, per sub and FC
+    Stats {
+        /// How far back to look
+        #[arg(long, default_value_t = 4, value_name = "N")]
+        weeks: u32,
+        /// Price the loot on this world's or data centre's market board
+        #[arg(long, value_name = "WORLD")]
+        prices: Option<String>,
+    },
+    /// Save every FC and sub in the local databases to a JSON file, to keep or move
+    Export {
+        /// Where to write it, instead of printing it
+        #[arg(long, short, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+    /// Replace the FCs and subs in the local databases with an export's
+    Import {
+        file: PathBuf,
+        /// Don't ask first
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// The schedule to paste into Discord, in times each reader sees in their own
+    /// timezone, or drawn as a picture
+    Share {
+        /// Save it as a PNG here instead
+        #[arg(long, value_name = "FILE")]
+        png: Option<PathBuf>,
+    },
+    /// Put the return schedule somewhere else
+    Sync {
+        #[command(subcommand)]
+        target: SyncTarget,
+    },
+    /// Print a completion script for this shell, with the characters, FCs and subs in
+    /// the databases to complete --character, --fc and --sub to
+    Completions {
+        #[arg(value_enum)]
+        shell: clap_complete::Shell,
+    },
+    /// Manage the config file
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Install the latest release from GitHub over this binary
+    SelfUpdate {
+        /// Only say whether there's a newer one
+        #[arg(long)]
+        check: bool,
+    },
+    /// Send a control command to the running daemon
+    Ctl {
+        #[arg(value_enum)]
+        command: ControlCommand,
+    },
+}
 
-    let mut notifs_data: HashMap<i64, NotifyMeta> = HashMap::new();
-    let db = open_db(None)?;
-    loop {
-        let subs = get_submarine_info(&db)?;
-        let mut bridge_json_payload = serde_json::Map::new();
-        let mut subs_in_group: u32 = 1;
-        let mut previous_return_time: Option<DateTime<Utc>> = None;
-        let mut current_pushover_notif: Option<Value> = None;
-        let mut current_id = "".to_string();
-        let mut message_count: u32 = 0;
-        for sub in subs {
-            let mut meta = notifs_data
-                .get(&sub.id)
-                .cloned()
-                .unwrap_or_else(|| NotifyMeta {
-                    submarine_id: sub.id,
-                    will_notify: true,
-                    last_return_time: Default::default(),
-                });
-            if meta.last_return_time != sub.return_time && sub.return_time > Local::now() {
-                meta.will_notify = true;
-                meta.last_return_time = sub.return_time;
-                let time = sub.return_time.with_timezone(&Local);
-                debug_println!(
-                    "notification scheduled for {subname} {time}",
-                    subname = sub.name
-                );
+#[derive(Subcommand, Debug, Clone, Copy)]
+enum ConfigAction {
+    /// Write a starting config, asking which notifications you want
+    Init {
+        /// Replace the config if there already is one
+        #[arg(long)]
+        force: bool,
+    },
+}
 
-                // Add a notification object to the pushover bridge API JSON payload
-                let time = sub.return_time.with_timezone(&Local);
-                let time_str = time.format("%b%e, %Y, %I:%M%p").to_string();
-                let body = if subs_in_group > 1 {
-                    format!(
-                        "{name} ({char_name} «{tag}») + {num} others returned on {time_str}",
-                        name = sub.name,
-                        char_name = sub.character_name,
-                        tag = sub.tag,
-                        num = subs_in_group - 1
-                    )
-                } else {
-                    format!(
-                        "{name} ({char_name} «{tag}») returned on {time_str}",
-                        name = sub.name,
-                        char_name = sub.character_name,
-                        tag = sub.tag
-                    )
-                };
+#[derive(Subcommand, Debug, Clone, Copy)]
+enum SyncTarget {
+    /// An event on the `[caldav]` calendar for each sub's return, kept up to date each run
+    Caldav,
+    /// The same on the `[google]` calendar, signing in in a browser the first time
+    Google,
+}
 
-                let title = if subs_in_group > 1 {
-                    format!("{name} (+{num}) returned", name = sub.name, num = subs_in_group - 1)
-                } else {
-                    format!("{name} returned", name = sub.name)
-                };
-                
-                let pushover_notif = json!({
-                    "title": title,
-                    "message": body,
-                    "timestamp": sub.return_time.timestamp_millis()
-                });
-                current_id = format!("{char_name}«{tag}»-{message_count}", char_name = sub.character_name, tag = sub.tag);
-                if let Some(prev_time) = previous_return_time {
-                    if sub.return_time.timestamp_millis() - prev_time.timestamp_millis() > 300000 {
-                        bridge_json_payload.insert(current_id.clone(), pushover_notif);
-                        previous_return_time = None;
-                        current_pushover_notif = None;
-                        subs_in_group = 0;
-                        message_count += 1;
-                    } else {
-                        previous_return_time = Some(sub.return_time);
-                        subs_in_group += 1;
-                        current_pushover_notif = Some(pushover_notif);
-                    }
-                } else {
-                    previous_return_time = Some(sub.return_time);
-                    subs_in_group += 1;
-                    current_pushover_notif = Some(pushover_notif);
-                }
-            }
+/// Listing exit codes, for scripts. Errors are 1 and bad arguments 2, as usual.
+const EXIT_SOME_RETURNED: u8 = 3;
+const EXIT_NO_DATABASE: u8 = 4;
 
-            if meta.will_notify && sub.return_time <= Local::now() {
-                meta.will_notify = false;
-                let summary = format!("{name} returned", name = sub.name);
-                let time = sub.return_time.with_timezone(&Local);
-                let time_str = time.format("%b%e, %Y, %I:%M%p").to_string();
-                let body = format!(
-                    "{name} ({char_name} «{tag}») returned on {time_str}",
-                    name = sub.name,
-                    char_name = sub.character_name,
-                    tag = sub.tag
-                );
-                Notification::new()
-                    .summary(&summary)
-                    .body(&body)
-                    .icon("dialog-information")
-                    .show()?;
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("Error: {e:?}");
+            if e.chain().any(|cause| cause.is::<db::DbNotFound>()) {
+                ExitCode::from(EXIT_NO_DATABASE)
+            } else {
+                ExitCode::FAILURE
             }
-            notifs_data.insert(sub.id, meta);
-        }
-        if let Some(dangling_push_notif) = current_pushover_notif {
-            bridge_json_payload.insert(current_id, dangling_push_notif);
-        }
-        if !bridge_json_payload.is_empty() {
-            let payload = Value::Object(bridge_json_payload);
-            debug_println!("pushover bridge json: {}", payload);
-            client
-                .post(bridge_url)
-                .header("Authorization", format!("Bearer {}", bridge_psk))
-                .json(&payload)
-                .send()?;
-            // ... and honestly don't care about the response. It either keeps working or it ain't
         }
-
-        std::thread::sleep(Duration::from_secs(1));
     }
 }
 
-fn main() -> anyhow::Result<()> {
+fn run() -> anyhow::Result<ExitCode> {
     let args = LaunchArgs::parse();
+    if let Some(generate) = args.generate {
+        let service = match generate {
+            Generate::SystemdUnit => systemd::generate_unit()?,
+            Generate::SystemdSystemUnit => systemd::generate_system_unit()?,
+            Generate::LaunchdPlist => service::generate_launchd_plist()?,
+        };
+        print!("{service}");
+        return Ok(ExitCode::SUCCESS);
+    }
+    match args.command {
+        Some(Command::InstallService) => return service::install().map(|_| ExitCode::SUCCESS),
+        Some(Command::Doctor) => return doctor::run().map(|_| ExitCode::SUCCESS),
+        Some(Command::Status) => {
+            let display = time_display(&args, &Config::load(&config_path())?)?;
+            return print_daemon_status(&display).map(|_| ExitCode::SUCCESS);
+        }
+        Some(Command::Config { action: ConfigAction::Init { force } }) => {
+            return init::run(&config_path(), force).map(|_| ExitCode::SUCCESS)
+        }
+        Some(Command::SelfUpdate { check }) => return self_update::run(check).map(|_| ExitCode::SUCCESS),
+        Some(Command::Completions { shell }) => {
+            // Completing works without a database, just without the names
+            let subs = Config::load(&config_path()).and_then(|config| {
+                let mut subs = get_all_submarine_info(&db::open_sources(&config.sources)?)?;
+                config.apply_aliases(&mut subs);
+                Ok(subs)
+            });
+            completions::write(&mut std::io::stdout().lock(), LaunchArgs::command(), shell, &subs.unwrap_or_default())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Ctl { command }) => {
+            let response = ipc::send(command)?;
+            println!("{}", serde_json::to_string_pretty(&response)?);
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(
+            Command::Routes
+            | Command::Loot { .. }
+            | Command::Stats { .. }
+            | Command::History { .. }
+            | Command::Plan { .. }
+            | Command::Share { .. }
+            | Command::Sync { .. }
+            | Command::Export { .. }
+            | Command::Import { .. },
+        )
+        | None => {}
+    }
+    let _log_guard = logging::init(args.verbose, args.log_file.as_deref())?;
     if args.daemon {
-        return main_daemon();
+        if args.hidden {
+            service::hide_console();
+        }
+        if args.system {
+            return system::run(args.verbose).map(|_| ExitCode::SUCCESS);
+        }
+        return daemon::run(&args).map(|_| ExitCode::SUCCESS);
     }
-    if let Some(updated) = args.update {
-        let parse_date = NaiveDateTime::parse_from_str(&updated, "%m/%d/%Y %H:%M")
-            .with_context(|| format!("Date format incorrect for '{}', FFXIV format expected\n\nExample: 11/14/2024 16:59", updated))?
-            .and_local_timezone(Local)
-            .unwrap();
-        let updated_timestamp = parse_date.timestamp();
-        let db = open_db(Some(rusqlite::OpenFlags::SQLITE_OPEN_READ_WRITE))?;
-        db.execute("UPDATE submarine SET Return = (?1)", [updated_timestamp])?;
-        db.close().unwrap();
-        println!("All submarine return times updated! These are the new return times...");
+    let config = Config::load(&config_path())?;
+    if args.agent {
+        return sync::run_agent(&config).map(|_| ExitCode::SUCCESS);
     }
-
-    let tz_str = mysql_real_get_timezone().unwrap();
-    let tz: Tz = tz_str.parse().unwrap();
-    let offset = tz.offset_from_utc_date(&Utc::now().date_naive());
-    let tz_abbr = offset.abbreviation();
-    let db = open_db(None)?;
-    let all_subs = get_submarine_info(&db)?;
-    let longest_name = all_subs.iter().map(|s| s.name.len()).max().unwrap_or(0);
-    let mut subs_by_char: HashMap<String, Vec<SubInfo>> = HashMap::new();
-    for sub in all_subs {
-        let char_ident = format!(
-            "{name} «{fc_tag}»",
-            name = sub.character_name,
-            fc_tag = sub.tag
-        );
-        subs_by_char
-            .entry(char_ident)
-            .or_insert_with(Vec::new)
-            .push(sub);
+    match &args.command {
+        Some(Command::Export { output }) => {
+            return backup::export(&config, output.as_deref()).map(|_| ExitCode::SUCCESS)
+        }
+        Some(Command::Import { file, yes }) => return backup::import(&config, file, *yes).map(|_| ExitCode::SUCCESS),
+        _ => {}
+    }
+    if args.undo_last_update {
+        update::undo_last_update()?;
     }
-    for (char, subs) in subs_by_char {
-        println!("{char}:");
-        for sub in subs {
-            let padding = " ".repeat(longest_name - sub.name.len());
-            let time = sub.return_time.with_timezone(&Local);
-            let time_str = time.format("%e %B %Y at %I:%M:%S %p").to_string();
-            println!("  {name}:{padding} {time_str} {tz_abbr}", name = sub.name);
+    if let Some(updated) = &args.update {
+        let filter = filter::SubFilter {
+            subs: args.subs.clone(),
+            ..view_filter(&args)
+        };
+        let options = update::UpdateOptions {
+            dry_run: args.dry_run,
+            yes: args.yes,
+            voyage: args.duration.map(|duration| duration.0),
+        };
+        let display = time_display(&args, &config)?;
+        update::set_return_time(&config, update::parse_time(updated)?, &filter, &options, &display)?;
+        if args.dry_run {
+            return Ok(ExitCode::SUCCESS);
         }
     }
 
-    Ok(())
-}
-
-fn mysql_real_get_timezone() -> Option<String> {
-    // first check for TZ since upstream doesn't
-    let env_tz = env::var("TZ").ok();
-    let tz = env_tz.or(get_timezone().ok());
-    return tz;
+    let view = if args.next {
+        list::View::Next
+    } else if args.summary {
+        list::View::Summary
+    } else if args.oneline {
+        list::View::Oneline
+    } else {
+        list::View::List
+    };
+    let mut output = list::Output {
+        view,
+        display: time_display(&args, &config)?,
+        format: args.format,
+        theme: theme::Theme::new(args.color, &config.theme)?,
+        group_order: config.display.group_order,
+        sort: args.sort,
+        reverse: args.reverse,
+        template: args.template.as_deref().map(template::Template::new).transpose()?,
+        show_route: args.route,
+        show_details: args.details,
+        sectors: sectors::Sectors::default(),
+        show_progress: args.progress,
+        departures: progress::Departures::default(),
+    };
+    let sources = db::open_sources(&config.sources)?;
+    let view_filter = view_filter(&args);
+    if args.details {
+        // Watching picks up new subs but not new routes' sectors, which the next plain
+        // listing fetches
+        output.sectors = sectors::Sectors::for_routes(&get_all_submarine_info(&sources).unwrap_or_default());
+    }
+    if args.progress {
+        // As of now, so watching leaves voyages that start later without a bar
+        output.departures = progress::Departures::read(&get_all_submarine_info(&sources).unwrap_or_default(), &sources);
+    }
+    if args.diff {
+        return watch::diff(&sources, &db::db_paths(&config.sources), &config, &view_filter, &output.display)
+            .map(|_| ExitCode::SUCCESS);
+    }
+    if args.watch {
+        return watch::run(&sources, &db::db_paths(&config.sources), &config, &view_filter, &output).map(|_| ExitCode::SUCCESS);
+    }
+    let mut all_subs = get_all_submarine_info(&sources)?;
+    config.apply_aliases(&mut all_subs);
+    predict::fill_in(&mut all_subs, &sources);
+    all_subs.retain(|sub| view_filter.matches(sub));
+    match args.command {
+        Some(Command::Routes) => {
+            routes::write(&mut std::io::stdout().lock(), &all_subs, &output.display)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Loot { last, ref prices }) => {
+            let market = prices.as_deref().or(config.loot.market.as_deref());
+            loot::warn_without_history(&sources);
+            loot::write(&mut std::io::stdout().lock(), &sources, &all_subs, last, market, &output.display)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Plan { idle_over }) => {
+            plan::write(&mut std::io::stdout().lock(), &all_subs, &config.play_windows, idle_over.0, &output.display)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::History { since }) => {
+            loot::warn_without_history(&sources);
+            history::write(&mut std::io::stdout().lock(), &sources, &all_subs, since.0, &output.display)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Share { png: None }) => {
+            share::write(&mut std::io::stdout().lock(), &all_subs, &output.display)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Share { png: Some(ref path) }) => {
+            share::write_png(path, &all_subs, &output.display, &output.theme)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Stats { weeks, ref prices }) => {
+            let market = prices.as_deref().or(config.loot.market.as_deref());
+            loot::warn_without_history(&sources);
+            stats::write(&mut std::io::stdout().lock(), &sources, &all_subs, weeks, market, output.display.text())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Sync { target: SyncTarget::Caldav }) => {
+            let Some(caldav) = &config.caldav else {
+                anyhow::bail!("Set [caldav] url, username and password in the config to the calendar to sync to");
+            };
+            calendar::sync(&mut caldav::CalDav::new(caldav)?, "caldav-events.json", &all_subs, output.display.text())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Sync { target: SyncTarget::Google }) => {
+            let Some(google) = &config.google else {
+                anyhow::bail!(
+                    "Set [google] client_id and client_secret in the config to the OAuth client to sign in with"
+                );
+            };
+            let mut google = google::Google::connect(google)?;
+            calendar::sync(&mut google, "google-events.json", &all_subs, output.display.text())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        _ => {}
+    }
+    if let (Some(scheduler), Some(command)) = (args.emit, &args.run) {
+        let command = template::Template::for_shell(command)?;
+        emit::write(&mut std::io::stdout().lock(), &all_subs, scheduler, &command, &output.display)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    let any_returned = all_subs.iter().any(|sub| sub.return_time <= Utc::now());
+    list::write(&mut std::io::stdout().lock(), all_subs, &output)?;
+    Ok(if any_returned {
+        ExitCode::from(EXIT_SOME_RETURNED)
+    } else {
+        ExitCode::SUCCESS
+    })
 }
 
-fn open_db(flags: Option<rusqlite::OpenFlags>) -> anyhow::Result<Connection> {
-    let user_dirs = directories::UserDirs::new().unwrap();
-    let sub_db_file: PathBuf = [
-        user_dirs.home_dir(),
-        Path::new(SUBTRACKER_FOLDER),
-        Path::new("submarine-sqlite.db"),
-    ]
-    .iter()
-    .collect();
-    let db = Connection::open_with_flags(
-        sub_db_file,
-        flags.unwrap_or(rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY),
-    )?;
-    Ok(db)
+/// The filters that narrow down what's listed or notified about
+fn view_filter(args: &LaunchArgs) -> filter::SubFilter {
+    filter::SubFilter {
+        characters: args.characters.clone(),
+        fcs: args.fcs.clone(),
+        returned: match (args.returned, args.pending) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        },
+        ..Default::default()
+    }
 }
 
-fn get_submarine_info(db: &Connection) -> anyhow::Result<Vec<SubInfo>> {
-    let query = "
-    SELECT
-        submarine.SubmarineId AS id,
-        submarine.Name AS name, 
-        submarine.Return AS return_time, 
-        freecompany.FreeCompanyTag AS tag, 
-        freecompany.CharacterName AS character_name
-    FROM submarine
-    JOIN freecompany
-    ON submarine.FreeCompanyId = freecompany.FreeCompanyId
-    ORDER BY return_time ASC
-    ";
-    let mut stmt = db.prepare(query)?;
-    let subs: Vec<SubInfo> = stmt
-        .query_map([], |row| {
-            let timestamp: i64 = row.get(2)?;
-            Ok(SubInfo {
-                id: row.get(0)?,
-                name: row.get(1)?,
-                return_time: Utc.timestamp_opt(timestamp, 0).single().unwrap(),
-                tag: row.get(3)?,
-                character_name: row.get(4)?,
-            })
-        })?
-        .filter_map(|r| r.ok())
-        .collect();
-    Ok(subs)
+/// Display settings from the config, with any flags on top
+fn time_display(args: &LaunchArgs, config: &Config) -> anyhow::Result<timefmt::TimeDisplay> {
+    let mut display = config.display.clone();
+    if args.time_format.is_some() {
+        display.time_format = args.time_format.clone();
+    }
+    if args.hour24 {
+        display.hour24 = Some(true);
+    }
+    if args.tz.is_some() {
+        display.timezone = args.tz.clone();
+    }
+    if args.lang.is_some() {
+        display.language = args.lang;
+    }
+    timefmt::TimeDisplay::new(&display)
 }
 
-#[derive(Clone)]
-pub struct NotifyMeta {
-    pub submarine_id: i64,
-    pub will_notify: bool,
-    pub last_return_time: DateTime<Utc>,
-}
+fn print_daemon_status(display: &timefmt::TimeDisplay) -> anyhow::Result<()> {
+    let Ok(status) = ipc::send(ControlCommand::Status) else {
+        println!("Daemon is not running");
+        return Ok(());
+    };
+    let format_time = |value: &Value| -> String {
+        serde_json::from_value::<DateTime<Utc>>(value.clone())
+            .map(|time| display.long(time).trim_start().to_string())
+            .unwrap_or_else(|_| "never".to_string())
+    };
 
-pub struct SubInfo {
-    pub id: i64,
-    pub name: String,
-    pub return_time: DateTime<Utc>,
-    pub tag: String,
-    pub character_name: String,
+    let paused = if status["paused"].as_bool().unwrap_or(false) {
+        ", notifications paused"
+    } else {
+        ""
+    };
+    println!("Daemon is running (pid {pid}){paused}", pid = status["pid"]);
+    println!("Last DB read: {}", format_time(&status["last_db_read"]));
+    println!("Last push:    {}", format_time(&status["last_push"]));
+    let pending = status["pending"].as_array().cloned().unwrap_or_default();
+    println!("Pending notifications: {}", pending.len());
+    for sub in pending {
+        println!(
+            "  {name} ({owner}) at {time}",
+            name = sub["name"].as_str().unwrap_or_default(),
+            owner = sub["owner"].as_str().unwrap_or_default(),
+            time = format_time(&sub["return_time"]),
+        );
+    }
+    Ok(())
 }
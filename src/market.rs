@@ -0,0 +1,135 @@
+use std::{collections::HashMap, fs, path::PathBuf, time::Duration};
+
+use chrono::{DateTime, Utc};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::warn;
+
+use crate::data_dir;
+
+const UNIVERSALIS: &str = "https://universalis.app/api/v2/aggregated";
+/// Universalis won't take more ids than this in one request
+const BATCH: usize = 100;
+/// Listings move, but not so fast that every run needs to ask again
+const MAX_AGE: chrono::Duration = chrono::Duration::hours(6);
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default)]
+struct Price {
+    nq: Option<u64>,
+    hq: Option<u64>,
+    fetched: DateTime<Utc>,
+}
+
+/// Market board prices on one world or data centre, from Universalis, cached on disk
+/// for a few hours
+pub struct Prices {
+    path: PathBuf,
+    market: String,
+    /// Everything cached, by lowercased market and then item id
+    cache: HashMap<String, HashMap<u32, Price>>,
+}
+
+impl Prices {
+    pub fn load(market: &str) -> Self {
+        let path = data_dir().join("prices.json");
+        let cache = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Prices { path, market: market.to_lowercase(), cache }
+    }
+
+    /// Fetches whichever of `ids` aren't cached or have gone stale. Failing keeps
+    /// whatever was cached before.
+    pub fn fetch(&mut self, client: &Client, ids: impl IntoIterator<Item = u32>) {
+        let now = Utc::now();
+        let cached = self.cache.entry(self.market.clone()).or_default();
+        let mut stale: Vec<u32> = ids
+            .into_iter()
+            .filter(|id| cached.get(id).is_none_or(|price| now - price.fetched > MAX_AGE))
+            .collect();
+        stale.sort();
+        stale.dedup();
+        if stale.is_empty() {
+            return;
+        }
+        for batch in stale.chunks(BATCH) {
+            match fetch_prices(client, &self.market, batch) {
+                Ok(found) => cached.extend(found),
+                Err(e) => {
+                    warn!(error = format!("{e:#}"), market = self.market, "couldn't fetch prices");
+                    return;
+                }
+            }
+        }
+        if let Err(e) = self.save() {
+            warn!(error = format!("{e:#}"), "couldn't save prices");
+        }
+    }
+
+    /// Cheapest listing for one of the item, HQ if it's HQ and there's an HQ listing.
+    /// None for anything nobody's selling, including untradeables.
+    pub fn price(&self, id: u32, hq: bool) -> Option<u64> {
+        let price = self.cache.get(&self.market)?.get(&id)?;
+        if hq {
+            price.hq.or(price.nq)
+        } else {
+            price.nq.or(price.hq)
+        }
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.cache)?)?;
+        Ok(())
+    }
+}
+
+fn fetch_prices(client: &Client, market: &str, ids: &[u32]) -> anyhow::Result<HashMap<u32, Price>> {
+    let ids: Vec<String> = ids.iter().map(u32::to_string).collect();
+    let response: Value = client
+        .get(format!("{UNIVERSALIS}/{market}/{}", ids.join(",")))
+        .timeout(Duration::from_secs(10))
+        .send()?
+        .error_for_status()?
+        .json()?;
+    let fetched = Utc::now();
+    let prices = response["results"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|result| {
+            let id = result["itemId"].as_u64()? as u32;
+            let price = Price { nq: cheapest(&result["nq"]), hq: cheapest(&result["hq"]), fetched };
+            Some((id, price))
+        })
+        .collect();
+    Ok(prices)
+}
+
+/// The narrowest scope Universalis answered for: the world if `market` was one, else
+/// the data centre, falling back to the last sale when nothing's listed
+fn cheapest(quality: &Value) -> Option<u64> {
+    ["minListing", "recentPurchase"].iter().find_map(|kind| {
+        ["world", "dc", "region"]
+            .iter()
+            .find_map(|scope| quality[kind][scope]["price"].as_f64())
+            .map(|price| price.round() as u64)
+    })
+}
+
+/// 1234567 as "1,234,567"
+pub fn gil(amount: u64) -> String {
+    let digits = amount.to_string();
+    let mut out = String::new();
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(',');
+        }
+        out.push(digit);
+    }
+    out
+}
@@ -0,0 +1,90 @@
+use std::{sync::mpsc, thread};
+
+use tracing::{info, warn};
+
+use crate::{
+    config::{Config, DiscordConfig, FcRoute, XmppConfig},
+    discord::Discord,
+    kdeconnect::KdeConnect,
+    xmpp::Xmpp,
+};
+
+/// Somewhere returns are announced besides the desktop and the bridge, like a chat
+/// room. Messages are the push templates' title and body, sent as returns come in.
+/// The desktop and the bridge work differently, one with buttons to answer and the
+/// other handed pushes hours ahead, so they aren't notifiers.
+pub trait Notifier: Send {
+    fn name(&self) -> &str;
+    fn send(&mut self, title: &str, body: &str) -> anyhow::Result<()>;
+}
+
+/// Every notifier the config sets up, or just the ones `route` has for its FC
+pub fn from_config(config: &Config, route: Option<&FcRoute>) -> Vec<Box<dyn Notifier>> {
+    let (xmpp, discord, kdeconnect) = match route {
+        None => (config.xmpp.clone(), config.discord.clone(), config.kdeconnect.clone()),
+        // Signed in with the top-level account and bot, sent where the FC's are
+        Some(route) => (
+            route.xmpp.as_ref().zip(config.xmpp.as_ref()).map(|(to, account)| XmppConfig {
+                to: to.to.clone(),
+                muc: to.muc,
+                nick: to.nick.clone().or_else(|| account.nick.clone()),
+                ..account.clone()
+            }),
+            route.discord.clone().map(|discord| DiscordConfig {
+                token: discord.token.or_else(|| config.discord.as_ref().and_then(|bot| bot.token.clone())),
+                ..discord
+            }),
+            route.kdeconnect.clone(),
+        ),
+    };
+    let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+    if let Some(xmpp) = xmpp {
+        notifiers.push(Box::new(Xmpp::new(xmpp)));
+    }
+    if let Some(discord) = discord {
+        if discord.webhook.is_some() || (discord.token.is_some() && discord.channel.is_some()) {
+            notifiers.push(Box::new(Discord::new(discord)));
+        }
+    }
+    if let Some(kdeconnect) = kdeconnect {
+        notifiers.push(Box::new(KdeConnect::new(kdeconnect)));
+    }
+    notifiers
+}
+
+struct Announcement {
+    notifiers: Vec<Box<dyn Notifier>>,
+    title: String,
+    body: String,
+}
+
+/// Sends announcements from a thread of their own, in order, so a slow or unreachable
+/// server doesn't hold up the daemon
+pub struct Announcer {
+    tx: mpsc::Sender<Announcement>,
+}
+
+impl Announcer {
+    pub fn start() -> Self {
+        let (tx, rx) = mpsc::channel::<Announcement>();
+        thread::spawn(move || {
+            for mut announcement in rx {
+                for notifier in &mut announcement.notifiers {
+                    match notifier.send(&announcement.title, &announcement.body) {
+                        Ok(()) => info!(notifier = notifier.name(), title = announcement.title, "announced"),
+                        Err(e) => warn!(notifier = notifier.name(), error = format!("{e:#}"), "couldn't announce"),
+                    }
+                }
+            }
+        });
+        Announcer { tx }
+    }
+
+    /// Sends to whichever notifiers `config` has, if any, or the FC's own with `route`
+    pub fn announce(&self, config: &Config, route: Option<&FcRoute>, title: String, body: String) {
+        let notifiers = from_config(config, route);
+        if !notifiers.is_empty() {
+            let _ = self.tx.send(Announcement { notifiers, title, body });
+        }
+    }
+}
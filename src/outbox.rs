@@ -0,0 +1,85 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    num::NonZeroU32,
+    time::{Duration, Instant},
+};
+
+use chrono::{TimeDelta, Utc};
+use serde_json::{Map, Value};
+use tracing::debug;
+
+/// How long a sent notification is remembered for spotting repeats
+const REMEMBER: TimeDelta = TimeDelta::days(1);
+
+/// Notifications on their way to the bridge. Anything the bridge has already been
+/// sent is dropped, a burst is held until it settles so it goes as one post, and only
+/// so many go a minute, the rest waiting for the next.
+pub struct Outbox {
+    queued: Map<String, Value>,
+    /// When the latest was queued, to tell when a burst is over
+    last_queued: Option<Instant>,
+    /// Everything sent in the last day, by id
+    sent: HashMap<String, Value>,
+    /// When each of the last minute's notifications went
+    recent: VecDeque<Instant>,
+}
+
+impl Outbox {
+    pub fn new() -> Self {
+        Outbox { queued: Map::new(), last_queued: None, sent: HashMap::new(), recent: VecDeque::new() }
+    }
+
+    /// Adds `payload`'s notifications, replacing any queued under the same id. Ones
+    /// identical to what was last sent under their id are dropped; the same text under
+    /// another id is another notification, since the bridge replaces pushes by id.
+    pub fn queue(&mut self, payload: Map<String, Value>) {
+        for (id, notif) in payload {
+            if self.sent.get(&id) == Some(&notif) {
+                debug!(id, "already sent, dropping");
+                continue;
+            }
+            self.queued.insert(id, notif);
+            self.last_queued = Some(Instant::now());
+        }
+    }
+
+    /// Drops the notification queued under `id`, if it hasn't gone yet
+    pub fn cancel(&mut self, id: &str) {
+        self.queued.remove(id);
+    }
+
+    /// Nothing waiting to be taken
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// What's ready to post: nothing until `settle` has passed since the last
+    /// notification was queued, then as many as `per_minute` allows, soonest first
+    pub fn take(&mut self, settle: Duration, per_minute: Option<NonZeroU32>) -> Option<Map<String, Value>> {
+        if self.queued.is_empty() || self.last_queued.is_some_and(|last| last.elapsed() < settle) {
+            return None;
+        }
+        while self.recent.front().is_some_and(|sent| sent.elapsed() >= Duration::from_secs(60)) {
+            self.recent.pop_front();
+        }
+        let allowed = per_minute.map_or(usize::MAX, |limit| (limit.get() as usize).saturating_sub(self.recent.len()));
+        if allowed == 0 {
+            return None;
+        }
+
+        let mut ids: Vec<String> = self.queued.keys().cloned().collect();
+        ids.sort_by_key(|id| self.queued[id]["timestamp"].as_i64());
+        ids.truncate(allowed);
+        let mut payload = Map::new();
+        for id in ids {
+            if let Some(notif) = self.queued.remove(&id) {
+                self.sent.insert(id.clone(), notif.clone());
+                self.recent.push_back(Instant::now());
+                payload.insert(id, notif);
+            }
+        }
+        let forget_before = (Utc::now() - REMEMBER).timestamp_millis();
+        self.sent.retain(|_, notif| notif["timestamp"].as_i64().is_some_and(|time| time >= forget_before));
+        Some(payload)
+    }
+}
@@ -0,0 +1,22 @@
+/// Letters for the part classes in the game's SubmarinePart sheet, which has each
+/// class's four parts in a row: Shark, Unkiu, Whale, Coelacanth, Syldra, then the
+/// modified versions of all five in the same order.
+const CLASSES: [char; 5] = ['S', 'U', 'W', 'C', 'Y'];
+
+/// The usual shorthand for a sub's build, hull-stern-bow-bridge, e.g. `WSUC`. Modified
+/// parts get a `+` and unknown ones a `?`.
+pub fn build_code(parts: &[u32; 4]) -> String {
+    let mut code = String::new();
+    for &part in parts {
+        match part.checked_sub(1).map(|index| index / 4) {
+            Some(class) if class < 10 => {
+                code.push(CLASSES[class as usize % 5]);
+                if class >= 5 {
+                    code.push('+');
+                }
+            }
+            _ => code.push('?'),
+        }
+    }
+    code
+}
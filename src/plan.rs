@@ -0,0 +1,85 @@
+use std::io::Write;
+
+use anyhow::bail;
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{config::PlayWindow, db::SubInfo, duration::format_duration, i18n::fill, timefmt::TimeDisplay};
+
+/// How far ahead to look for a window to collect in
+const LOOKAHEAD_DAYS: u64 = 14;
+
+/// One opening of a play window, start to end
+type Session = (DateTime<Utc>, DateTime<Utc>);
+
+/// A sub's next collection: when, and how long it'll have sat there by then
+struct Collection<'a> {
+    sub: &'a SubInfo,
+    at: DateTime<Utc>,
+    idle: TimeDelta,
+}
+
+/// `plan`: for each play session coming up, which subs to collect and send out again
+/// and when. Each sub goes in the first session it's back in time for, which is also
+/// the one it waits least for; later sessions aren't weighed against it. Subs idle
+/// longer than `idle_over` are flagged.
+pub fn write(
+    out: &mut dyn Write,
+    subs: &[SubInfo],
+    windows: &[PlayWindow],
+    idle_over: TimeDelta,
+    display: &TimeDisplay,
+) -> anyhow::Result<()> {
+    if windows.is_empty() {
+        bail!("No play windows to plan around, add some [[play_windows]] to the config");
+    }
+    let now = Utc::now();
+    let mut sessions: Vec<Session> =
+        windows.iter().flat_map(|window| window.occurrences(now, LOOKAHEAD_DAYS)).collect();
+    sessions.sort();
+    sessions.dedup();
+
+    let mut plan: Vec<(Session, Collection)> = Vec::new();
+    let mut unplanned: Vec<&SubInfo> = Vec::new();
+    for sub in subs {
+        let ready = sub.return_time.max(now);
+        // The first session it's back before the end of
+        match sessions.iter().find(|(_, end)| *end > ready) {
+            Some(&(start, end)) => {
+                let at = ready.max(start);
+                plan.push(((start, end), Collection { sub, at, idle: at - sub.return_time }));
+            }
+            None => unplanned.push(sub),
+        }
+    }
+    plan.sort_by_key(|(session, collection)| (*session, collection.at));
+
+    let text = display.text();
+    let mut current: Option<Session> = None;
+    for (session, collection) in &plan {
+        if current != Some(*session) {
+            current = Some(*session);
+            let (start, end) = (display.short(session.0.max(now)), display.short(session.1));
+            writeln!(out, "{}", fill(text.plan_session, &[("start", &start), ("end", &end)]))?;
+        }
+        let idle = match collection.idle {
+            idle if idle <= TimeDelta::minutes(1) => text.plan_no_wait.to_string(),
+            idle => fill(text.plan_idle, &[("time", &format_duration(idle))]),
+        };
+        let flag = if collection.idle > idle_over { " (!)" } else { "" };
+        writeln!(
+            out,
+            "  {}  {} ({}), {idle}{flag}",
+            display.short(collection.at),
+            collection.sub.name,
+            collection.sub.owner()
+        )?;
+    }
+    for sub in unplanned {
+        writeln!(out, "{}", fill(text.plan_unplanned, &[("name", &sub.name), ("owner", &sub.owner())]))?;
+    }
+    let flagged = plan.iter().filter(|(_, collection)| collection.idle > idle_over).count();
+    if flagged > 0 {
+        writeln!(out, "{}", fill(text.plan_flagged, &[("time", &format_duration(idle_over))]))?;
+    }
+    Ok(())
+}
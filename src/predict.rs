@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    db::{SubInfo, SubSource},
+    loot,
+    sectors::Sectors,
+};
+
+/// Fills in a guess for subs whose return time the plugin didn't keep up with: none at
+/// all, or one from before a voyage it has loot for. They went out again when that
+/// voyage was collected, so they're back the route's length after that. The guesses
+/// are marked `estimated`, and are early by however long the sub spends travelling
+/// between sectors, which [`Sectors::route_duration`] leaves out.
+pub fn fill_in(subs: &mut [SubInfo], sources: &[SubSource]) {
+    // A guess isn't worth failing the listing over
+    let voyages = loot::read_all(sources).unwrap_or_default();
+    let departures: Vec<(usize, DateTime<Utc>)> = subs
+        .iter()
+        .enumerate()
+        .filter(|(_, sub)| !sub.route.is_empty())
+        .filter_map(|(index, sub)| {
+            let collected = voyages.iter().find(|voyage| voyage.key == sub.key())?.returned;
+            let stale = sub.return_time.timestamp() <= 0 || sub.return_time < collected;
+            stale.then_some((index, collected))
+        })
+        .collect();
+    if departures.is_empty() {
+        return;
+    }
+    let sectors = Sectors::for_routes(subs);
+    for (index, departed) in departures {
+        let sub = &mut subs[index];
+        if let Some(duration) = sectors.route_duration(&sub.route) {
+            sub.return_time = departed + duration;
+            sub.estimated = true;
+        }
+    }
+    subs.sort_by_key(|sub| sub.return_time);
+}
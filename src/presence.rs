@@ -0,0 +1,161 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde_json::{Map, Value};
+use tracing::{debug, info};
+
+use crate::config::{PresenceAction, PresenceConfig};
+
+/// Pushes kept here until they're due instead of being handed to the bridge ahead of
+/// time, so whether you're at the computer is checked when they'd go off rather than
+/// hours before
+pub struct Presence {
+    held: Map<String, Value>,
+}
+
+impl Presence {
+    pub fn new() -> Self {
+        Presence { held: Map::new() }
+    }
+
+    /// Keeps `payload`'s notifications until they're due, replacing any held under the
+    /// same id
+    pub fn hold(&mut self, payload: Map<String, Value>) {
+        self.held.extend(payload);
+    }
+
+    /// Drops the notification held under `id`, if there is one
+    pub fn cancel(&mut self, id: &str) {
+        self.held.remove(id);
+    }
+
+    /// The held notifications that have come due and should still go: all of them if
+    /// you're away, none (`skip`) or the same ones `delay` later if you're here. Without
+    /// `[presence]` anything still held goes as it is.
+    pub fn due(&mut self, config: Option<&PresenceConfig>, now: DateTime<Utc>) -> Map<String, Value> {
+        let Some(config) = config else {
+            return std::mem::take(&mut self.held);
+        };
+        let due: Vec<String> = self
+            .held
+            .iter()
+            .filter(|(_, notif)| notif["timestamp"].as_i64().is_none_or(|time| time <= now.timestamp_millis()))
+            .map(|(id, _)| id.clone())
+            .collect();
+        if due.is_empty() {
+            return Map::new();
+        }
+        let mut payload = Map::new();
+        for id in &due {
+            if let Some(notif) = self.held.remove(id) {
+                payload.insert(id.clone(), notif);
+            }
+        }
+
+        if !here(config) {
+            return payload;
+        }
+        match config.action {
+            PresenceAction::Skip => {
+                info!(count = payload.len(), "you're at the computer, skipping pushes");
+                Map::new()
+            }
+            PresenceAction::Delay => {
+                let delay = config.delay.map_or(TimeDelta::minutes(10), |delay| delay.0);
+                info!(count = payload.len(), %delay, "you're at the computer, delaying pushes");
+                // Straight to the bridge with the later time, so they aren't checked again
+                let later = (now + delay).timestamp_millis();
+                for notif in payload.values_mut().filter_map(Value::as_object_mut) {
+                    notif.insert("timestamp".to_string(), later.into());
+                }
+                payload
+            }
+        }
+    }
+
+    /// When the soonest held notification comes due
+    pub fn next_due(&self) -> Option<DateTime<Utc>> {
+        self.held
+            .values()
+            .filter_map(|notif| notif["timestamp"].as_i64())
+            .min()
+            .and_then(DateTime::from_timestamp_millis)
+    }
+
+    /// Everything still held, for shutting down: with nothing left to check, they go to
+    /// the bridge as scheduled
+    pub fn drain(&mut self) -> Map<String, Value> {
+        std::mem::take(&mut self.held)
+    }
+}
+
+/// Whether you're at the computer: it's been used in the last `idle_after`, and the
+/// screen isn't locked
+pub fn here(config: &PresenceConfig) -> bool {
+    let idle_after = config.idle_after.map_or(TimeDelta::minutes(5), |idle| idle.0);
+    let idle = idle_time();
+    debug!(?idle, "checked for activity");
+    idle.is_some_and(|idle| idle < idle_after.to_std().unwrap_or_default())
+}
+
+/// How long since the keyboard or mouse was last used, the longest possible with the
+/// screen locked, or None when it can't be told (no desktop session, or one that
+/// doesn't say), which counts as away
+#[cfg(target_os = "linux")]
+fn idle_time() -> Option<Duration> {
+    use zbus::blocking::Connection;
+
+    let connection = Connection::session().ok()?;
+    let call = |destination: &str, path: &str, method: &str| {
+        connection.call_method(Some(destination), path, Some(destination), method, &()).ok()
+    };
+    for (screensaver, path) in
+        [("org.gnome.ScreenSaver", "/org/gnome/ScreenSaver"), ("org.freedesktop.ScreenSaver", "/ScreenSaver")]
+    {
+        let locked = call(screensaver, path, "GetActive").and_then(|reply| reply.body().deserialize::<bool>().ok());
+        if locked == Some(true) {
+            return Some(Duration::MAX);
+        }
+    }
+    // GNOME, then KDE, both in milliseconds
+    if let Some(reply) = call("org.gnome.Mutter.IdleMonitor", "/org/gnome/Mutter/IdleMonitor/Core", "GetIdletime") {
+        if let Ok(ms) = reply.body().deserialize::<u64>() {
+            return Some(Duration::from_millis(ms));
+        }
+    }
+    let reply = call("org.freedesktop.ScreenSaver", "/ScreenSaver", "GetSessionIdleTime")?;
+    reply.body().deserialize::<u32>().ok().map(|ms| Duration::from_millis(ms.into()))
+}
+
+#[cfg(target_os = "macos")]
+fn idle_time() -> Option<Duration> {
+    let output = std::process::Command::new("ioreg").args(["-c", "IOHIDSystem", "-d", "4"]).output().ok()?;
+    // e.g. `"HIDIdleTime" = 1234567890`, in nanoseconds
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|line| line.split_once("\"HIDIdleTime\" = ")?.1.trim().parse().ok())
+        .map(Duration::from_nanos)
+}
+
+#[cfg(windows)]
+fn idle_time() -> Option<Duration> {
+    use windows_sys::Win32::{
+        System::SystemInformation::GetTickCount,
+        UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
+    };
+
+    let mut info = LASTINPUTINFO { cbSize: size_of::<LASTINPUTINFO>() as u32, dwTime: 0 };
+    // SAFETY: info is a LASTINPUTINFO with its size filled in, as the call wants
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    // Both are milliseconds since boot, wrapping every 49 days
+    // SAFETY: GetTickCount has no preconditions
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(now.wrapping_sub(info.dwTime).into()))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn idle_time() -> Option<Duration> {
+    None
+}
@@ -0,0 +1,28 @@
+use std::{fs, io::Write, path::Path};
+
+/// Writes `contents` to `path` so only we can read it, for files with passwords or
+/// tokens in them. One already there is made private too, not just new ones.
+pub fn write(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let mut file = options.open(path)?;
+    restrict(&file)?;
+    file.write_all(contents.as_bytes())
+}
+
+/// Takes away everyone else's access to `file`. Windows profiles are already private.
+pub fn restrict(file: &fs::File) -> std::io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    }
+    #[cfg(not(unix))]
+    let _ = file;
+    Ok(())
+}
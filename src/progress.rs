@@ -0,0 +1,60 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::{
+    db::{SubInfo, SubSource},
+    loot, voyage_log,
+};
+
+/// Longer than any route takes, so a departure further back than this before the
+/// return is from some other voyage
+const LONGEST_VOYAGE: TimeDelta = TimeDelta::days(4);
+
+/// When subs went out on their voyages, for the progress bars
+#[derive(Default)]
+pub struct Departures {
+    /// By key, with the return it was for
+    voyages: HashMap<String, (DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl Departures {
+    /// When each of `subs` went out, for the ones that can be told. The daemon's voyage
+    /// log has it for voyages it saw start; otherwise the plugin's loot for the one
+    /// before says when that was collected, which is when the sub went out again.
+    pub fn read(subs: &[SubInfo], sources: &[SubSource]) -> Self {
+        let logged = voyage_log::read().unwrap_or_default();
+        // Newest first
+        let voyages = loot::read_all(sources).unwrap_or_default();
+        let mut departures = Departures::default();
+        for sub in subs {
+            let seen = logged
+                .iter()
+                .rev()
+                .find(|dispatch| dispatch.key == sub.key() && dispatch.return_time == sub.return_time)
+                .map(|dispatch| dispatch.seen);
+            let collected = || voyages.iter().find(|voyage| voyage.key == sub.key()).map(|voyage| voyage.returned);
+            if let Some(departed) = seen.or_else(collected) {
+                if departed < sub.return_time && sub.return_time - departed <= LONGEST_VOYAGE {
+                    departures.voyages.insert(sub.key(), (sub.return_time, departed));
+                }
+            }
+        }
+        departures
+    }
+
+    /// When `sub` went out, if it's still on the voyage this knows about
+    pub fn get(&self, sub: &SubInfo) -> Option<DateTime<Utc>> {
+        self.voyages
+            .get(&sub.key())
+            .filter(|(return_time, _)| *return_time == sub.return_time)
+            .map(|(_, departed)| *departed)
+    }
+}
+
+/// How far along the voyage is at `now`, from 0 to 1
+pub fn done(departed: DateTime<Utc>, return_time: DateTime<Utc>, now: DateTime<Utc>) -> f64 {
+    let total = (return_time - departed).num_seconds() as f64;
+    let gone = (now - departed).num_seconds() as f64;
+    (gone / total).clamp(0.0, 1.0)
+}
@@ -0,0 +1,20 @@
+use crate::{db::SubInfo, i18n::fill, timefmt::TimeDisplay};
+
+/// Title and body for the one notification that stands in for everything that came
+/// back during quiet hours, or with `title` (filled in with `{count}`) for some other
+/// stretch nobody was told about
+pub fn catch_up_message(subs: &[&SubInfo], title: Option<&str>, display: &TimeDisplay) -> (String, String) {
+    let text = display.text();
+    let title = fill(title.unwrap_or(text.quiet_title), &[("count", &subs.len().to_string())]);
+    let body = subs
+        .iter()
+        .map(|sub| {
+            fill(
+                text.quiet_line,
+                &[("name", &sub.name), ("owner", &sub.owner()), ("time", &display.notification(sub.return_time))],
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    (title, body)
+}
@@ -0,0 +1,128 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::Command,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use rusqlite::{Connection, OpenFlags};
+
+use crate::db::{open_db_at, DB_FILE_NAME};
+
+/// How often a remote database is copied down again.
+const FETCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A database on another machine, reached with the system's `scp` so the user's
+/// existing SSH keys and config just work. It's copied into a local cache and read
+/// from there; the copy is refreshed at most every `FETCH_INTERVAL`.
+pub struct RemoteDb {
+    url: String,
+    host: String,
+    port: Option<u16>,
+    remote_path: String,
+    cache_path: PathBuf,
+    db: Option<Connection>,
+    last_fetch: Option<Instant>,
+}
+
+impl RemoteDb {
+    /// `url` looks like `ssh://user@host[:port]/path`, where the path is either the
+    /// plugin folder or the database in it.
+    pub fn new(url: &str, cache_path: PathBuf) -> anyhow::Result<RemoteDb> {
+        let Some(rest) = url.strip_prefix("ssh://") else {
+            bail!("'{url}' isn't an ssh:// URL");
+        };
+        let Some((authority, path)) = rest.split_once('/') else {
+            bail!("'{url}' is missing the path to the database");
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port.parse().with_context(|| format!("Bad port in '{url}'"))?)),
+            None => (authority, None),
+        };
+        let mut remote_path = format!("/{path}");
+        if !remote_path.ends_with(".db") {
+            remote_path = format!("{}/{DB_FILE_NAME}", remote_path.trim_end_matches('/'));
+        }
+        Ok(RemoteDb {
+            url: url.to_string(),
+            host: host.to_string(),
+            port,
+            remote_path,
+            cache_path,
+            db: None,
+            last_fetch: None,
+        })
+    }
+
+    pub fn is_remote(path: &str) -> bool {
+        path.starts_with("ssh://")
+    }
+
+    /// The local copy, fetching a fresh one first if it's due. A failed fetch keeps
+    /// serving the last good copy, since the gaming PC being asleep is normal.
+    pub fn connection(&mut self) -> anyhow::Result<&Connection> {
+        let due = self.last_fetch.is_none_or(|at| at.elapsed() >= FETCH_INTERVAL);
+        if due {
+            self.last_fetch = Some(Instant::now());
+            match self.fetch() {
+                Ok(()) => {}
+                Err(e) if self.db.is_some() => {
+                    tracing::warn!(url = %self.url, error = format!("{e:#}"), "couldn't fetch remote database, using the last copy");
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        self.db.as_ref().context("No copy of the remote database yet")
+    }
+
+    fn fetch(&mut self) -> anyhow::Result<()> {
+        if let Some(parent) = self.cache_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let download = self.cache_path.with_extension("download");
+        self.scp(&self.remote_path, &download)?;
+
+        // Swap the copy in under a closed connection. Whatever WAL went with the old
+        // copy is stale now, and any fresh one is fetched alongside.
+        if let Some(db) = self.db.take() {
+            let _ = db.close();
+        }
+        for suffix in ["-wal", "-shm"] {
+            let _ = fs::remove_file(format!("{}{suffix}", self.cache_path.display()));
+        }
+        fs::rename(&download, &self.cache_path)?;
+        // Changes the plugin hasn't checkpointed yet live in the WAL, if there is one
+        let wal_path = PathBuf::from(format!("{}-wal", self.cache_path.display()));
+        let _ = self.scp(&format!("{}-wal", self.remote_path), &wal_path);
+
+        // Read-write only so SQLite can build the shared memory file for the WAL;
+        // this is our private copy, nothing ever writes to it
+        self.db = Some(open_db_at(
+            &self.cache_path,
+            OpenFlags::SQLITE_OPEN_READ_WRITE | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?);
+        Ok(())
+    }
+
+    fn scp(&self, remote_path: &str, local_path: &std::path::Path) -> anyhow::Result<()> {
+        let mut command = Command::new("scp");
+        command.args(["-q", "-o", "BatchMode=yes", "-o", "ConnectTimeout=10"]);
+        if let Some(port) = self.port {
+            command.args(["-P", &port.to_string()]);
+        }
+        let output = command
+            .arg(format!("{}:{}", self.host, remote_path))
+            .arg(local_path)
+            .output()
+            .context("Couldn't run scp, is OpenSSH installed?")?;
+        if !output.status.success() {
+            bail!(
+                "scp from {} failed: {}",
+                self.host,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
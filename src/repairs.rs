@@ -0,0 +1,42 @@
+use crate::{
+    config::RepairConfig,
+    db::SubInfo,
+    i18n::{fill, Messages},
+};
+
+/// What the game stores for a part in perfect condition
+const FULL_CONDITION: f64 = 30000.0;
+
+/// The part in the worst condition, named in the language of `text`, and its condition
+/// in percent
+pub fn worst_part(sub: &SubInfo, text: &Messages) -> Option<(&'static str, f64)> {
+    let condition = sub.condition?;
+    text.parts
+        .into_iter()
+        .zip(condition)
+        .map(|(part, condition)| (part, f64::from(condition) * 100.0 / FULL_CONDITION))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+/// The worst part and roughly where it'll be once the voyage the sub's on is over.
+/// The database has the condition from when it was sent out, so the route's wear
+/// comes off that.
+pub fn after_voyage(sub: &SubInfo, config: &RepairConfig, text: &Messages) -> Option<(&'static str, f64)> {
+    let (part, condition) = worst_part(sub, text)?;
+    let wear = sub.route.len() as f64 * config.wear_per_sector;
+    Some((part, (condition - wear).max(0.0)))
+}
+
+/// The warning for a sub that'll be back below the threshold, if it will be
+pub fn warning(sub: &SubInfo, config: &RepairConfig, text: &Messages) -> Option<(String, String)> {
+    let (part, condition) = after_voyage(sub, config, text)?;
+    if condition >= config.threshold {
+        return None;
+    }
+    let title = fill(text.repair_title, &[("sub", &sub.name)]);
+    let body = fill(
+        text.repair_body,
+        &[("owner", &sub.owner()), ("sub", &sub.name), ("part", part), ("condition", &format!("{condition:.0}"))],
+    );
+    Some((title, body))
+}
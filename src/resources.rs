@@ -0,0 +1,42 @@
+use crate::{
+    db::SubInfo,
+    i18n::{fill, Messages},
+    sectors::Sectors,
+};
+
+/// Tanks it'll take to send each of the owner's subs out again on the route it's on
+/// now. Subs with no known route are left out, so this can come up short.
+pub fn tanks_for_round(fleet: &[&SubInfo], sectors: &Sectors) -> u32 {
+    fleet.iter().filter_map(|sub| sectors.route_tanks(&sub.route)).sum()
+}
+
+/// Owners whose FCs don't have the tanks for another round, with how many they have
+/// and how many it takes
+pub fn short_on_tanks(subs: &[SubInfo], sectors: &Sectors) -> Vec<(String, u32, u32)> {
+    let mut owners: Vec<String> = subs.iter().map(|sub| sub.owner()).collect();
+    owners.sort();
+    owners.dedup();
+    owners
+        .into_iter()
+        .filter_map(|owner| {
+            let fleet: Vec<&SubInfo> = subs.iter().filter(|sub| sub.owner() == owner).collect();
+            let have = fleet[0].tanks?;
+            let need = tanks_for_round(&fleet, sectors);
+            (have < need).then_some((owner, have, need))
+        })
+        .collect()
+}
+
+/// "412 tanks, 36 kits", or None if the database doesn't track them
+pub fn describe(sub: &SubInfo, text: &Messages) -> Option<String> {
+    match (sub.tanks, sub.kits) {
+        (None, None) => None,
+        (tanks, kits) => {
+            let count = |forms: [&'static str; 2], count: Option<u32>| match count {
+                Some(count) => fill(text.plural(forms, count as usize), &[("count", &count.to_string())]),
+                None => fill(forms[1], &[("count", "?")]),
+            };
+            Some(format!("{}, {}", count(text.tanks, tanks), count(text.kits, kits)))
+        }
+    }
+}
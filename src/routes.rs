@@ -0,0 +1,69 @@
+use std::io::Write;
+
+use chrono::Utc;
+
+use crate::{db::SubInfo, timefmt::TimeDisplay};
+
+/// First sector id of each voyage map, from the game's SubmarineExploration sheet.
+/// Sector letters count up from these, the way the plugin and route guides write them.
+const MAPS: &[(u32, &str)] = &[
+    (1, "Deep-sea Site"),
+    (32, "Sea of Ash"),
+    (54, "Sea of Jade"),
+    (74, "Sirensong Sea"),
+    (97, "Lilac Sea"),
+];
+
+/// A, B, ... Z, AA, AB, like spreadsheet columns
+fn letter(index: u32) -> String {
+    let mut letters = String::new();
+    let mut n = index + 1;
+    while n > 0 {
+        letters.insert(0, char::from(b'A' + ((n - 1) % 26) as u8));
+        n = (n - 1) / 26;
+    }
+    letters
+}
+
+fn map_of(sector: u32) -> Option<(u32, &'static str)> {
+    MAPS.iter().rev().find(|(first, _)| sector >= *first).copied()
+}
+
+/// Just the letter within its map, or the id if it's past the known maps
+pub fn sector_letter(sector: u32) -> String {
+    match map_of(sector) {
+        Some((start, _)) => letter(sector - start),
+        None => sector.to_string(),
+    }
+}
+
+/// "Deep-sea Site M-R-O-J-Z", or the bare ids for sectors past the known maps
+pub fn describe(route: &[u32]) -> String {
+    let Some(&first) = route.first() else {
+        return String::new();
+    };
+    let Some((_, map)) = map_of(first) else {
+        return route.iter().map(u32::to_string).collect::<Vec<_>>().join("-");
+    };
+    let sectors: Vec<String> = route.iter().map(|&sector| sector_letter(sector)).collect();
+    format!("{map} {}", sectors.join("-"))
+}
+
+/// `routes`: what each sub is out running, by character
+pub fn write(out: &mut dyn Write, subs: &[SubInfo], display: &TimeDisplay) -> anyhow::Result<()> {
+    let now = Utc::now();
+    let mut owners: Vec<String> = subs.iter().map(|sub| sub.owner()).collect();
+    owners.sort();
+    owners.dedup();
+    for owner in owners {
+        writeln!(out, "{owner}:")?;
+        for sub in subs.iter().filter(|sub| sub.owner() == owner) {
+            let route = match describe(&sub.route) {
+                route if route.is_empty() => display.text().no_route.to_string(),
+                route => route,
+            };
+            writeln!(out, "  {}: {route} ({})", sub.name, display.relative(sub.return_time, now))?;
+        }
+    }
+    Ok(())
+}
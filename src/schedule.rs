@@ -0,0 +1,45 @@
+//! When notifications go out, worked out from return times alone so other tools can
+//! line up with the daemon.
+
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::db::SubInfo;
+
+/// When the last of the owner's subs that are back within `window` of each other,
+/// chained from `sub`, returns. `subs` is sorted by return time.
+pub fn group_end(subs: &[SubInfo], sub: &SubInfo, window: TimeDelta) -> DateTime<Utc> {
+    let mut end = sub.return_time;
+    for other in subs.iter().filter(|other| other.owner() == sub.owner()) {
+        if other.return_time > end && other.return_time - end <= window {
+            end = other.return_time;
+        }
+    }
+    end
+}
+
+/// Splits returns into runs per owner where each is within `window` of the one before.
+/// `subs` is sorted by return time, and other owners' returns in between don't count.
+pub fn group_returns<'a>(subs: &[&'a SubInfo], window: TimeDelta) -> Vec<Vec<&'a SubInfo>> {
+    let mut groups: Vec<Vec<&SubInfo>> = Vec::new();
+    for sub in subs {
+        let group = groups.iter_mut().find(|group| {
+            let last = group[group.len() - 1];
+            last.owner() == sub.owner() && sub.return_time - last.return_time <= window
+        });
+        match group {
+            Some(group) => group.push(sub),
+            None => groups.push(vec![sub]),
+        }
+    }
+    groups
+}
+
+/// Heads-ups before `sub`'s return (`leads`, as time before it) that are due at `now`
+/// and not in `sent`, given as seconds before the return
+pub fn due_leads(sub: &SubInfo, leads: &[TimeDelta], sent: &[i64], now: DateTime<Utc>) -> Vec<TimeDelta> {
+    leads
+        .iter()
+        .copied()
+        .filter(|lead| sub.return_time - *lead <= now && !sent.contains(&lead.num_seconds()))
+        .collect()
+}
@@ -0,0 +1,108 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::PathBuf,
+};
+
+use chrono::TimeDelta;
+use reqwest::blocking::Client;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{data_dir, db::SubInfo, xivapi};
+
+/// Rows of the game's SubmarineExploration sheet, which has what each sector rewards
+/// and asks for. Fetched from XIVAPI as sectors turn up and kept on disk.
+#[derive(Default)]
+pub struct Sectors {
+    path: PathBuf,
+    rows: HashMap<u32, Value>,
+    /// Asked for already, so the daemon doesn't keep asking while XIVAPI is down
+    tried: HashSet<u32>,
+}
+
+impl Sectors {
+    pub fn load() -> Self {
+        let path = data_dir().join("sectors.json");
+        let rows = fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default();
+        Sectors { path, rows, tried: HashSet::new() }
+    }
+
+    /// Loaded, with every sector on `subs`' routes looked up
+    pub fn for_routes(subs: &[SubInfo]) -> Self {
+        let mut sectors = Sectors::load();
+        sectors.fetch(&Client::new(), subs.iter().flat_map(|sub| sub.route.iter().copied()));
+        sectors
+    }
+
+    /// Looks up whichever of `ids` aren't known yet. Failing leaves them unknown.
+    pub fn fetch(&mut self, client: &Client, ids: impl IntoIterator<Item = u32>) {
+        let mut missing: Vec<u32> = ids
+            .into_iter()
+            .filter(|id| !self.rows.contains_key(id) && !self.tried.contains(id))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return;
+        }
+        self.tried.extend(&missing);
+        match xivapi::fetch_rows(client, "SubmarineExploration", &[], &missing) {
+            Ok(found) => {
+                self.rows.extend(found);
+                if let Err(e) = self.save() {
+                    warn!(error = format!("{e:#}"), "couldn't save sectors");
+                }
+            }
+            Err(e) => warn!(error = format!("{e:#}"), "couldn't look up sectors"),
+        }
+    }
+
+    fn field(&self, sector: u32, name: &str) -> Option<u32> {
+        self.rows.get(&sector)?[name].as_u64().map(|value| value as u32)
+    }
+
+    /// `name` summed over the whole route, if every sector on it is known
+    fn route_total(&self, route: &[u32], name: &str) -> Option<u32> {
+        if route.is_empty() {
+            return None;
+        }
+        route.iter().map(|&sector| self.field(sector, name)).sum()
+    }
+
+    /// EXP the whole route gives
+    pub fn route_exp(&self, route: &[u32]) -> Option<u32> {
+        self.route_total(route, "ExpReward")
+    }
+
+    /// How long surveying the route's sectors takes. Travel between them isn't in here,
+    /// it depends on the build, so this errs on the early side.
+    pub fn route_duration(&self, route: &[u32]) -> Option<TimeDelta> {
+        self.route_total(route, "SurveyDurationmin").map(|minutes| TimeDelta::minutes(minutes.into()))
+    }
+
+    /// Ceruleum tanks it takes to send a sub out on the route
+    pub fn route_tanks(&self, route: &[u32]) -> Option<u32> {
+        self.route_total(route, "CeruleumTankReq")
+    }
+
+    fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, serde_json::to_string(&self.rows)?)?;
+        Ok(())
+    }
+}
+
+/// Voyages of `sub`'s current route until it ranks up, counting the one it's on
+pub fn voyages_to_rank(sub: &SubInfo, sectors: &Sectors) -> Option<u32> {
+    let per_voyage = sectors.route_exp(&sub.route).filter(|&exp| exp > 0)?;
+    if sub.next_exp == 0 {
+        return None;
+    }
+    Some(sub.next_exp.saturating_sub(sub.exp).div_ceil(per_voyage).max(1))
+}
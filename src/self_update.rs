@@ -0,0 +1,147 @@
+use std::{env, fs, io, path::Path, time::Duration};
+
+use anyhow::{bail, Context};
+use reqwest::blocking::Client;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::{config::BridgeConfig, config_path};
+
+const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/tyrone-sudeium/submarine-returns-rs/releases/latest";
+/// The release asset with every binary's SHA-256, in `sha256sum` format
+const CHECKSUMS_ASSET: &str = "SHA256SUMS";
+/// The bridge release builds have compiled in, .cargo/config.toml's placeholder
+const RELEASE_BRIDGE_URL: &str = "http://example.com";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// `self-update`: if the latest GitHub release is newer than this build, downloads its
+/// binary for this platform, checks it against the release's checksums and puts it
+/// where this one is. With `check_only` it just says whether there's one.
+pub fn run(check_only: bool) -> anyhow::Result<()> {
+    let client = Client::builder()
+        .user_agent(concat!("sub-returns/", env!("CARGO_PKG_VERSION")))
+        .timeout(Duration::from_secs(120))
+        .build()?;
+    let release: Release = client
+        .get(LATEST_RELEASE_URL)
+        .send()
+        .context("Couldn't reach GitHub")?
+        .error_for_status()
+        .context("Couldn't find the latest release")?
+        .json()?;
+    let current = env!("CARGO_PKG_VERSION");
+    let latest = release.tag_name.trim_start_matches('v');
+    if !is_newer(latest, current) {
+        println!("Already up to date ({current})");
+        return Ok(());
+    }
+    if check_only {
+        println!("{latest} is out, this is {current}");
+        return Ok(());
+    }
+
+    // Otherwise pushes would quietly start going to the placeholder instead
+    if BridgeConfig::default().url != RELEASE_BRIDGE_URL && !config_has_bridge()? {
+        bail!(
+            "This build has its own bridge compiled in, which release builds don't. Set [bridge] url and psk in {} \
+             first, or pushes will stop",
+            config_path().display()
+        );
+    }
+
+    let name = asset_name();
+    let asset = |name: &str| release.assets.iter().find(|asset| asset.name == name);
+    let Some(binary) = asset(&name) else {
+        bail!("Release {} has no {name}, you'll have to build this platform yourself", release.tag_name);
+    };
+    let Some(checksums) = asset(CHECKSUMS_ASSET) else {
+        bail!("Release {} has no {CHECKSUMS_ASSET}, so there's no checking the download", release.tag_name);
+    };
+    let checksums = download(&client, checksums)?;
+    let checksums = String::from_utf8_lossy(&checksums);
+    let Some(expected) = checksums.lines().find_map(|line| {
+        let (sum, file) = line.split_once(char::is_whitespace)?;
+        // sha256sum marks binary mode with a *
+        (file.trim_start().trim_start_matches('*') == name).then(|| sum.to_lowercase())
+    }) else {
+        bail!("{CHECKSUMS_ASSET} doesn't have {name} in it");
+    };
+    let bytes = download(&client, binary)?;
+    let actual: String = Sha256::digest(&bytes).iter().map(|byte| format!("{byte:02x}")).collect();
+    if actual != expected {
+        bail!("{name} doesn't match its checksum, not installing it");
+    }
+
+    let exe = env::current_exe()?;
+    replace(&exe, &bytes).with_context(|| format!("Couldn't replace {}", exe.display()))?;
+    println!("Updated {current} to {latest}. A running daemon keeps the old version until it's restarted.");
+    Ok(())
+}
+
+/// Whether config.toml gives the bridge's URL and PSK itself, rather than leaving them
+/// to the compiled-in ones
+fn config_has_bridge() -> anyhow::Result<bool> {
+    let contents = match fs::read_to_string(config_path()) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    let config: toml::Table = toml::from_str(&contents).context("Invalid config.toml")?;
+    let bridge = config.get("bridge").and_then(toml::Value::as_table);
+    Ok(bridge.is_some_and(|bridge| bridge.contains_key("url") && bridge.contains_key("psk")))
+}
+
+/// e.g. `sub-returns-linux-x86_64`, `sub-returns-macos-aarch64`, `sub-returns-windows-x86_64.exe`
+fn asset_name() -> String {
+    format!("sub-returns-{}-{}{}", env::consts::OS, env::consts::ARCH, env::consts::EXE_SUFFIX)
+}
+
+fn download(client: &Client, asset: &Asset) -> anyhow::Result<Vec<u8>> {
+    let response = client
+        .get(&asset.browser_download_url)
+        .send()
+        .and_then(|response| response.error_for_status())
+        .with_context(|| format!("Couldn't download {}", asset.name))?;
+    Ok(response.bytes()?.to_vec())
+}
+
+/// Dotted version numbers compared number by number, ignoring any `-beta` or `+build`
+fn is_newer(latest: &str, current: &str) -> bool {
+    let parse = |version: &str| -> Vec<u64> {
+        let release = version.split(['-', '+']).next().unwrap_or_default();
+        release.split('.').map(|part| part.parse().unwrap_or(0)).collect()
+    };
+    parse(latest) > parse(current)
+}
+
+/// Writes the new binary next to the old one and renames it over, so there's never a
+/// half-written executable where the old one was
+fn replace(exe: &Path, bytes: &[u8]) -> anyhow::Result<()> {
+    let new = exe.with_extension("new");
+    fs::write(&new, bytes)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&new, fs::Permissions::from_mode(0o755))?;
+    }
+    // Windows won't let a running executable be replaced, but it can be moved aside
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("old");
+        let _ = fs::remove_file(&old);
+        fs::rename(exe, &old)?;
+    }
+    fs::rename(&new, exe)?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use std::{env, path::PathBuf};
+
+use anyhow::bail;
+
+const LAUNCHD_LABEL: &str = "com.github.tyrone-sudeium.sub-returns";
+
+/// A LaunchAgent that keeps the daemon running from wherever this binary currently lives.
+pub fn generate_launchd_plist() -> anyhow::Result<String> {
+    let exe = env::current_exe()?;
+    let log_path = directories::UserDirs::new()
+        .map(|dirs| dirs.home_dir().join("Library/Logs/sub-returns.log"))
+        .unwrap_or_else(|| PathBuf::from("/tmp/sub-returns.log"));
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{LAUNCHD_LABEL}</string>
+    <key>ProgramArguments</key>
+    <array>
+        <string>{exe}</string>
+        <string>--daemon</string>
+    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <true/>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        exe = xml_escape(&exe.to_string_lossy()),
+        log = xml_escape(&log_path.to_string_lossy()),
+    ))
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// Registers the daemon to start at login and starts it now.
+#[cfg(target_os = "macos")]
+pub fn install() -> anyhow::Result<()> {
+    use std::{fs, process::Command};
+
+    let Some(user_dirs) = directories::UserDirs::new() else {
+        bail!("Couldn't find your home directory");
+    };
+    let agents_dir = user_dirs.home_dir().join("Library/LaunchAgents");
+    fs::create_dir_all(&agents_dir)?;
+    let plist_path = agents_dir.join(format!("{LAUNCHD_LABEL}.plist"));
+
+    // Loading over an existing agent fails, so reinstalling has to unload first
+    if plist_path.exists() {
+        let _ = Command::new("launchctl").arg("unload").arg(&plist_path).status();
+    }
+    fs::write(&plist_path, generate_launchd_plist()?)?;
+    let status = Command::new("launchctl").args(["load", "-w"]).arg(&plist_path).status()?;
+    if !status.success() {
+        bail!("launchctl couldn't load {}", plist_path.display());
+    }
+    println!("Installed and started {}", plist_path.display());
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+pub fn install() -> anyhow::Result<()> {
+    bail!("Use `--generate systemd-unit` to create a user service on Linux")
+}
+
+/// Adds the daemon to the user's startup programs and starts it now, both without a
+/// console window. Toasts don't need a console, so nothing is lost by hiding it.
+#[cfg(windows)]
+pub fn install() -> anyhow::Result<()> {
+    use std::{os::windows::process::CommandExt, process::Command};
+
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let exe = env::current_exe()?;
+    let run_command = format!("\"{}\" --daemon --hidden", exe.display());
+    let status = Command::new("reg")
+        .args(["add", r"HKCU\Software\Microsoft\Windows\CurrentVersion\Run"])
+        .args(["/v", "sub-returns", "/t", "REG_SZ", "/f", "/d", &run_command])
+        .status()?;
+    if !status.success() {
+        bail!("Couldn't add sub-returns to your startup programs");
+    }
+    Command::new(&exe)
+        .args(["--daemon", "--replace"])
+        .creation_flags(CREATE_NO_WINDOW)
+        .spawn()?;
+    println!("Registered to start at login and started the daemon");
+    Ok(())
+}
+
+/// Startup entries run in a fresh console window; this gets rid of it.
+#[cfg(windows)]
+pub fn hide_console() {
+    // SAFETY: FreeConsole has no preconditions, it fails harmlessly without a console
+    unsafe {
+        windows_sys::Win32::System::Console::FreeConsole();
+    }
+}
+
+#[cfg(not(windows))]
+pub fn hide_console() {}
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", windows)))]
+pub fn install() -> anyhow::Result<()> {
+    bail!("Installing a service isn't supported on this platform yet")
+}
@@ -0,0 +1,121 @@
+use std::{fs, io::Write, path::Path};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Utc};
+
+use crate::{
+    db::SubInfo,
+    discord,
+    i18n::fill,
+    image::{self, Canvas, Rgb, CHAR_WIDTH, LINE_HEIGHT},
+    theme::{Theme, Urgency},
+    timefmt::TimeDisplay,
+};
+
+/// Space around the schedule in the picture
+const MARGIN: usize = 24;
+/// Discord's dark theme, so it sits in the channel like a message does
+const BACKGROUND: Rgb = [49, 51, 56];
+const TEXT: Rgb = [219, 222, 225];
+const DIM: Rgb = [148, 155, 164];
+const HEADING: Rgb = [242, 243, 245];
+const RETURNED: Rgb = [35, 165, 90];
+const SOON: Rgb = [240, 178, 50];
+
+/// `share`: the schedule as a Discord message, in timestamps each reader sees in their
+/// own timezone
+pub fn write(out: &mut dyn Write, subs: &[SubInfo], display: &TimeDisplay) -> anyhow::Result<()> {
+    let mut subs = subs.to_vec();
+    subs.sort_by_key(|sub| sub.return_time);
+    let text = display.text();
+    writeln!(out, "## {}\n{}", text.share_title, discord::list(&subs, text))?;
+    Ok(())
+}
+
+/// `share --png`: the same drawn as a picture, in the configured timezone, for
+/// anywhere that doesn't understand Discord's timestamps. The font only has Latin
+/// letters, so the picture's own text falls back to English when it's in a language
+/// the font can't draw, and names it can't draw are turned down rather than drawn as
+/// question marks.
+pub fn write_png(path: &Path, subs: &[SubInfo], display: &TimeDisplay, theme: &Theme) -> anyhow::Result<()> {
+    let mut subs = subs.to_vec();
+    subs.sort_by_key(|sub| sub.return_time);
+    let now = Utc::now();
+    let drawable = |lines: &[Line]| lines.iter().flatten().all(|(column, _)| image::can_draw(column));
+    let mut lines = picture_lines(&subs, display, theme, now);
+    if !drawable(&lines) {
+        lines = picture_lines(&subs, &display.english(), theme, now);
+    }
+    if !drawable(&lines) {
+        bail!("The picture's font only has Latin letters, and some names have others, share them as text instead");
+    }
+
+    let mut widths: Vec<usize> = Vec::new();
+    for line in lines.iter().filter(|line| line.len() > 1) {
+        for (i, (column, _)) in line.iter().enumerate() {
+            match widths.get_mut(i) {
+                Some(width) => *width = (*width).max(column.chars().count()),
+                None => widths.push(column.chars().count()),
+            }
+        }
+    }
+    // Two spaces between columns; headings just need to fit
+    let row_chars = widths.iter().sum::<usize>() + 2 * widths.len().saturating_sub(1);
+    let chars = lines
+        .iter()
+        .filter(|line| line.len() == 1)
+        .map(|line| line[0].0.chars().count())
+        .chain([row_chars])
+        .max()
+        .unwrap_or_default();
+
+    let mut canvas = Canvas::new(chars * CHAR_WIDTH + 2 * MARGIN, lines.len() * LINE_HEIGHT + 2 * MARGIN, BACKGROUND);
+    for (row, line) in lines.iter().enumerate() {
+        let mut x = MARGIN;
+        for (i, (column, color)) in line.iter().enumerate() {
+            canvas.text(x, MARGIN + row * LINE_HEIGHT, column, *color);
+            x += (widths.get(i).copied().unwrap_or_default() + 2) * CHAR_WIDTH;
+        }
+    }
+    fs::write(path, canvas.png()).with_context(|| format!("Couldn't write {}", path.display()))?;
+    eprintln!("Saved the picture to {}", path.display());
+    Ok(())
+}
+
+/// One line of the picture as columns, so names and times line up under one another
+type Line = Vec<(String, Rgb)>;
+
+fn picture_lines(subs: &[SubInfo], display: &TimeDisplay, theme: &Theme, now: DateTime<Utc>) -> Vec<Line> {
+    let text = display.text();
+    let mut lines: Vec<Line> = vec![
+        vec![(text.share_title.to_string(), HEADING)],
+        vec![(fill(text.share_as_of, &[("time", &display.short(now))]), DIM)],
+    ];
+    if subs.is_empty() {
+        lines.push(Vec::new());
+        lines.push(vec![(text.no_subs.to_string(), TEXT)]);
+    }
+    let mut owners: Vec<String> = Vec::new();
+    for sub in subs {
+        if !owners.contains(&sub.owner()) {
+            owners.push(sub.owner());
+        }
+    }
+    for owner in &owners {
+        lines.push(Vec::new());
+        lines.push(vec![(owner.clone(), HEADING)]);
+        for sub in subs.iter().filter(|sub| sub.owner() == *owner) {
+            let color = match theme.urgency(sub.return_time, now) {
+                Urgency::Returned => RETURNED,
+                Urgency::Soon => SOON,
+                Urgency::Later => TEXT,
+            };
+            lines.push(vec![
+                (format!("  {}", sub.name), TEXT),
+                (display.short(sub.return_time), color),
+                (display.relative(sub.return_time, now), DIM),
+            ]);
+        }
+    }
+    lines
+}
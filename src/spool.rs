@@ -0,0 +1,69 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+use serde_json::Value;
+
+/// Bridge payloads that couldn't be delivered, kept on disk (one JSON object per line)
+/// until the bridge is reachable again. Pushover messages carry their own timestamp,
+/// so a late delivery still shows when the sub actually returned.
+pub struct Spool {
+    path: PathBuf,
+}
+
+impl Spool {
+    pub fn new(path: PathBuf) -> Self {
+        Spool { path }
+    }
+
+    pub fn push(&self, payload: &Value) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        writeln!(file, "{}", payload)?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        fs::metadata(&self.path)
+            .map(|m| m.len() == 0)
+            .unwrap_or(true)
+    }
+
+    /// Sends spooled payloads oldest first, stopping at the first failure so ordering
+    /// is preserved. Whatever wasn't sent is written back for next time.
+    pub fn flush<F>(&self, mut send: F) -> anyhow::Result<usize>
+    where
+        F: FnMut(&Value) -> anyhow::Result<()>,
+    {
+        if self.is_empty() {
+            return Ok(0);
+        }
+        let reader = BufReader::new(File::open(&self.path)?);
+        let pending: Vec<Value> = reader
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(&line).ok())
+            .collect();
+
+        let mut sent = 0;
+        for payload in &pending {
+            if send(payload).is_err() {
+                break;
+            }
+            sent += 1;
+        }
+
+        let mut file = File::create(&self.path)?;
+        for payload in &pending[sent..] {
+            writeln!(file, "{}", payload)?;
+        }
+        Ok(sent)
+    }
+}
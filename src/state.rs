@@ -0,0 +1,35 @@
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use crate::daemon::NotifyMeta;
+
+/// The daemon's per-sub notification bookkeeping (keyed by `SubInfo::key`), saved as
+/// JSON so a restart picks up where the last run left off instead of re-firing (or
+/// forgetting) notifications.
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    pub fn new(path: PathBuf) -> Self {
+        StateFile { path }
+    }
+
+    /// A missing or unreadable state file just means starting fresh.
+    pub fn load(&self) -> HashMap<String, NotifyMeta> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, notifs_data: &HashMap<String, NotifyMeta>) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Write then rename so being killed mid-write can't leave a truncated file behind
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string(notifs_data)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+}
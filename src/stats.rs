@@ -0,0 +1,147 @@
+use std::io::Write;
+
+use chrono::{TimeDelta, Utc};
+use reqwest::blocking::Client;
+
+use crate::{
+    db::{SubInfo, SubSource},
+    i18n::{fill, Messages},
+    loot::{self, Voyage},
+    market::{self, Prices},
+    routes,
+    sectors::Sectors,
+};
+
+/// How many of the most visited sectors to list
+const TOP_SECTORS: usize = 3;
+
+/// `stats`: how each sub, and each FC's subs together, have done over the last `weeks`
+/// of loot history
+pub fn write(
+    out: &mut dyn Write,
+    sources: &[SubSource],
+    subs: &[SubInfo],
+    weeks: u32,
+    market: Option<&str>,
+    text: &Messages,
+) -> anyhow::Result<()> {
+    let since = Utc::now() - TimeDelta::weeks(weeks.into());
+    let voyages: Vec<Voyage> = loot::read_all(sources)?
+        .into_iter()
+        .filter(|voyage| voyage.returned >= since)
+        .collect();
+    let client = Client::new();
+    let prices = loot::prices(&client, market, &voyages);
+    let mut sectors = Sectors::load();
+    sectors.fetch(&client, voyages.iter().flat_map(|voyage| voyage.sectors.iter().map(|sector| sector.sector)));
+    let stats = |histories: &[Vec<&Voyage>]| Stats::of(histories, weeks, prices.as_ref(), &sectors).describe(text);
+
+    let mut fcs: Vec<String> = subs.iter().map(free_company).collect();
+    fcs.sort();
+    fcs.dedup();
+    for fc in fcs {
+        let owned: Vec<(&SubInfo, Vec<&Voyage>)> = subs
+            .iter()
+            .filter(|sub| free_company(sub) == fc)
+            .map(|sub| (sub, voyages.iter().filter(|voyage| voyage.key == sub.key()).collect()))
+            .collect();
+        let histories: Vec<Vec<&Voyage>> = owned.iter().map(|(_, history)| history.clone()).collect();
+        writeln!(out, "{fc}: {}", stats(&histories))?;
+        for (sub, history) in owned {
+            writeln!(out, "  {}: {}", sub.name, stats(&[history]))?;
+        }
+    }
+    Ok(())
+}
+
+/// The FC a sub belongs to, e.g. `«ABC»` or `«ABC» @ Alt`, whichever character it was
+/// last seen from
+fn free_company(sub: &SubInfo) -> String {
+    match &sub.source {
+        Some(label) => format!("«{}» @ {label}", sub.tag),
+        None => format!("«{}»", sub.tag),
+    }
+}
+
+struct Stats {
+    voyages: usize,
+    per_week: f64,
+    average_gil: Option<u64>,
+    /// Share of the time between returns spent out on a voyage rather than waiting to
+    /// be sent out again
+    uptime: Option<f64>,
+    /// Sector ids and visits, most visited first
+    most_visited: Vec<(u32, usize)>,
+}
+
+impl Stats {
+    /// Over one history per sub, each newest first
+    fn of(histories: &[Vec<&Voyage>], weeks: u32, prices: Option<&Prices>, sectors: &Sectors) -> Stats {
+        let all: Vec<&Voyage> = histories.iter().flatten().copied().collect();
+        // A sub that only started recently shouldn't look slow for it
+        let covered = all
+            .iter()
+            .map(|voyage| Utc::now() - voyage.returned)
+            .max()
+            .unwrap_or_default()
+            .clamp(TimeDelta::days(1), TimeDelta::weeks(weeks.into()));
+        let per_week = all.len() as f64 * TimeDelta::weeks(1).num_seconds() as f64 / covered.num_seconds() as f64;
+
+        let average_gil = prices
+            .filter(|_| !all.is_empty())
+            .map(|prices| all.iter().map(|voyage| voyage.value(prices)).sum::<u64>() / all.len() as u64);
+
+        // Each return to the next is the idle time before it went out again plus that
+        // next voyage, which takes as long as its route does
+        let (mut busy, mut elapsed) = (TimeDelta::zero(), TimeDelta::zero());
+        for history in histories {
+            for pair in history.windows(2) {
+                let (newer, older) = (pair[0], pair[1]);
+                let route: Vec<u32> = newer.sectors.iter().map(|sector| sector.sector).collect();
+                if let Some(duration) = sectors.route_duration(&route) {
+                    let gap = newer.returned - older.returned;
+                    busy += duration.min(gap);
+                    elapsed += gap;
+                }
+            }
+        }
+        let uptime = (elapsed > TimeDelta::zero())
+            .then(|| busy.num_seconds() as f64 / elapsed.num_seconds() as f64);
+
+        let mut most_visited: Vec<(u32, usize)> = Vec::new();
+        for sector in all.iter().flat_map(|voyage| &voyage.sectors) {
+            match most_visited.iter_mut().find(|(id, _)| *id == sector.sector) {
+                Some((_, visits)) => *visits += 1,
+                None => most_visited.push((sector.sector, 1)),
+            }
+        }
+        most_visited.sort_by_key(|&(id, visits)| (std::cmp::Reverse(visits), id));
+        most_visited.truncate(TOP_SECTORS);
+
+        Stats { voyages: all.len(), per_week, average_gil, uptime, most_visited }
+    }
+
+    /// "12 voyages (3.0 a week), about 80,800 gil each, out 85% of the time; mostly
+    /// Deep-sea Site M (12), ..."
+    fn describe(&self, text: &Messages) -> String {
+        if self.voyages == 0 {
+            return text.stats_none.to_string();
+        }
+        let mut described = fill(
+            text.plural(text.stats_voyages, self.voyages),
+            &[("count", &self.voyages.to_string()), ("per_week", &format!("{:.1}", self.per_week))],
+        );
+        if let Some(gil) = self.average_gil {
+            described += &fill(text.stats_gil, &[("gil", &market::gil(gil))]);
+        }
+        if let Some(uptime) = self.uptime {
+            described += &fill(text.stats_uptime, &[("percent", &format!("{:.0}", uptime * 100.0))]);
+        }
+        let visited: Vec<String> = self
+            .most_visited
+            .iter()
+            .map(|(sector, visits)| format!("{} ({visits})", routes::describe(&[*sector])))
+            .collect();
+        described + &fill(text.stats_sectors, &[("sectors", &visited.join(", "))])
+    }
+}
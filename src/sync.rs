@@ -0,0 +1,165 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::Read,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use reqwest::blocking::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::{
+    config::{Config, SyncConfig},
+    data_dir,
+    db::{self, Snapshots, SubInfo},
+};
+
+/// How often the agent re-reads its databases.
+const AGENT_POLL_INTERVAL: Duration = Duration::from_secs(30);
+/// Resend even if nothing changed, so a collector that restarted catches up.
+const AGENT_RESEND_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Snapshots bigger than this aren't from an agent.
+const MAX_SNAPSHOT_BYTES: u64 = 1024 * 1024;
+
+/// What an agent sends: every sub it can see, replacing whatever it sent last time.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub agent: String,
+    pub subs: Vec<SubInfo>,
+}
+
+fn require_key(sync: &SyncConfig) -> anyhow::Result<&str> {
+    match sync.key.as_deref() {
+        Some(key) if !key.is_empty() => Ok(key),
+        _ => bail!("Set [sync] key in the config to the same secret on the agent and the collector"),
+    }
+}
+
+/// Reads the local databases and sends them to the collector whenever they change.
+/// Runs until killed; the collector holds on to the last snapshot while we're gone.
+pub fn run_agent(config: &Config) -> anyhow::Result<()> {
+    let key = require_key(&config.sync)?;
+    let Some(url) = config.sync.collector_url.as_deref() else {
+        bail!("Set [sync] collector_url in the config to where the collector is listening");
+    };
+    let agent = config.sync.agent_name.clone().unwrap_or_else(default_agent_name);
+    let client = Client::builder().timeout(Duration::from_secs(10)).build()?;
+    let sources = db::open_sources(&config.sources)?;
+
+    let mut last_sent: Option<(String, Instant)> = None;
+    loop {
+        match db::get_all_submarine_info(&sources) {
+            Ok(subs) => {
+                let body = serde_json::to_string(&Snapshot { agent: agent.clone(), subs })?;
+                let due = last_sent
+                    .as_ref()
+                    .is_none_or(|(sent, at)| *sent != body || at.elapsed() >= AGENT_RESEND_INTERVAL);
+                if due {
+                    let sent = client
+                        .post(url)
+                        .bearer_auth(key)
+                        .header("Content-Type", "application/json")
+                        .body(body.clone())
+                        .send()
+                        .and_then(|response| response.error_for_status());
+                    match sent {
+                        Ok(_) => {
+                            info!(%agent, "sent snapshot to collector");
+                            last_sent = Some((body, Instant::now()));
+                        }
+                        Err(e) => warn!(error = %e, "couldn't reach the collector"),
+                    }
+                }
+            }
+            Err(e) => warn!(error = format!("{e:#}"), "couldn't read the database"),
+        }
+        thread::sleep(AGENT_POLL_INTERVAL);
+    }
+}
+
+fn default_agent_name() -> String {
+    std::env::var("COMPUTERNAME")
+        .or_else(|_| std::env::var("HOSTNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok().map(|name| name.trim().to_string()))
+        .unwrap_or_else(|| "agent".to_string())
+}
+
+fn snapshots_path() -> PathBuf {
+    data_dir().join("collector-snapshots.json")
+}
+
+/// Starts listening for agents in the background. Snapshots are saved to disk too, so
+/// a restarted collector still knows about subs on an agent that's currently asleep.
+pub fn start_collector(sync: &SyncConfig) -> anyhow::Result<Snapshots> {
+    let key = require_key(sync)?.to_string();
+    // Plain HTTP, so only this machine unless it's asked for
+    let listen = sync.listen.as_deref().unwrap_or("127.0.0.1:1415");
+    let server = tiny_http::Server::http(listen)
+        .map_err(|e| anyhow::anyhow!("{e}"))
+        .with_context(|| format!("Couldn't listen on {listen}"))?;
+    info!(%listen, "collector listening for agents");
+
+    let saved = fs::read_to_string(snapshots_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default();
+    let snapshots: Snapshots = Arc::new(Mutex::new(saved));
+    let server_snapshots = snapshots.clone();
+    thread::spawn(move || {
+        let expected_auth = format!("Bearer {key}");
+        for mut request in server.incoming_requests() {
+            let authorized = request
+                .headers()
+                .iter()
+                .any(|h| h.field.equiv("Authorization") && same_secret(h.value.as_str(), &expected_auth));
+            let status = if !authorized {
+                401
+            } else if *request.method() != tiny_http::Method::Post {
+                405
+            } else {
+                let mut body = String::new();
+                let read = request
+                    .as_reader()
+                    .take(MAX_SNAPSHOT_BYTES)
+                    .read_to_string(&mut body);
+                match read.ok().and_then(|_| serde_json::from_str::<Snapshot>(&body).ok()) {
+                    Some(snapshot) => {
+                        info!(agent = %snapshot.agent, subs = snapshot.subs.len(), "snapshot received");
+                        let mut snapshots = server_snapshots.lock().unwrap_or_else(PoisonError::into_inner);
+                        snapshots.insert(snapshot.agent, snapshot.subs);
+                        if let Err(e) = save_snapshots(&snapshots) {
+                            warn!(error = format!("{e:#}"), "couldn't save collector snapshots");
+                        }
+                        204
+                    }
+                    None => 400,
+                }
+            };
+            let _ = request.respond(tiny_http::Response::empty(status));
+        }
+    });
+    Ok(snapshots)
+}
+
+/// Compares digests of the two, all the way through, so neither how long it takes nor
+/// where it stops says how much of the key an agent got right
+fn same_secret(given: &str, expected: &str) -> bool {
+    let (given, expected) = (Sha256::digest(given), Sha256::digest(expected));
+    given.iter().zip(expected.iter()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn save_snapshots(snapshots: &HashMap<String, Vec<SubInfo>>) -> anyhow::Result<()> {
+    let path = snapshots_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string(snapshots)?)?;
+    Ok(())
+}
@@ -0,0 +1,192 @@
+// Only unix can run daemons as other users, elsewhere `run` just says so
+#![cfg_attr(not(unix), allow(dead_code, unused_imports))]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Child, Command},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use serde::Deserialize;
+use tracing::{error, info, warn};
+
+use crate::systemd;
+
+/// Who the system daemon runs for
+pub const USERS_PATH: &str = "/etc/sub-returns/users.toml";
+/// A user's daemon that stops sooner than this after starting waits this long before
+/// it's started again, so one that can't start doesn't spin
+const RESTART_DELAY: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize)]
+struct UsersFile {
+    users: Vec<SystemUser>,
+}
+
+#[derive(Deserialize)]
+struct SystemUser {
+    name: String,
+    /// Looked up in /etc/passwd if left out
+    home: Option<PathBuf>,
+}
+
+/// One user's daemon, started as them with their home as its home, so it reads their
+/// config and databases and notifies through their own backends
+struct UserDaemon {
+    name: String,
+    home: PathBuf,
+    child: Option<Child>,
+    started: Instant,
+}
+
+/// `--daemon --system`: runs a daemon for each user in [`USERS_PATH`], restarting any
+/// that stop, until told to stop itself.
+#[cfg(unix)]
+pub fn run(verbose: u8) -> anyhow::Result<()> {
+    let contents = fs::read_to_string(USERS_PATH).with_context(|| format!("Couldn't read {USERS_PATH}"))?;
+    let users: UsersFile = toml::from_str(&contents).with_context(|| format!("Invalid {USERS_PATH}"))?;
+    if users.users.is_empty() {
+        bail!("{USERS_PATH} has no [[users]] to run for");
+    }
+    let mut daemons = Vec::new();
+    for user in users.users {
+        let home = match user.home {
+            Some(home) => home,
+            None => passwd_home(&user.name)?,
+        };
+        daemons.push(UserDaemon { name: user.name, home, child: None, started: Instant::now() });
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .context("Couldn't install the shutdown signal handler")?;
+
+    let watchdog = systemd::watchdog_interval().is_some();
+    systemd::notify("READY=1");
+    let mut first = true;
+    while running.load(Ordering::SeqCst) {
+        for daemon in &mut daemons {
+            let exited = match daemon.child.as_mut().map(Child::try_wait) {
+                Some(Ok(Some(status))) => Some(status.to_string()),
+                Some(Err(e)) => Some(e.to_string()),
+                Some(Ok(None)) | None => None,
+            };
+            if let Some(status) = exited {
+                warn!(user = daemon.name, status, "daemon stopped");
+                daemon.child = None;
+            }
+            if daemon.child.is_none() && (first || daemon.started.elapsed() >= RESTART_DELAY) {
+                if let Err(e) = daemon.start(verbose) {
+                    error!(user = daemon.name, error = format!("{e:#}"), "couldn't start daemon");
+                }
+            }
+        }
+        first = false;
+        if watchdog {
+            systemd::notify("WATCHDOG=1");
+        }
+        thread::sleep(Duration::from_secs(1));
+    }
+
+    systemd::notify("STOPPING=1");
+    info!("stopping everyone's daemons");
+    for daemon in &mut daemons {
+        daemon.stop();
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run(_verbose: u8) -> anyhow::Result<()> {
+    bail!("--system is only for Linux and macOS servers")
+}
+
+#[cfg(unix)]
+impl UserDaemon {
+    fn start(&mut self, verbose: u8) -> anyhow::Result<()> {
+        use std::os::unix::{fs::MetadataExt, process::CommandExt};
+
+        self.started = Instant::now();
+        // Whoever owns the home directory is who the daemon runs as
+        let owner = fs::metadata(&self.home).with_context(|| format!("Couldn't find {}", self.home.display()))?;
+        let mut command = Command::new(std::env::current_exe()?);
+        command.arg("--daemon");
+        for _ in 0..verbose {
+            command.arg("-v");
+        }
+        command
+            .env("HOME", &self.home)
+            .env("USER", &self.name)
+            .env("LOGNAME", &self.name)
+            // Otherwise they'd all share ours instead of going by their own home
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("XDG_CACHE_HOME")
+            .env_remove("XDG_STATE_HOME")
+            .env_remove("XDG_RUNTIME_DIR")
+            .env_remove("DBUS_SESSION_BUS_ADDRESS")
+            // Only this process talks to systemd
+            .env_remove("NOTIFY_SOCKET")
+            .env_remove("WATCHDOG_USEC")
+            .env_remove("WATCHDOG_PID");
+        // Logged in, they have a session bus for desktop notifications to go through
+        let runtime_dir = Path::new("/run/user").join(owner.uid().to_string());
+        if runtime_dir.join("bus").exists() {
+            command
+                .env("DBUS_SESSION_BUS_ADDRESS", format!("unix:path={}", runtime_dir.join("bus").display()))
+                .env("XDG_RUNTIME_DIR", &runtime_dir);
+        }
+        // SAFETY: getuid has no preconditions and can't fail
+        match unsafe { libc::getuid() } {
+            0 => {
+                command.uid(owner.uid()).gid(owner.gid());
+            }
+            uid if uid != owner.uid() => {
+                bail!("Running {}'s daemon needs root, since {} isn't ours", self.name, self.home.display())
+            }
+            _ => {}
+        }
+        let child = command.spawn().with_context(|| format!("Couldn't start {}'s daemon", self.name))?;
+        info!(user = self.name, pid = child.id(), "started daemon");
+        self.child = Some(child);
+        Ok(())
+    }
+
+    /// Asks it to stop the way a signal would, so it saves its state, and waits a
+    /// while for it to
+    fn stop(&mut self) {
+        let Some(child) = &mut self.child else {
+            return;
+        };
+        let _ = Command::new("kill").args(["-TERM", &child.id().to_string()]).status();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while Instant::now() < deadline {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            thread::sleep(Duration::from_millis(250));
+        }
+        warn!(user = self.name, "daemon didn't stop, killing it");
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
+
+/// The home directory /etc/passwd has for `name`
+fn passwd_home(name: &str) -> anyhow::Result<PathBuf> {
+    let passwd = fs::read_to_string("/etc/passwd").context("Couldn't read /etc/passwd")?;
+    passwd
+        .lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&name))
+        .and_then(|fields| fields.get(5).map(PathBuf::from))
+        .with_context(|| format!("No user {name} in /etc/passwd, give their home in {USERS_PATH}"))
+}
@@ -0,0 +1,84 @@
+use std::{env, time::Duration};
+
+/// How often systemd expects a `WATCHDOG=1`, if the unit has `WatchdogSec` set.
+pub fn watchdog_interval() -> Option<Duration> {
+    let usec: u64 = env::var("WATCHDOG_USEC").ok()?.parse().ok()?;
+    // WATCHDOG_PID is only set when the watchdog is meant for a specific process
+    if let Ok(pid) = env::var("WATCHDOG_PID") {
+        if pid.parse() != Ok(std::process::id()) {
+            return None;
+        }
+    }
+    Some(Duration::from_micros(usec))
+}
+
+/// Sends a state update (`READY=1`, `WATCHDOG=1`, ...) to systemd. Does nothing
+/// when not running under systemd.
+#[cfg(target_os = "linux")]
+pub fn notify(state: &str) {
+    use std::os::{linux::net::SocketAddrExt, unix::net::{SocketAddr, UnixDatagram}};
+
+    let Some(socket_path) = env::var_os("NOTIFY_SOCKET") else {
+        return;
+    };
+    let socket_path = socket_path.to_string_lossy();
+    let addr = match socket_path.strip_prefix('@') {
+        Some(name) => SocketAddr::from_abstract_name(name),
+        None => SocketAddr::from_pathname(socket_path.as_ref()),
+    };
+    let sent = UnixDatagram::unbound()
+        .and_then(|socket| addr.and_then(|addr| socket.send_to_addr(state.as_bytes(), &addr)));
+    if let Err(e) = sent {
+        tracing::warn!(error = %e, "couldn't notify systemd");
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn notify(_state: &str) {}
+
+/// A user unit that runs the daemon from wherever this binary currently lives. The
+/// watchdog is only fed between passes, so it allows for a pass's requests each taking
+/// as long as the daemon lets one.
+pub fn generate_unit() -> anyhow::Result<String> {
+    let exe = env::current_exe()?;
+    Ok(format!(
+        "[Unit]
+Description=Submarine return notifications
+After=graphical-session.target network-online.target
+
+[Service]
+Type=notify
+NotifyAccess=main
+ExecStart=\"{exe}\" --daemon
+Restart=on-failure
+WatchdogSec=120
+
+[Install]
+WantedBy=default.target
+",
+        exe = exe.display()
+    ))
+}
+
+/// A system unit for `--daemon --system`, which starts each user's daemon as them.
+pub fn generate_system_unit() -> anyhow::Result<String> {
+    let exe = env::current_exe()?;
+    Ok(format!(
+        "[Unit]
+Description=Submarine return notifications for everyone in /etc/sub-returns/users.toml
+After=network-online.target
+Wants=network-online.target
+
+[Service]
+Type=notify
+NotifyAccess=main
+ExecStart=\"{exe}\" --daemon --system
+Restart=on-failure
+WatchdogSec=120
+
+[Install]
+WantedBy=multi-user.target
+",
+        exe = exe.display()
+    ))
+}
@@ -0,0 +1,151 @@
+use anyhow::Context;
+use chrono::{DateTime, TimeDelta, Utc};
+use handlebars::Handlebars;
+use serde_json::{json, Value};
+
+use crate::{
+    config::NotificationConfig, db::SubInfo, duration::format_duration, timefmt::TimeDisplay,
+};
+
+/// A user-supplied Handlebars template, e.g. `{{character}} {{sub}} {{return_relative}}`.
+/// Output isn't HTML-escaped, it's for terminals and scripts.
+pub struct Template {
+    registry: Handlebars<'static>,
+}
+
+impl Template {
+    pub fn new(source: &str) -> anyhow::Result<Self> {
+        Self::with_escape(source, handlebars::no_escape)
+    }
+
+    /// For a shell command: `{{sub}}` and the like come out quoted, so names with spaces
+    /// or quotes in them are one argument. `{{{sub}}}` leaves them as they are.
+    pub fn for_shell(source: &str) -> anyhow::Result<Self> {
+        Self::with_escape(source, |value| format!("'{}'", value.replace('\'', "'\\''")))
+    }
+
+    fn with_escape(source: &str, escape: fn(&str) -> String) -> anyhow::Result<Self> {
+        let mut registry = Handlebars::new();
+        registry.set_strict_mode(true);
+        registry.register_escape_fn(escape);
+        registry
+            .register_template_string("template", source)
+            .map_err(|e| anyhow::anyhow!("Invalid template: {e}"))?;
+        Ok(Template { registry })
+    }
+
+    pub fn render(&self, context: &Value) -> anyhow::Result<String> {
+        self.registry
+            .render("template", context)
+            .map_err(|e| anyhow::anyhow!("Couldn't fill in the template: {e}"))
+    }
+}
+
+/// The variables a template can use for one sub
+pub fn sub_context(sub: &SubInfo, display: &TimeDisplay, now: DateTime<Utc>) -> Value {
+    json!({
+        "sub": sub.name,
+        "id": sub.id,
+        "character": sub.character_name,
+        "tag": sub.tag,
+        "owner": sub.owner(),
+        "source": sub.source,
+        "return_time": display.long(sub.return_time).trim_start(),
+        "return_iso": sub.return_time.to_rfc3339(),
+        "return_unix": sub.return_time.timestamp(),
+        "return_relative": display.relative(sub.return_time, now),
+        "returned": sub.return_time <= now,
+        "estimated": sub.estimated,
+        "route": crate::routes::describe(&sub.route),
+        "rank": sub.rank,
+        "build": crate::parts::build_code(&sub.parts),
+        "exp": sub.exp,
+        "next_exp": sub.next_exp,
+    })
+}
+
+/// Titles and bodies for the daemon's notifications, from `[notifications]` in the
+/// config or the built-in ones in the display language.
+pub struct NotificationTemplates {
+    pub push_title: Template,
+    pub push_body: Template,
+    pub desktop_title: Template,
+    pub desktop_body: Template,
+    pub lead_title: Template,
+    pub lead_body: Template,
+    pub nag_title: Template,
+    pub nag_body: Template,
+    pub overdue_title: Template,
+    pub overdue_body: Template,
+    pub voyage_title: Template,
+    pub voyage_body: Template,
+    pub all_home_title: Template,
+    pub all_home_body: Template,
+}
+
+impl NotificationTemplates {
+    pub fn new(config: &NotificationConfig, display: &TimeDisplay) -> anyhow::Result<Self> {
+        // Unknown variables only show up when rendering, and the daemon is a bad place
+        // to find out, so try each one once now
+        let sample = SubInfo {
+            id: 1,
+            name: "Sub".to_string(),
+            return_time: Utc::now(),
+            tag: "TAG".to_string(),
+            character_name: "Character".to_string(),
+            source: None,
+            route: vec![1, 2],
+            rank: 50,
+            parts: [9, 2, 7, 16],
+            exp: 1000,
+            next_exp: 2000,
+            condition: Some([30000; 4]),
+            tanks: Some(100),
+            kits: Some(10),
+            estimated: false,
+        };
+        let returned = notification_context(&sample, display, 2);
+        let lead = lead_context(&sample, display, TimeDelta::minutes(30));
+        let text = display.text();
+        let load = |custom: &Option<String>, default: &str, name: &str, sample: &Value| {
+            let template = Template::new(custom.as_deref().unwrap_or(default))
+                .and_then(|template| template.render(sample).map(|_| template))
+                .with_context(|| format!("notifications.{name}"))?;
+            anyhow::Ok(template)
+        };
+        Ok(NotificationTemplates {
+            push_title: load(&config.push_title, text.returned_title, "push_title", &returned)?,
+            push_body: load(&config.push_body, text.returned_body, "push_body", &returned)?,
+            desktop_title: load(&config.desktop_title, text.returned_title, "desktop_title", &returned)?,
+            desktop_body: load(&config.desktop_body, text.returned_body, "desktop_body", &returned)?,
+            lead_title: load(&config.lead_title, text.lead_title, "lead_title", &lead)?,
+            lead_body: load(&config.lead_body, text.lead_body, "lead_body", &lead)?,
+            nag_title: load(&config.nag_title, text.nag_title, "nag_title", &returned)?,
+            nag_body: load(&config.nag_body, text.nag_body, "nag_body", &returned)?,
+            overdue_title: load(&config.overdue_title, text.overdue_title, "overdue_title", &returned)?,
+            overdue_body: load(&config.overdue_body, text.overdue_body, "overdue_body", &returned)?,
+            voyage_title: load(&config.voyage_title, text.voyage_title, "voyage_title", &returned)?,
+            voyage_body: load(&config.voyage_body, text.voyage_body, "voyage_body", &returned)?,
+            all_home_title: load(&config.all_home_title, text.all_home_title, "all_home_title", &returned)?,
+            all_home_body: load(&config.all_home_body, text.all_home_body, "all_home_body", &returned)?,
+        })
+    }
+}
+
+/// Everything `sub_context` has, with the notification time format, plus `count` (subs
+/// in this notification) and `others` (count minus this one)
+pub fn notification_context(sub: &SubInfo, display: &TimeDisplay, count: u32) -> Value {
+    let mut context = sub_context(sub, display, Utc::now());
+    context["return_time"] = json!(display.notification(sub.return_time));
+    context["count"] = json!(count);
+    context["others"] = json!(count.saturating_sub(1));
+    context
+}
+
+/// `notification_context` for a heads-up, with `lead` as how long before the return it
+/// goes out (`30m`)
+pub fn lead_context(sub: &SubInfo, display: &TimeDisplay, lead: TimeDelta) -> Value {
+    let mut context = notification_context(sub, display, 1);
+    context["lead"] = json!(format_duration(lead));
+    context
+}
@@ -0,0 +1,195 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+};
+
+use anyhow::bail;
+use chrono::{DateTime, TimeDelta, Utc};
+use clap::ValueEnum;
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::ThemeConfig;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq)]
+pub enum ColorChoice {
+    /// When printing to a terminal and NO_COLOR isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How soon a sub needs attention, which decides its colour
+#[derive(Clone, Copy, PartialEq)]
+pub enum Urgency {
+    Returned,
+    /// Back within the hour, or `soon_within`
+    Soon,
+    Later,
+}
+
+/// Lines to draw the table with
+pub struct Borders {
+    pub horizontal: char,
+    pub vertical: char,
+    /// The left, in between and right corners of the line above the header, the one
+    /// under it and the one at the bottom
+    pub top: [char; 3],
+    pub middle: [char; 3],
+    pub bottom: [char; 3],
+}
+
+const UNICODE_BORDERS: Borders = Borders {
+    horizontal: '─',
+    vertical: '│',
+    top: ['┌', '┬', '┐'],
+    middle: ['├', '┼', '┤'],
+    bottom: ['└', '┴', '┘'],
+};
+
+const ASCII_BORDERS: Borders = Borders {
+    horizontal: '-',
+    vertical: '|',
+    top: ['+', '+', '+'],
+    middle: ['+', '+', '+'],
+    bottom: ['+', '+', '+'],
+};
+
+/// Cells in a progress bar, not counting the percentage after it
+const BAR_WIDTH: usize = 10;
+
+/// Terminal colours and glyphs, from `[theme]`, toned down to what the terminal can
+/// take: no colour with `NO_COLOR` set, off a terminal or on a dumb one, and nothing
+/// but ASCII for lines on a dumb one or outside a UTF-8 locale
+pub struct Theme {
+    color: bool,
+    unicode: bool,
+    /// SGR codes for returned, soon and later subs
+    codes: [Option<String>; 3],
+    soon_within: TimeDelta,
+    returned_marker: Option<String>,
+    box_drawing: bool,
+}
+
+impl Theme {
+    pub fn new(choice: ColorChoice, config: &ThemeConfig) -> anyhow::Result<Self> {
+        let dumb = env::var("TERM").is_ok_and(|term| term == "dumb");
+        let color = match choice {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty()) && !dumb
+            }
+        };
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|var| env::var(var).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_default()
+            .to_lowercase();
+        // Windows terminals are UTF-8 without saying so in the environment
+        let unicode = !dumb && (cfg!(windows) || locale.contains("utf-8") || locale.contains("utf8"));
+        let code = |configured: &Option<String>, default: Option<&str>| match configured {
+            Some(name) => sgr_code(name),
+            None => Ok(default.map(String::from)),
+        };
+        Ok(Theme {
+            color,
+            unicode,
+            codes: [
+                code(&config.returned, Some("32"))?,
+                code(&config.soon, Some("33"))?,
+                code(&config.later, None)?,
+            ],
+            soon_within: config.soon_within.map_or(TimeDelta::hours(1), |within| within.0),
+            returned_marker: config.returned_marker.clone().filter(|marker| !marker.is_empty()),
+            box_drawing: config.box_drawing,
+        })
+    }
+
+    pub fn urgency(&self, return_time: DateTime<Utc>, now: DateTime<Utc>) -> Urgency {
+        if return_time <= now {
+            Urgency::Returned
+        } else if return_time - now <= self.soon_within {
+            Urgency::Soon
+        } else {
+            Urgency::Later
+        }
+    }
+
+    pub fn paint(&self, urgency: Urgency, text: &str) -> String {
+        let code = match urgency {
+            Urgency::Returned => &self.codes[0],
+            Urgency::Soon => &self.codes[1],
+            Urgency::Later => &self.codes[2],
+        };
+        match code {
+            Some(code) if self.color => format!("\x1b[{code}m{text}\x1b[0m"),
+            _ => text.to_string(),
+        }
+    }
+
+    /// `returned_marker` and a space for a sub that's back, the same width of blanks
+    /// for one that isn't so names still line up, or nothing without a marker
+    pub fn marker(&self, urgency: Urgency) -> String {
+        match &self.returned_marker {
+            Some(marker) if urgency == Urgency::Returned => format!("{marker} "),
+            Some(marker) => " ".repeat(marker.width() + 1),
+            None => String::new(),
+        }
+    }
+
+    /// A bar `BAR_WIDTH` wide filled to `done` (0 to 1), and the percentage
+    pub fn bar(&self, done: f64) -> String {
+        let filled = (done * BAR_WIDTH as f64).floor() as usize;
+        let (full, empty) = if self.unicode { ("█", "░") } else { ("#", "-") };
+        format!("{}{} {:>3.0}%", full.repeat(filled), empty.repeat(BAR_WIDTH - filled), done * 100.0)
+    }
+
+    /// Between subs on one line
+    pub fn separator(&self) -> &'static str {
+        if self.unicode { " · " } else { " | " }
+    }
+
+    /// What to draw the table's lines with, if it should have any
+    pub fn borders(&self) -> Option<&'static Borders> {
+        match (self.box_drawing, self.unicode) {
+            (false, _) => None,
+            (true, true) => Some(&UNICODE_BORDERS),
+            (true, false) => Some(&ASCII_BORDERS),
+        }
+    }
+}
+
+/// A colour name, or names to combine like `bold red`, as an SGR code. Codes can be
+/// given as they are too, e.g. `1;35`, and `none` is no colour.
+fn sgr_code(name: &str) -> anyhow::Result<Option<String>> {
+    let name = name.trim().to_lowercase();
+    if name.is_empty() || name == "none" {
+        return Ok(None);
+    }
+    if name.chars().all(|c| c.is_ascii_digit() || c == ';') {
+        return Ok(Some(name));
+    }
+    const COLORS: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+    let mut codes = Vec::new();
+    for word in name.split_whitespace() {
+        let code = match word {
+            "bold" => 1,
+            "dim" => 2,
+            "italic" => 3,
+            "underline" => 4,
+            _ => {
+                let (base, color) = match word.strip_prefix("bright_") {
+                    Some(color) => (90, color),
+                    None => (30, word),
+                };
+                match COLORS.iter().position(|known| *known == color) {
+                    Some(index) => base + index,
+                    None => bail!("Unknown colour \"{word}\" in [theme], try a name like green or bright_red"),
+                }
+            }
+        };
+        codes.push(code.to_string());
+    }
+    Ok(Some(codes.join(";")))
+}
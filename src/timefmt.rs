@@ -0,0 +1,188 @@
+use std::env;
+
+use anyhow::bail;
+use anyhow::Context;
+use chrono::{format::StrftimeItems, DateTime, Datelike, Timelike, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    config::DisplayConfig,
+    duration::format_duration,
+    i18n::{fill, Language, Messages},
+    local_timezone,
+};
+
+/// Countries that write the time 12-hour. Everywhere else gets 24-hour once the locale
+/// says where we are.
+const TWELVE_HOUR_TERRITORIES: &[&str] = &["US", "CA", "AU", "NZ", "IN", "PH", "PK", "EG", "SA", "MY"];
+const MONTH_FIRST_TERRITORIES: &[&str] = &["US", "PH"];
+
+#[derive(Clone, Copy, PartialEq)]
+enum DateOrder {
+    DayFirst,
+    MonthFirst,
+}
+
+/// How return times are shown, in the listing and in notifications. A custom format
+/// replaces all of the built-in ones and is used as-is, with no timezone tacked on.
+#[derive(Clone)]
+pub struct TimeDisplay {
+    custom: Option<String>,
+    hour24: bool,
+    /// None keeps the original mix: day first in the listing, month first in
+    /// notifications
+    order: Option<DateOrder>,
+    tz: Tz,
+    language: Language,
+}
+
+impl TimeDisplay {
+    pub fn new(config: &DisplayConfig) -> anyhow::Result<Self> {
+        let custom = config.time_format.clone();
+        if let Some(format) = &custom {
+            // chrono only notices a bad format when it panics halfway through printing
+            if StrftimeItems::new(format).parse().is_err() {
+                bail!("Invalid time format {format:?}, see https://docs.rs/chrono/latest/chrono/format/strftime/");
+            }
+        }
+        let territory = locale_territory();
+        let order = territory.as_deref().map(|territory| {
+            if MONTH_FIRST_TERRITORIES.contains(&territory) {
+                DateOrder::MonthFirst
+            } else {
+                DateOrder::DayFirst
+            }
+        });
+        let language = config.language.unwrap_or_else(Language::detect);
+        let hour24 = config.hour24.unwrap_or_else(|| {
+            territory.is_some_and(|territory| !TWELVE_HOUR_TERRITORIES.contains(&territory.as_str()))
+        });
+
+        let tz = match &config.timezone {
+            Some(name) => name
+                .parse()
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("Unknown timezone {name:?}, expected something like Europe/Berlin"))?,
+            None => local_timezone()
+                .context("Couldn't work out the local timezone, set one with --tz")?
+                .parse()
+                .map_err(anyhow::Error::msg)?,
+        };
+        Ok(TimeDisplay { custom, hour24, order, tz, language })
+    }
+
+    /// The same, but in English, for somewhere the configured language can't be shown
+    pub fn english(&self) -> TimeDisplay {
+        TimeDisplay { language: Language::En, ..self.clone() }
+    }
+
+    /// Text for the listing and notifications, in the configured language
+    pub fn text(&self) -> &'static Messages {
+        self.language.messages()
+    }
+
+    /// "in 3h 12m" or "returned 2h 5m ago"
+    pub fn relative(&self, time: DateTime<Utc>, now: DateTime<Utc>) -> String {
+        let delta = time - now;
+        let text = if delta > chrono::TimeDelta::zero() { self.text().returns_in } else { self.text().returned_ago };
+        fill(text, &[("time", &format_duration(delta))])
+    }
+
+    fn format(&self, time: DateTime<Utc>, default: &str, with_tz: bool) -> String {
+        let local = time.with_timezone(&self.tz);
+        let format = |format: &str| local.format(&self.localize(format, &local)).to_string();
+        match &self.custom {
+            Some(custom) => format(custom),
+            None if with_tz => format!("{} {}", format(default), local.format("%Z")),
+            None => format(default),
+        }
+    }
+
+    /// Month names and AM/PM written out in the language, which chrono only knows in
+    /// English
+    fn localize(&self, format: &str, local: &DateTime<Tz>) -> String {
+        if self.language == Language::En {
+            return format.to_string();
+        }
+        let text = self.text();
+        let month = local.month0() as usize;
+        let mut localized = String::new();
+        let mut chars = format.chars();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                localized.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('B') => localized += text.months[month],
+                Some('b') => localized += text.months_short[month],
+                Some('p') => localized += text.am_pm[usize::from(local.hour() >= 12)],
+                Some(next) => {
+                    localized.push('%');
+                    localized.push(next);
+                }
+                None => localized.push('%'),
+            }
+        }
+        localized
+    }
+
+    fn clock(&self, seconds: bool) -> &'static str {
+        match (self.hour24, seconds) {
+            (true, true) => "%H:%M:%S",
+            (true, false) => "%H:%M",
+            (false, true) => "%I:%M:%S %p",
+            (false, false) => "%I:%M %p",
+        }
+    }
+
+    /// e.g. `14 October 2026 at 02:08:35 PM AEDT`
+    pub fn long(&self, time: DateTime<Utc>) -> String {
+        let clock = self.clock(true);
+        let format = match (self.language, self.order) {
+            (Language::De, _) => format!("%e. %B %Y um {clock}"),
+            (Language::Fr, _) => format!("%e %B %Y à {clock}"),
+            (Language::Ja, _) => format!("%Y年%B%-d日 {clock}"),
+            (Language::En, Some(DateOrder::MonthFirst)) => format!("%B %e, %Y at {clock}"),
+            (Language::En, _) => format!("%e %B %Y at {clock}"),
+        };
+        self.format(time, &format, true)
+    }
+
+    /// e.g. `14 October at 02:08 PM AEDT`, or `4 October`, without the space `%e` pads with
+    pub fn short(&self, time: DateTime<Utc>) -> String {
+        let clock = self.clock(false);
+        let format = match (self.language, self.order) {
+            (Language::De, _) => format!("%e. %B um {clock}"),
+            (Language::Fr, _) => format!("%e %B à {clock}"),
+            (Language::Ja, _) => format!("%B%-d日 {clock}"),
+            (Language::En, Some(DateOrder::MonthFirst)) => format!("%B %e at {clock}"),
+            (Language::En, _) => format!("%e %B at {clock}"),
+        };
+        self.format(time, &format, true).trim_start().to_string()
+    }
+
+    /// e.g. `Oct14, 2026, 02:08PM`
+    pub fn notification(&self, time: DateTime<Utc>) -> String {
+        let format = match (self.language, self.order, self.hour24) {
+            (Language::De, _, _) => format!("%e. %b %Y, {}", self.clock(false)),
+            (Language::Fr, _, _) => format!("%e %b %Y, {}", self.clock(false)),
+            (Language::Ja, _, _) => format!("%Y/%m/%d {}", self.clock(false)),
+            (Language::En, Some(DateOrder::DayFirst), _) => format!("%e %b %Y, {}", self.clock(false)),
+            (Language::En, _, true) => "%b%e, %Y, %H:%M".to_string(),
+            (Language::En, _, false) => "%b%e, %Y, %I:%M%p".to_string(),
+        };
+        self.format(time, &format, false).trim_start().to_string()
+    }
+}
+
+/// The country part of the time locale (`en_GB.UTF-8` is `GB`), or None when there
+/// isn't one to go by.
+fn locale_territory() -> Option<String> {
+    let locale = ["LC_ALL", "LC_TIME", "LANG"]
+        .iter()
+        .filter_map(|var| env::var(var).ok())
+        .find(|value| !value.is_empty())?;
+    let territory = locale.split(['.', '@']).next()?.split_once('_')?.1;
+    Some(territory.to_ascii_uppercase())
+}
@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+use anyhow::{bail, Context};
+use chrono::{DateTime, Local, NaiveDateTime, TimeDelta, Utc};
+use rusqlite::OpenFlags;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    config::Config,
+    data_dir,
+    db::{self, Schema, SubInfo},
+    duration::{format_duration, parse_duration},
+    filter::SubFilter,
+    timefmt::TimeDisplay,
+    voyage_log,
+};
+
+/// Parses the time given to `--update`: either FFXIV's own date format, or a duration
+/// from now like `+36h30m` / `now+31h`, or before it like `now-2h`.
+pub fn parse_time(updated: &str) -> anyhow::Result<DateTime<Utc>> {
+    let relative = updated.trim();
+    let relative = relative.strip_prefix("now").unwrap_or(relative);
+    if let Some(duration) = relative.strip_prefix('+') {
+        let duration = parse_duration(duration)
+            .with_context(|| format!("Duration incorrect for '{}'\n\nExample: +36h30m", updated))?;
+        return Ok(Utc::now() + duration);
+    }
+    if let Some(duration) = relative.strip_prefix('-') {
+        let duration = parse_duration(duration)
+            .with_context(|| format!("Duration incorrect for '{}'\n\nExample: now-2h", updated))?;
+        return Ok(Utc::now() - duration);
+    }
+
+    let parse_date = NaiveDateTime::parse_from_str(updated, "%m/%d/%Y %H:%M")
+        .with_context(|| format!("Date format incorrect for '{}', FFXIV format expected\n\nExample: 11/14/2024 16:59 or +36h30m", updated))?
+        .and_local_timezone(Local)
+        .unwrap();
+    Ok(parse_date.with_timezone(&Utc))
+}
+
+/// How `set_return_time` should go about it.
+pub struct UpdateOptions {
+    /// Only show what would change
+    pub dry_run: bool,
+    /// Don't ask before writing
+    pub yes: bool,
+    /// The time given is when the subs were sent out, on a voyage this long
+    pub voyage: Option<TimeDelta>,
+}
+
+/// Rewrites the return time of every sub `filter` matches, in every local database,
+/// after showing what's about to change and asking first. `time` is the return, or
+/// with `options.voyage` when they went out, which is logged with the return it makes.
+/// The old times are saved so `undo_last_update` can put them back.
+pub fn set_return_time(
+    config: &Config,
+    time: DateTime<Utc>,
+    filter: &SubFilter,
+    options: &UpdateOptions,
+    display: &TimeDisplay,
+) -> anyhow::Result<()> {
+    // The database keeps whole seconds, and the voyage log has to match it
+    let time = DateTime::from_timestamp(time.timestamp(), 0).unwrap_or(time);
+    let db_paths = db::db_paths(&config.sources);
+    let mut planned = Vec::new();
+    for db_path in &db_paths {
+        let db = db::open_db_at(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        let mut subs = db::read_sqlite(&db)?;
+        config.apply_aliases(&mut subs);
+        planned.push(subs.into_iter().filter(|sub| filter.matches(sub)).collect::<Vec<_>>());
+    }
+    let count: usize = planned.iter().map(Vec::len).sum();
+    if count == 0 {
+        bail!("No submarines matched, nothing was updated");
+    }
+    let returns = match options.voyage {
+        Some(_) if time > Utc::now() => bail!("That's when they were sent out, which can't be still to come"),
+        Some(voyage) => time + voyage,
+        None => time,
+    };
+
+    let format_time = |time: DateTime<Utc>| display.long(time).trim_start().to_string();
+    println!("{count} submarine return time(s) will change:");
+    let voyage = match options.voyage {
+        Some(voyage) => format!(" (sent out {}, {} away)", format_time(time), format_duration(voyage)),
+        None => String::new(),
+    };
+    for sub in planned.iter().flatten() {
+        println!(
+            "  {name} ({owner}): {old} -> {new}{voyage}",
+            name = sub.name,
+            owner = sub.owner(),
+            old = format_time(sub.return_time),
+            new = format_time(returns),
+        );
+    }
+    if options.dry_run {
+        println!("Dry run, nothing was written");
+        return Ok(());
+    }
+    if !options.yes && !confirm("Write these changes?")? {
+        bail!("Cancelled, nothing was updated");
+    }
+
+    let backup: Vec<BackupEntry> = db_paths
+        .iter()
+        .zip(&planned)
+        .flat_map(|(db_path, subs)| {
+            subs.iter().map(|sub| BackupEntry {
+                db_path: db_path.clone(),
+                sub_id: sub.id,
+                return_time: sub.return_time,
+            })
+        })
+        .collect();
+    save_backup(&backup)?;
+
+    for (db_path, subs) in db_paths.iter().zip(&planned) {
+        if subs.is_empty() {
+            continue;
+        }
+        let mut db = db::open_db_at(db_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        let schema = Schema::detect(&db)?;
+        // All or nothing per database, so a lock halfway through doesn't leave a mix
+        let tx = db.transaction()?;
+        for sub in subs {
+            tx.execute(&schema.update_return_sql(), (returns.timestamp(), sub.id))?;
+        }
+        tx.commit()?;
+        db.close().map_err(|(_, e)| e)?;
+    }
+    // The database only has returns, so this log is the one place the dispatch is kept
+    if options.voyage.is_some() {
+        for sub in planned.iter().flatten() {
+            let sent = SubInfo { return_time: returns, ..sub.clone() };
+            if let Err(e) = voyage_log::append(&sent, time) {
+                eprintln!("Couldn't log {} going out: {e:#}", sub.name);
+            }
+        }
+    }
+
+    if filter.is_empty() {
+        println!("All submarine return times updated! These are the new return times...");
+    } else {
+        println!("{count} submarine return time(s) updated! These are the new return times...");
+    }
+    Ok(())
+}
+
+/// One sub's return time from before the last `--update`.
+#[derive(Serialize, Deserialize)]
+struct BackupEntry {
+    db_path: PathBuf,
+    sub_id: i64,
+    return_time: DateTime<Utc>,
+}
+
+fn backup_path() -> PathBuf {
+    data_dir().join("last-update.json")
+}
+
+fn save_backup(backup: &[BackupEntry]) -> anyhow::Result<()> {
+    let path = backup_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string_pretty(backup)?)
+        .with_context(|| format!("Couldn't save a backup to {}, nothing was updated", path.display()))
+}
+
+/// Puts back the return times the last `--update` overwrote. There's only ever one
+/// level of undo, and it's used up once restored.
+pub fn undo_last_update() -> anyhow::Result<()> {
+    let path = backup_path();
+    let contents = match fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => bail!("There's no update to undo"),
+        Err(e) => return Err(e.into()),
+    };
+    let backup: Vec<BackupEntry> = serde_json::from_str(&contents)
+        .with_context(|| format!("{} is corrupt", path.display()))?;
+
+    let mut by_db: Vec<(&PathBuf, Vec<&BackupEntry>)> = Vec::new();
+    for entry in &backup {
+        match by_db.iter_mut().find(|(db_path, _)| *db_path == &entry.db_path) {
+            Some((_, entries)) => entries.push(entry),
+            None => by_db.push((&entry.db_path, vec![entry])),
+        }
+    }
+    for (db_path, entries) in by_db {
+        let mut db = db::open_db_at(db_path, OpenFlags::SQLITE_OPEN_READ_WRITE)?;
+        let schema = Schema::detect(&db)?;
+        let tx = db.transaction()?;
+        for entry in entries {
+            tx.execute(&schema.update_return_sql(), (entry.return_time.timestamp(), entry.sub_id))?;
+        }
+        tx.commit()?;
+        db.close().map_err(|(_, e)| e)?;
+    }
+    fs::remove_file(&path)?;
+    println!("Restored {} submarine return time(s)! These are the return times now...", backup.len());
+    Ok(())
+}
+
+/// Asks `question`, refusing to guess without a terminal to ask on
+pub fn confirm(question: &str) -> anyhow::Result<bool> {
+    if !io::stdin().is_terminal() {
+        bail!("Not asking for confirmation without a terminal, pass --yes to go ahead anyway");
+    }
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
@@ -0,0 +1,66 @@
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{data_dir, db::SubInfo};
+
+/// A sub the daemon saw sent out, one JSON object per line in `voyages.jsonl`. The
+/// database only keeps the latest return, so this is the only record of who sent
+/// what when.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Dispatch {
+    pub key: String,
+    pub sub: String,
+    pub owner: String,
+    /// When the daemon noticed, which is within a second of the plugin writing it, or
+    /// when `--update` was told it went out
+    pub seen: DateTime<Utc>,
+    pub return_time: DateTime<Utc>,
+}
+
+fn path() -> PathBuf {
+    data_dir().join("voyages.jsonl")
+}
+
+/// Logs `sub` going out at `seen` on the voyage it's on now, unless that voyage is
+/// logged already, as it is when the daemon notices one `--update` wrote
+pub fn append(sub: &SubInfo, seen: DateTime<Utc>) -> anyhow::Result<()> {
+    let last = read()?.into_iter().rev().find(|dispatch| dispatch.key == sub.key());
+    if last.is_some_and(|last| last.return_time == sub.return_time) {
+        return Ok(());
+    }
+    let dispatch = Dispatch {
+        key: sub.key(),
+        sub: sub.name.clone(),
+        owner: sub.owner(),
+        seen,
+        return_time: sub.return_time,
+    };
+    let path = path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(file, "{}", serde_json::to_string(&dispatch)?)?;
+    Ok(())
+}
+
+/// Everything logged, oldest first. No log yet is just nothing logged.
+pub fn read() -> anyhow::Result<Vec<Dispatch>> {
+    let file = match File::open(path()) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let dispatches = BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect();
+    Ok(dispatches)
+}
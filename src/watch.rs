@@ -0,0 +1,195 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Context;
+use chrono::Utc;
+
+use crate::{
+    config::Config,
+    db::{self, get_all_submarine_info, SubInfo, SubSource},
+    diff::{self, Change},
+    filter::SubFilter,
+    list::{self, Output, OutputFormat},
+    predict,
+    timefmt::TimeDisplay,
+};
+
+/// How often sources we can't watch for changes (remote and collector) are re-read
+const REREAD_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Keeps the listing on screen until Ctrl+C. The database is only re-read when one of
+/// its files changes, and the screen is only redrawn when the text does, so there's
+/// nothing to flicker.
+pub fn run(
+    sources: &[SubSource],
+    db_paths: &[PathBuf],
+    config: &Config,
+    filter: &SubFilter,
+    output: &Output,
+) -> anyhow::Result<()> {
+    let running = stop_on_ctrl_c()?;
+    let mut stdout = io::stdout();
+    // Only a terminal can be redrawn in place, anything else gets each frame after the last
+    // with nothing to undo afterwards
+    if output.format == OutputFormat::Waybar || !stdout.is_terminal() {
+        return watch(&mut stdout, &running, sources, db_paths, config, filter, output);
+    }
+    // Alternate screen, cursor hidden
+    write!(stdout, "\x1b[?1049h\x1b[?25l")?;
+    let result = watch(&mut stdout, &running, sources, db_paths, config, filter, output);
+    write!(stdout, "\x1b[?25h\x1b[?1049l")?;
+    stdout.flush()?;
+    result
+}
+
+fn stop_on_ctrl_c() -> anyhow::Result<Arc<AtomicBool>> {
+    let running = Arc::new(AtomicBool::new(true));
+    let handler_running = running.clone();
+    ctrlc::set_handler(move || handler_running.store(false, Ordering::SeqCst))
+        .context("Couldn't install the Ctrl+C handler")?;
+    Ok(running)
+}
+
+fn watch(
+    stdout: &mut io::Stdout,
+    running: &AtomicBool,
+    sources: &[SubSource],
+    db_paths: &[PathBuf],
+    config: &Config,
+    filter: &SubFilter,
+    output: &Output,
+) -> anyhow::Result<()> {
+    let in_place = output.format != OutputFormat::Waybar && stdout.is_terminal();
+    let mut subs: Vec<SubInfo> = Vec::new();
+    let mut last_modified: Vec<Option<SystemTime>> = Vec::new();
+    let mut last_read: Option<Instant> = None;
+    let mut last_frame = String::new();
+    let mut error: Option<String> = None;
+
+    while running.load(Ordering::SeqCst) {
+        let modified = db::modified_times(db_paths);
+        let stale = last_read.is_none_or(|time| time.elapsed() >= REREAD_INTERVAL);
+        if modified != last_modified || stale {
+            match get_all_submarine_info(sources) {
+                Ok(mut fresh) => {
+                    config.apply_aliases(&mut fresh);
+                    predict::fill_in(&mut fresh, sources);
+                    fresh.retain(|sub| filter.matches(sub));
+                    subs = fresh;
+                    error = None;
+                }
+                // Keep showing what we had, it's most likely the plugin mid-write
+                Err(e) => error = Some(format!("{e:#}")),
+            }
+            last_modified = modified;
+            last_read = Some(Instant::now());
+        }
+
+        let mut frame = Vec::new();
+        list::write(&mut frame, subs.clone(), output)?;
+        // Not a bar's business, which would choke on it
+        if let Some(error) = error.as_ref().filter(|_| output.format != OutputFormat::Waybar) {
+            writeln!(frame, "\nCouldn't read the database: {error}")?;
+        }
+        let frame = String::from_utf8_lossy(&frame).into_owned();
+        if frame != last_frame && !in_place {
+            // A bar reads a line at a time, each replacing the last
+            write!(stdout, "{frame}")?;
+            stdout.flush()?;
+            last_frame = frame;
+        } else if frame != last_frame {
+            // Overwrite in place rather than clearing first, clearing each line's leftovers
+            write!(stdout, "\x1b[H")?;
+            for line in frame.lines() {
+                write!(stdout, "{line}\x1b[K\r\n")?;
+            }
+            write!(stdout, "\x1b[J")?;
+            stdout.flush()?;
+            last_frame = frame;
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    Ok(())
+}
+
+/// `--diff`: a line for each thing that changes about the subs, from whenever the
+/// databases do, until Ctrl+C
+pub fn diff(
+    sources: &[SubSource],
+    db_paths: &[PathBuf],
+    config: &Config,
+    filter: &SubFilter,
+    display: &TimeDisplay,
+) -> anyhow::Result<()> {
+    let running = stop_on_ctrl_c()?;
+    let read = || -> anyhow::Result<Vec<SubInfo>> {
+        let mut subs = get_all_submarine_info(sources)?;
+        config.apply_aliases(&mut subs);
+        predict::fill_in(&mut subs, sources);
+        subs.retain(|sub| filter.matches(sub));
+        Ok(subs)
+    };
+    let mut subs = read()?;
+    let mut last_modified = db::modified_times(db_paths);
+    let mut last_read = Instant::now();
+    let mut error: Option<String> = None;
+    println!("Watching {} sub(s) for changes, Ctrl+C to stop", subs.len());
+
+    let mut stdout = io::stdout();
+    while running.load(Ordering::SeqCst) {
+        std::thread::sleep(Duration::from_secs(1));
+        let modified = db::modified_times(db_paths);
+        if modified == last_modified && last_read.elapsed() < REREAD_INTERVAL {
+            continue;
+        }
+        last_modified = modified;
+        last_read = Instant::now();
+        let fresh = match read() {
+            Ok(fresh) => fresh,
+            // Most likely the plugin mid-write, the next change will have it
+            Err(e) => {
+                let e = format!("{e:#}");
+                if error.as_ref() != Some(&e) {
+                    eprintln!("Couldn't read the database: {e}");
+                }
+                error = Some(e);
+                continue;
+            }
+        };
+        error = None;
+        let now = Utc::now();
+        for change in diff::changes(&subs, &fresh, now) {
+            writeln!(stdout, "{}  {}", display.short(now), describe(&change, display))?;
+        }
+        stdout.flush()?;
+        subs = fresh;
+    }
+    Ok(())
+}
+
+fn describe(change: &Change, display: &TimeDisplay) -> String {
+    let now = Utc::now();
+    let back = |sub: &SubInfo| {
+        format!("back {} ({})", display.short(sub.return_time), display.relative(sub.return_time, now))
+    };
+    match change {
+        Change::NewOwner(owner) => format!("New: {owner}"),
+        Change::OwnerGone(owner) => format!("Gone: {owner}"),
+        Change::NewSub(sub) => format!("New sub: {} ({}), {}", sub.name, sub.owner(), back(sub)),
+        Change::Gone(sub) => format!("Gone: {} ({})", sub.name, sub.owner()),
+        Change::Renamed { sub, from } => format!("{from} ({}) renamed to {}", sub.owner(), sub.name),
+        Change::Dispatched { sub, .. } => {
+            format!("{} ({}) collected and sent out again, {}", sub.name, sub.owner(), back(sub))
+        }
+        Change::Retimed { sub, previous } => {
+            format!("{} ({}) now {}, was {}", sub.name, sub.owner(), back(sub), display.short(*previous))
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use reqwest::blocking::Client;
+use serde_json::Value;
+
+const XIVAPI: &str = "https://v2.xivapi.com/api/sheet";
+
+/// `fields` (or all of them, if empty) of each of `rows` in one of the game's sheets,
+/// by row id
+pub fn fetch_rows(client: &Client, sheet: &str, fields: &[&str], rows: &[u32]) -> anyhow::Result<Vec<(u32, Value)>> {
+    let rows: Vec<String> = rows.iter().map(u32::to_string).collect();
+    let mut query = vec![("rows", rows.join(","))];
+    if !fields.is_empty() {
+        query.push(("fields", fields.join(",")));
+    }
+    let response: Value = client
+        .get(format!("{XIVAPI}/{sheet}"))
+        .query(&query)
+        .timeout(Duration::from_secs(10))
+        .send()?
+        .error_for_status()?
+        .json()?;
+    let found = response["rows"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|row| Some((row["row_id"].as_u64()? as u32, row["fields"].clone())))
+        .collect();
+    Ok(found)
+}
@@ -0,0 +1,164 @@
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    time::Duration,
+};
+
+use anyhow::{anyhow, bail, Context};
+use base64::Engine;
+
+use crate::{config::XmppConfig, notifier::Notifier};
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Messages over XMPP, to one person or posted in a group chat. Each is its own
+/// connection, since returns are hours apart: STARTTLS, SASL PLAIN, send, close.
+pub struct Xmpp {
+    config: XmppConfig,
+}
+
+impl Xmpp {
+    pub fn new(config: XmppConfig) -> Self {
+        Xmpp { config }
+    }
+}
+
+impl Notifier for Xmpp {
+    fn name(&self) -> &str {
+        "xmpp"
+    }
+
+    /// Just the body, a chat message has nowhere to put a title
+    fn send(&mut self, _title: &str, body: &str) -> anyhow::Result<()> {
+        let config = &self.config;
+        let bare = config.jid.split('/').next().unwrap_or_default();
+        let Some((user, domain)) = bare.split_once('@') else {
+            bail!("[xmpp] jid should look like name@example.org, not {}", config.jid);
+        };
+        let address = match &config.server {
+            Some(server) if server.contains(':') => server.clone(),
+            Some(server) => format!("{server}:5222"),
+            None => format!("{domain}:5222"),
+        };
+
+        let tcp = TcpStream::connect(&address).with_context(|| format!("Couldn't connect to {address}"))?;
+        tcp.set_read_timeout(Some(TIMEOUT))?;
+        tcp.set_write_timeout(Some(TIMEOUT))?;
+        let mut plain = Stream { io: tcp, buffer: Vec::new() };
+        let features = plain.open(domain)?;
+        if !features.contains("<starttls") {
+            bail!("{address} doesn't offer STARTTLS, not sending the password in the clear");
+        }
+        plain.write("<starttls xmlns='urn:ietf:params:xml:ns:xmpp-tls'/>")?;
+        if plain.read_until(&["<proceed", "<failure"])?.contains("<failure") {
+            bail!("{address} refused STARTTLS");
+        }
+        // The certificate is for the JID's domain, whichever host serves it
+        let tls = native_tls::TlsConnector::new()?
+            .connect(domain, plain.io)
+            .map_err(|e| anyhow!("TLS with {address} failed: {e}"))?;
+        let mut stream = Stream { io: tls, buffer: Vec::new() };
+
+        let features = stream.open(domain)?;
+        if !features.contains(">PLAIN<") {
+            bail!("{address} doesn't take a plain password, which is all this can sign in with");
+        }
+        let credentials = base64::engine::general_purpose::STANDARD.encode(format!("\0{user}\0{}", config.password));
+        stream.write(&format!("<auth xmlns='urn:ietf:params:xml:ns:xmpp-sasl' mechanism='PLAIN'>{credentials}</auth>"))?;
+        if stream.read_until(&["<success", "<failure"])?.contains("<failure") {
+            bail!("Couldn't sign in as {bare}, check [xmpp] password");
+        }
+        stream.open(domain)?;
+        stream.write(
+            "<iq type='set' id='bind'><bind xmlns='urn:ietf:params:xml:ns:xmpp-bind'>\
+             <resource>sub-returns</resource></bind></iq>",
+        )?;
+        stream.read_until(&["</iq>"])?;
+
+        let to = escape(&config.to);
+        let kind = if config.muc {
+            let nick = escape(config.nick.as_deref().unwrap_or("sub-returns"));
+            stream.write(&format!(
+                "<presence to='{to}/{nick}'><x xmlns='http://jabber.org/protocol/muc'><history maxchars='0'/></x></presence>"
+            ))?;
+            // Status 110 is the room confirming we're in
+            let joined = stream.read_until(&["code='110'", "code=\"110\"", "type='error'", "type=\"error\""])?;
+            if joined.contains("error") {
+                bail!("Couldn't join {}", config.to);
+            }
+            "groupchat"
+        } else {
+            "chat"
+        };
+        stream.write(&format!("<message to='{to}' type='{kind}'><body>{}</body></message>", escape(body)))?;
+        stream.write("</stream:stream>")?;
+        // Waiting for the server to close its side means the message has been taken
+        let _ = stream.read_until(&["</stream:stream>"]);
+        Ok(())
+    }
+}
+
+/// One direction of XML stanzas, read only as far as the reply being waited for
+struct Stream<S> {
+    io: S,
+    /// Read but not yet waited for
+    buffer: Vec<u8>,
+}
+
+impl<S: Read + Write> Stream<S> {
+    fn write(&mut self, xml: &str) -> anyhow::Result<()> {
+        self.io.write_all(xml.as_bytes())?;
+        self.io.flush()?;
+        Ok(())
+    }
+
+    /// Starts a stream (again, after STARTTLS and signing in), returning the features
+    /// the server offers on it
+    fn open(&mut self, domain: &str) -> anyhow::Result<String> {
+        self.buffer.clear();
+        self.write(&format!(
+            "<?xml version='1.0'?><stream:stream to='{}' version='1.0' xmlns='jabber:client' \
+             xmlns:stream='http://etherx.jabber.org/streams'>",
+            escape(domain)
+        ))?;
+        self.read_until(&["</stream:features>"])
+    }
+
+    /// Everything up to the end of the first tag with any of `needles` in it
+    fn read_until(&mut self, needles: &[&str]) -> anyhow::Result<String> {
+        let mut chunk = [0; 4096];
+        loop {
+            let end = needles
+                .iter()
+                .filter_map(|needle| {
+                    let start = find(&self.buffer, needle.as_bytes())?;
+                    find(&self.buffer[start..], b">").map(|close| start + close + 1)
+                })
+                .min();
+            if let Some(end) = end {
+                let reply: Vec<u8> = self.buffer.drain(..end).collect();
+                return Ok(String::from_utf8_lossy(&reply).into_owned());
+            }
+            if find(&self.buffer, b"<stream:error").is_some() {
+                bail!("The server ended the stream: {}", String::from_utf8_lossy(&self.buffer));
+            }
+            let read = self.io.read(&mut chunk).context("No reply from the server")?;
+            if read == 0 {
+                bail!("The server closed the connection");
+            }
+            self.buffer.extend_from_slice(&chunk[..read]);
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\'', "&apos;")
+        .replace('"', "&quot;")
+}
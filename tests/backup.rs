@@ -0,0 +1,74 @@
+//! `export` and `import` round-tripping a fixture database
+
+mod common;
+
+use std::path::Path;
+
+use common::{assert_asks_first, fixture_file, sub_returns, CURRENT, MINIMAL, SUBS};
+use rusqlite::Connection;
+
+fn names(db: &Path) -> Vec<String> {
+    let db = Connection::open(db).unwrap();
+    let mut stmt = db.prepare("SELECT Name FROM submarine ORDER BY SubmarineId").unwrap();
+    stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn round_trips() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), CURRENT, SUBS);
+    let update = "UPDATE submarine SET Points = 'AB', Rank = 80 WHERE SubmarineId = 1";
+    Connection::open(&db).unwrap().execute(update, []).unwrap();
+    let export = home.path().join("subs.json");
+    let output = sub_returns(&home).args(["export", "--output"]).arg(&export).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    Connection::open(&db).unwrap().execute_batch("DELETE FROM submarine; DELETE FROM freecompany").unwrap();
+    let output = sub_returns(&home).arg("import").arg(&export).arg("--yes").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(names(&db), ["Sub I", "Sub II", "Boaty"]);
+    let db = Connection::open(&db).unwrap();
+    let first: (String, i64, i64) = db
+        .query_row("SELECT Points, Rank, Return FROM submarine WHERE SubmarineId = 1", [], |row| {
+            Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+        })
+        .unwrap();
+    assert_eq!(first, ("AB".to_string(), 80, 1_731_600_000));
+    let tag: String =
+        db.query_row("SELECT FreeCompanyTag FROM freecompany WHERE FreeCompanyId = 2", [], |row| row.get(0)).unwrap();
+    assert_eq!(tag, "XYZ");
+}
+
+#[test]
+fn imports_shared_columns_into_another_schema() {
+    let from = tempfile::tempdir().unwrap();
+    fixture_file(from.path(), CURRENT, SUBS);
+    let output = sub_returns(&from).arg("export").output().unwrap();
+    assert!(output.status.success());
+    let export = from.path().join("subs.json");
+    std::fs::write(&export, &output.stdout).unwrap();
+
+    let to = tempfile::tempdir().unwrap();
+    let db = fixture_file(to.path(), MINIMAL, &SUBS[..1]);
+    let output = sub_returns(&to).arg("import").arg(&export).arg("--yes").output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(names(&db), ["Sub I", "Sub II", "Boaty"]);
+    let db = Connection::open(&db).unwrap();
+    let mut stmt = db.prepare("SELECT Return FROM submarine ORDER BY SubmarineId").unwrap();
+    let times: Vec<i64> = stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap();
+    assert_eq!(times, [1_731_600_000, 1_731_596_400, 1_731_603_600]);
+    // Parts, ranks and so on have nowhere to go
+    assert!(String::from_utf8_lossy(&output.stderr).contains("left those out"));
+}
+
+#[test]
+fn import_asks_first() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), CURRENT, SUBS);
+    let export = home.path().join("subs.json");
+    assert!(sub_returns(&home).args(["export", "-o"]).arg(&export).output().unwrap().status.success());
+    Connection::open(&db).unwrap().execute("DELETE FROM submarine", []).unwrap();
+    assert_asks_first(&sub_returns(&home).arg("import").arg(&export).output().unwrap());
+    assert!(names(&db).is_empty());
+}
@@ -0,0 +1,126 @@
+//! Databases with SubmarineTracker's tables, to read in tests.
+
+#![allow(dead_code)]
+
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use chrono::{DateTime, TimeDelta, TimeZone, Utc};
+use rusqlite::Connection;
+use sub_returns::db::SubInfo;
+use tempfile::TempDir;
+
+/// Every column `Schema` reads, as SubmarineTracker names them
+pub const CURRENT: &str = "
+    CREATE TABLE freecompany (
+        FreeCompanyId INTEGER PRIMARY KEY, CharacterName TEXT, World TEXT, FreeCompanyTag TEXT,
+        FreeCompanyName TEXT, Tanks INTEGER, Kits INTEGER
+    );
+    CREATE TABLE submarine (
+        FreeCompanyId INTEGER, SubmarineId INTEGER PRIMARY KEY, Name TEXT, Rank INTEGER,
+        Hull INTEGER, Stern INTEGER, Bow INTEGER, Bridge INTEGER, CExp INTEGER, NExp INTEGER,
+        HullDurability INTEGER, SternDurability INTEGER, BowDurability INTEGER,
+        BridgeDurability INTEGER, Return INTEGER, Points TEXT
+    );
+    PRAGMA user_version = 5;
+";
+
+/// Only the columns return times can't do without, none of the optional ones
+pub const MINIMAL: &str = "
+    CREATE TABLE freecompany (FreeCompanyId INTEGER PRIMARY KEY, CharacterName TEXT, FreeCompanyTag TEXT);
+    CREATE TABLE submarine (FreeCompanyId INTEGER, SubmarineId INTEGER PRIMARY KEY, Name TEXT, Return INTEGER);
+";
+
+/// One sub to put in a fixture
+pub struct Sub {
+    pub fc: i64,
+    pub id: i64,
+    pub name: &'static str,
+    pub return_time: i64,
+}
+
+/// Two of Alpha Beta's subs and one of Gamma Delta's, back in November 2024
+pub const SUBS: &[Sub] = &[
+    Sub { fc: 1, id: 1, name: "Sub I", return_time: 1_731_600_000 },
+    Sub { fc: 1, id: 2, name: "Sub II", return_time: 1_731_596_400 },
+    Sub { fc: 2, id: 3, name: "Boaty", return_time: 1_731_603_600 },
+];
+
+/// An in-memory database with `schema`, FCs 1 (Alpha Beta «ABC») and 2 (Gamma Delta
+/// «XYZ»), and `subs`
+pub fn fixture(schema: &str, subs: &[Sub]) -> Connection {
+    let db = Connection::open_in_memory().unwrap();
+    fill(&db, schema, subs);
+    db
+}
+
+/// The same, written to `submarine-sqlite.db` in `dir`
+pub fn fixture_file(dir: &Path, schema: &str, subs: &[Sub]) -> PathBuf {
+    let path = dir.join(sub_returns::db::DB_FILE_NAME);
+    fill(&Connection::open(&path).unwrap(), schema, subs);
+    path
+}
+
+fn fill(db: &Connection, schema: &str, subs: &[Sub]) {
+    db.execute_batch(schema).unwrap();
+    for (id, name, fc_tag) in [(1, "Alpha Beta", "ABC"), (2, "Gamma Delta", "XYZ")] {
+        db.execute(
+            "INSERT INTO freecompany (FreeCompanyId, CharacterName, FreeCompanyTag) VALUES (?1, ?2, ?3)",
+            (id, name, fc_tag),
+        )
+        .unwrap();
+    }
+    for sub in subs {
+        db.execute(
+            "INSERT INTO submarine (FreeCompanyId, SubmarineId, Name, Return) VALUES (?1, ?2, ?3, ?4)",
+            (sub.fc, sub.id, sub.name, sub.return_time),
+        )
+        .unwrap();
+    }
+}
+
+/// A sub for the scheduling functions, back `minutes` after `base()`
+pub fn sub_info(id: i64, character: &str, minutes: i64) -> SubInfo {
+    SubInfo {
+        id,
+        name: format!("Sub {id}"),
+        return_time: base() + TimeDelta::minutes(minutes),
+        tag: "ABC".to_string(),
+        character_name: character.to_string(),
+        source: None,
+        route: Vec::new(),
+        rank: 0,
+        parts: [0; 4],
+        exp: 0,
+        next_exp: 0,
+        condition: None,
+        tanks: None,
+        kits: None,
+        estimated: false,
+    }
+}
+
+pub fn base() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 11, 14, 16, 0, 0).unwrap()
+}
+
+/// sub-returns with its config, data and database all in `home`, in UTC
+pub fn sub_returns(home: &TempDir) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_sub-returns"));
+    command
+        .env("HOME", home.path())
+        .env("XDG_CONFIG_HOME", home.path().join("config"))
+        .env("XDG_DATA_HOME", home.path().join("data"))
+        .env("SUB_RETURNS_DB", home.path().join(sub_returns::db::DB_FILE_NAME))
+        .env("TZ", "UTC");
+    command
+}
+
+/// Turned down for wanting a confirmation there was no terminal to ask for
+pub fn assert_asks_first(output: &Output) {
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("pass --yes"), "{stderr}");
+}
@@ -0,0 +1,65 @@
+mod common;
+
+use common::sub_info;
+use sub_returns::config::Config;
+
+#[test]
+fn aliases_rename_subs() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        r#"
+        [[aliases]]
+        sub = "sub 1"
+        name = "Gil Printer"
+
+        [[aliases]]
+        sub = "2"
+        character = "Gamma"
+        name = "Leveller"
+
+        [[muted]]
+        sub = "Gil Printer"
+        "#,
+    )
+    .unwrap();
+    let config = Config::load(&path).unwrap();
+
+    let mut subs = [sub_info(1, "Alpha", 0), sub_info(2, "Alpha", 0), sub_info(2, "Gamma", 0)];
+    config.apply_aliases(&mut subs);
+    let names: Vec<&str> = subs.iter().map(|sub| sub.name.as_str()).collect();
+    // By name whoever's it is, by id only for Gamma's
+    assert_eq!(names, ["Gil Printer", "Sub 2", "Leveller"]);
+    assert!(config.is_muted(&subs[0]));
+}
+
+#[test]
+fn free_companies_route_pushes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config.toml");
+    std::fs::write(
+        &path,
+        r#"
+        [[free_companies]]
+        tag = "abc"
+        discord = { webhook = "https://discord.com/api/webhooks/1/static" }
+
+        [[free_companies]]
+        tag = "SOLO"
+        pushover = { device = "phone" }
+        "#,
+    )
+    .unwrap();
+    let config = Config::load(&path).unwrap();
+
+    let mut solo = sub_info(2, "Gamma", 0);
+    solo.tag = "SOLO".to_string();
+    let mut elsewhere = sub_info(3, "Gamma", 0);
+    elsewhere.tag = "XYZ".to_string();
+    // The static's FC only goes to Discord, the other two still push
+    assert!(!config.delivery(&sub_info(1, "Alpha", 0)).push);
+    assert!(config.delivery(&solo).push);
+    assert_eq!(config.route(&solo).and_then(|route| route.pushover.as_ref()?.device.as_deref()), Some("phone"));
+    assert!(config.delivery(&elsewhere).push && config.route(&elsewhere).is_none());
+}
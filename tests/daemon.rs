@@ -0,0 +1,83 @@
+//! The daemon against a fixture database and a stand-in for the bridge
+
+mod common;
+
+use std::{
+    process::{Child, Stdio},
+    time::Duration,
+};
+
+use chrono::Utc;
+use common::{fixture_file, sub_returns, CURRENT, SUBS};
+use rusqlite::Connection;
+use serde_json::{Map, Value};
+use tempfile::TempDir;
+use tiny_http::{Response, Server};
+
+/// Killed when the test is done with it, however that goes
+struct Daemon(Child);
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// A daemon that only pushes, to `bridge`, straight away
+fn start(home: &TempDir, bridge: &Server) -> Daemon {
+    let config = format!(
+        "[bridge]\nurl = \"http://{}/\"\npsk = \"test\"\nsettle = \"0s\"\n\n[notifications]\ndesktop = false\n",
+        bridge.server_addr().to_ip().unwrap()
+    );
+    std::fs::create_dir_all(home.path().join("config/sub-returns")).unwrap();
+    std::fs::write(home.path().join("config/sub-returns/config.toml"), config).unwrap();
+    let child = sub_returns(home)
+        .args(["--daemon", "--lang", "en"])
+        .env_remove("XDG_RUNTIME_DIR")
+        .env_remove("DBUS_SESSION_BUS_ADDRESS")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    Daemon(child)
+}
+
+/// The next payload posted to the bridge
+fn next_post(bridge: &Server) -> Map<String, Value> {
+    let mut request = bridge.recv_timeout(Duration::from_secs(20)).unwrap().expect("nothing was posted");
+    let payload: Value = serde_json::from_reader(request.as_reader()).unwrap();
+    request.respond(Response::empty(200)).unwrap();
+    payload.as_object().unwrap().clone()
+}
+
+fn send_out(db: &Connection, id: i64, return_time: i64) {
+    db.execute("UPDATE submarine SET Return = ?1 WHERE SubmarineId = ?2", (return_time, id)).unwrap();
+}
+
+#[test]
+fn regroups_pushes_as_subs_go_out() {
+    let home = tempfile::tempdir().unwrap();
+    let db = Connection::open(fixture_file(home.path(), CURRENT, SUBS)).unwrap();
+    let bridge = Server::http("127.0.0.1:0").unwrap();
+    let _daemon = start(&home, &bridge);
+    let owner = "Alpha Beta «ABC»";
+    let first = Utc::now().timestamp() + 3600;
+
+    send_out(&db, 1, first);
+    let post = next_post(&bridge);
+    assert_eq!(post.keys().collect::<Vec<_>>(), [&format!("{owner}-1-{first}")]);
+    assert_eq!(post[&format!("{owner}-1-{first}")]["title"], "Sub I returned");
+
+    // Far apart, so a push of its own that doesn't take Sub I's place
+    send_out(&db, 2, first + 3 * 3600);
+    let post = next_post(&bridge);
+    assert_eq!(post.keys().collect::<Vec<_>>(), [&format!("{owner}-2-{}", first + 3 * 3600)]);
+
+    // Close behind, so the two are one push counting both
+    send_out(&db, 2, first + 120);
+    let post = next_post(&bridge);
+    let id = format!("{owner}-2-{}", first + 120);
+    assert_eq!(post.keys().collect::<Vec<_>>(), [&id]);
+    assert_eq!(post[&id]["title"], "Sub II (+1) returned");
+}
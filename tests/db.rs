@@ -0,0 +1,85 @@
+mod common;
+
+use common::{fixture, fixture_file, CURRENT, MINIMAL, SUBS};
+use sub_returns::db::{self, get_all_submarine_info, get_submarine_info, read_sqlite, SubSource};
+
+#[test]
+fn reads_current_schema() {
+    let db = fixture(CURRENT, SUBS);
+    db.execute_batch(
+        "UPDATE submarine SET Rank = 78, Hull = 9, Stern = 2, Bow = 7, Bridge = 16, CExp = 100, NExp = 1000,
+         HullDurability = 30000, SternDurability = 6000, BowDurability = 30000, BridgeDurability = 30000,
+         Points = '[13,18,15]' WHERE SubmarineId = 1;
+         UPDATE freecompany SET Tanks = 12, Kits = 30 WHERE FreeCompanyId = 1;",
+    )
+    .unwrap();
+    let subs = read_sqlite(&db).unwrap();
+
+    let names: Vec<&str> = subs.iter().map(|sub| sub.name.as_str()).collect();
+    assert_eq!(names, ["Sub II", "Sub I", "Boaty"], "soonest return first");
+    let sub = &subs[1];
+    assert_eq!(sub.owner(), "Alpha Beta «ABC»");
+    assert_eq!(sub.return_time.timestamp(), 1_731_600_000);
+    assert_eq!(sub.route, [13, 18, 15]);
+    assert_eq!((sub.rank, sub.parts, sub.exp, sub.next_exp), (78, [9, 2, 7, 16], 100, 1000));
+    assert_eq!(sub.condition, Some([30000, 6000, 30000, 30000]));
+    assert_eq!((sub.tanks, sub.kits), (Some(12), Some(30)));
+    // Nothing recorded reads as nothing rather than failing
+    assert_eq!((subs[0].route.len(), subs[0].rank, subs[0].condition), (0, 0, None));
+}
+
+#[test]
+fn reads_minimal_schema() {
+    let subs = read_sqlite(&fixture(MINIMAL, SUBS)).unwrap();
+    assert_eq!(subs.len(), 3);
+    assert_eq!(subs[2].owner(), "Gamma Delta «XYZ»");
+    assert_eq!(subs[2].return_time.timestamp(), 1_731_603_600);
+    assert_eq!((subs[2].tanks, subs[2].condition), (None, None));
+}
+
+#[test]
+fn notices_new_columns() {
+    let source = SubSource::from_connection(fixture(MINIMAL, SUBS), None);
+    assert!(get_submarine_info(&source).unwrap().iter().all(|sub| sub.rank == 0));
+    let upgrade = "ALTER TABLE submarine ADD COLUMN Rank INTEGER; UPDATE submarine SET Rank = 5";
+    source.with_sqlite(|db| Ok(db.execute_batch(upgrade)?)).unwrap();
+    assert!(get_submarine_info(&source).unwrap().iter().all(|sub| sub.rank == 5));
+}
+
+#[test]
+fn rejects_unknown_schema() {
+    let db = fixture(CURRENT, SUBS);
+    db.execute_batch("ALTER TABLE submarine RENAME COLUMN Name TO Nickname").unwrap();
+    let error = format!("{:#}", read_sqlite(&db).unwrap_err());
+    assert!(error.contains("missing submarine.Name"), "{error}");
+
+    let empty = rusqlite::Connection::open_in_memory().unwrap();
+    assert!(read_sqlite(&empty).is_err());
+}
+
+#[test]
+fn labels_subs_by_source() {
+    let main = SubSource::from_connection(fixture(CURRENT, &SUBS[..1]), None);
+    let alt = SubSource::from_connection(fixture(MINIMAL, &SUBS[1..]), Some("Alt".to_string()));
+    let subs = get_all_submarine_info(&[main, alt]).unwrap();
+
+    let keys: Vec<String> = subs.iter().map(|sub| sub.key()).collect();
+    assert_eq!(keys, ["Alt/2", "1", "Alt/3"]);
+    assert_eq!(subs[0].owner(), "Alpha Beta «ABC» @ Alt");
+}
+
+#[test]
+fn opens_fixture_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = fixture_file(dir.path(), CURRENT, SUBS);
+
+    // Either the plugin folder or the database in it
+    for path in [dir.path(), path.as_path()] {
+        let source = SubSource::open_path(path, None).unwrap();
+        assert_eq!(get_submarine_info(&source).unwrap().len(), 3);
+        source.close().unwrap();
+    }
+
+    let missing = SubSource::open_path(&dir.path().join("nowhere.db"), None).err().unwrap();
+    assert!(missing.is::<db::DbNotFound>());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use chrono::TimeDelta;
+use common::{base, sub_info};
+use sub_returns::diff::{changes, Change};
+
+#[test]
+fn nothing_changed() {
+    let subs = [sub_info(1, "Alpha", -30), sub_info(2, "Alpha", 60)];
+    // Sub 1 coming back in between isn't a change to the database
+    assert!(changes(&subs, &subs, base()).is_empty());
+}
+
+#[test]
+fn dispatch_needs_a_sub_that_was_back() {
+    let now = base();
+    let old = [sub_info(1, "Alpha", -30), sub_info(2, "Alpha", 60)];
+    let new = [sub_info(1, "Alpha", 24 * 60), sub_info(2, "Alpha", 90)];
+    assert_eq!(
+        changes(&old, &new, now),
+        [
+            Change::Dispatched { sub: new[0].clone(), previous: now - TimeDelta::minutes(30) },
+            Change::Retimed { sub: new[1].clone(), previous: now + TimeDelta::hours(1) },
+        ]
+    );
+}
+
+#[test]
+fn new_and_gone() {
+    let old = [sub_info(1, "Alpha", 60), sub_info(2, "Gamma", 60)];
+    let mut renamed = sub_info(1, "Alpha", 60);
+    renamed.name = "Boaty".to_string();
+    let new = [renamed.clone(), sub_info(3, "Delta", 120)];
+    assert_eq!(
+        changes(&old, &new, base()),
+        [
+            Change::NewOwner("Delta «ABC»".to_string()),
+            Change::OwnerGone("Gamma «ABC»".to_string()),
+            Change::Renamed { sub: renamed, from: "Sub 1".to_string() },
+            Change::NewSub(new[1].clone()),
+            Change::Gone(old[1].clone()),
+        ]
+    );
+}
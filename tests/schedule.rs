@@ -0,0 +1,85 @@
+mod common;
+
+use chrono::TimeDelta;
+use common::{base, sub_info};
+use sub_returns::{db::SubInfo, schedule};
+
+const WINDOW: TimeDelta = TimeDelta::minutes(5);
+
+fn ids(groups: &[Vec<&SubInfo>]) -> Vec<Vec<i64>> {
+    groups.iter().map(|group| group.iter().map(|sub| sub.id).collect()).collect()
+}
+
+#[test]
+fn groups_returns_close_together() {
+    let subs = [sub_info(1, "Alpha", 0), sub_info(2, "Alpha", 3), sub_info(3, "Alpha", 7), sub_info(4, "Alpha", 20)];
+    let subs: Vec<&SubInfo> = subs.iter().collect();
+    // Each is within the window of the one before, so the first three chain together
+    assert_eq!(ids(&schedule::group_returns(&subs, WINDOW)), [vec![1, 2, 3], vec![4]]);
+}
+
+#[test]
+fn groups_exactly_at_the_window() {
+    let subs = [sub_info(1, "Alpha", 0), sub_info(2, "Alpha", 5), sub_info(3, "Alpha", 11)];
+    let subs: Vec<&SubInfo> = subs.iter().collect();
+    assert_eq!(ids(&schedule::group_returns(&subs, WINDOW)), [vec![1, 2], vec![3]]);
+}
+
+#[test]
+fn keeps_owners_apart() {
+    let subs = [sub_info(1, "Alpha", 0), sub_info(2, "Gamma", 1), sub_info(3, "Alpha", 2)];
+    let subs: Vec<&SubInfo> = subs.iter().collect();
+    assert_eq!(ids(&schedule::group_returns(&subs, WINDOW)), [vec![1, 3], vec![2]]);
+}
+
+#[test]
+fn far_return_starts_over() {
+    let subs = [sub_info(1, "Alpha", 0), sub_info(2, "Alpha", 3), sub_info(3, "Alpha", 20), sub_info(4, "Alpha", 22)];
+    let subs: Vec<&SubInfo> = subs.iter().collect();
+    // Nothing from the first run carries over into the second
+    assert_eq!(ids(&schedule::group_returns(&subs, WINDOW)), [vec![1, 2], vec![3, 4]]);
+}
+
+#[test]
+fn other_owners_dont_bridge_a_gap() {
+    // Gamma is within the window of both, but Alpha's two are too far apart themselves
+    let subs = [sub_info(1, "Alpha", 0), sub_info(2, "Gamma", 4), sub_info(3, "Alpha", 8)];
+    let subs: Vec<&SubInfo> = subs.iter().collect();
+    assert_eq!(ids(&schedule::group_returns(&subs, WINDOW)), [vec![1], vec![2], vec![3]]);
+}
+
+#[test]
+fn zero_window_only_groups_the_same_time() {
+    let subs = [sub_info(1, "Alpha", 0), sub_info(2, "Alpha", 0), sub_info(3, "Alpha", 1)];
+    let subs: Vec<&SubInfo> = subs.iter().collect();
+    assert_eq!(ids(&schedule::group_returns(&subs, TimeDelta::zero())), [vec![1, 2], vec![3]]);
+}
+
+#[test]
+fn no_returns_no_groups() {
+    assert!(schedule::group_returns(&[], WINDOW).is_empty());
+}
+
+#[test]
+fn group_end_follows_the_chain() {
+    let subs = [
+        sub_info(1, "Alpha", 0),
+        sub_info(2, "Alpha", 4),
+        sub_info(3, "Gamma", 6),
+        sub_info(4, "Alpha", 8),
+        sub_info(5, "Alpha", 30),
+    ];
+    assert_eq!(schedule::group_end(&subs, &subs[0], WINDOW), base() + TimeDelta::minutes(8));
+    assert_eq!(schedule::group_end(&subs, &subs[4], WINDOW), subs[4].return_time);
+}
+
+#[test]
+fn leads_due_once() {
+    let sub = sub_info(1, "Alpha", 60);
+    let leads = [TimeDelta::minutes(30), TimeDelta::minutes(10)];
+    let at = |minutes| base() + TimeDelta::minutes(minutes);
+
+    assert!(schedule::due_leads(&sub, &leads, &[], at(20)).is_empty());
+    assert_eq!(schedule::due_leads(&sub, &leads, &[], at(30)), [TimeDelta::minutes(30)]);
+    assert_eq!(schedule::due_leads(&sub, &leads, &[1800], at(55)), [TimeDelta::minutes(10)]);
+}
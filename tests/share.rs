@@ -0,0 +1,54 @@
+//! `share` against a fixture database
+
+mod common;
+
+use common::{fixture_file, sub_returns, CURRENT, SUBS};
+
+#[test]
+fn prints_discord_timestamps() {
+    let home = tempfile::tempdir().unwrap();
+    fixture_file(home.path(), CURRENT, SUBS);
+    let output = sub_returns(&home).args(["share", "--lang", "en"]).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("## Submarine returns\n"), "{stdout}");
+    assert!(stdout.contains("Sub I: <t:1731600000:f> (<t:1731600000:R>)"), "{stdout}");
+}
+
+#[test]
+fn draws_a_png() {
+    let home = tempfile::tempdir().unwrap();
+    fixture_file(home.path(), CURRENT, SUBS);
+    let path = home.path().join("subs.png");
+    let output = sub_returns(&home).arg("share").arg("--png").arg(&path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+    let png = std::fs::read(&path).unwrap();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[12..16], b"IHDR");
+    let width = u32::from_be_bytes(png[16..20].try_into().unwrap());
+    let height = u32::from_be_bytes(png[20..24].try_into().unwrap());
+    assert!(width > 200 && height > 100, "{width}x{height}");
+}
+
+#[test]
+fn draws_japanese_in_english() {
+    let home = tempfile::tempdir().unwrap();
+    fixture_file(home.path(), CURRENT, SUBS);
+    let path = home.path().join("subs.png");
+    let output = sub_returns(&home).args(["share", "--lang", "ja", "--png"]).arg(&path).output().unwrap();
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    assert!(path.exists());
+}
+
+#[test]
+fn turns_down_names_it_cant_draw() {
+    let home = tempfile::tempdir().unwrap();
+    let db = rusqlite::Connection::open(fixture_file(home.path(), CURRENT, SUBS)).unwrap();
+    db.execute("UPDATE submarine SET Name = 'エクスカリバー' WHERE SubmarineId = 1", []).unwrap();
+    let path = home.path().join("subs.png");
+    let output = sub_returns(&home).arg("share").arg("--png").arg(&path).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("share them as text instead"));
+    assert!(!path.exists());
+}
@@ -0,0 +1,90 @@
+//! `--update` run the way it's used, against a fixture database
+
+mod common;
+
+use std::{path::Path, process::Output};
+
+use chrono::Utc;
+use common::{assert_asks_first, fixture_file, sub_returns, CURRENT, MINIMAL, SUBS};
+use rusqlite::Connection;
+
+/// Listing after the update exits with 3 when some of the subs are back, which 2024's are
+fn assert_ran(output: &Output) {
+    let code = output.status.code();
+    assert!(matches!(code, Some(0 | 3)), "exited with {code:?}: {}", String::from_utf8_lossy(&output.stderr));
+}
+
+fn return_times(db: &Path, column: &str) -> Vec<i64> {
+    let db = Connection::open(db).unwrap();
+    let mut stmt = db.prepare(&format!("SELECT {column} FROM submarine ORDER BY SubmarineId")).unwrap();
+    stmt.query_map([], |row| row.get(0)).unwrap().collect::<Result<_, _>>().unwrap()
+}
+
+#[test]
+fn updates_matching_subs() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), CURRENT, SUBS);
+    let output = sub_returns(&home).args(["--update", "+2h", "--yes", "--fc", "ABC", "--sub", "Sub II"]).output().unwrap();
+    assert_ran(&output);
+
+    let times = return_times(&db, "Return");
+    let expected = (Utc::now() + chrono::TimeDelta::hours(2)).timestamp();
+    assert_eq!([times[0], times[2]], [1_731_600_000, 1_731_603_600], "only Sub II changes");
+    assert!((times[1] - expected).abs() < 60, "{} is not about {expected}", times[1]);
+}
+
+#[test]
+fn updates_minimal_schema_and_undoes() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), MINIMAL, SUBS);
+    let output = sub_returns(&home).args(["--update", "11/14/2024 16:59", "--yes"]).output().unwrap();
+    assert_ran(&output);
+    assert_eq!(return_times(&db, "Return"), [1_731_603_540; 3]);
+
+    let output = sub_returns(&home).arg("--undo-last-update").output().unwrap();
+    assert_ran(&output);
+    assert_eq!(return_times(&db, "Return"), [1_731_600_000, 1_731_596_400, 1_731_603_600]);
+}
+
+#[test]
+fn dry_run_writes_nothing() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), CURRENT, SUBS);
+    let output = sub_returns(&home).args(["--update", "+1h", "--dry-run"]).output().unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("3 submarine return time(s) will change"), "{stdout}");
+    assert!(stdout.contains("Dry run"), "{stdout}");
+    assert_eq!(return_times(&db, "Return"), [1_731_600_000, 1_731_596_400, 1_731_603_600]);
+}
+
+#[test]
+fn needs_a_match() {
+    let home = tempfile::tempdir().unwrap();
+    fixture_file(home.path(), CURRENT, SUBS);
+    let output = sub_returns(&home).args(["--update", "+1h", "--yes", "--sub", "Nobody"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("No submarines matched"));
+}
+
+#[test]
+fn asks_first() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), CURRENT, SUBS);
+    assert_asks_first(&sub_returns(&home).args(["--update", "+1h"]).output().unwrap());
+    assert_eq!(return_times(&db, "Return")[0], 1_731_600_000);
+}
+
+#[test]
+fn dispatch_time_and_duration() {
+    let home = tempfile::tempdir().unwrap();
+    let db = fixture_file(home.path(), CURRENT, SUBS);
+    let args = ["--update", "11/14/2024 10:00", "--duration", "36h30m", "--yes", "--sub", "Boaty"];
+    let output = sub_returns(&home).args(args).output().unwrap();
+    assert_ran(&output);
+    assert_eq!(return_times(&db, "Return")[2], 1_731_578_400 + 36 * 3600 + 1800);
+
+    // Logged as going out then, so the voyage is the right length everywhere else
+    let log = std::fs::read_to_string(home.path().join("data/sub-returns/voyages.jsonl")).unwrap();
+    assert!(log.contains(r#""seen":"2024-11-14T10:00:00Z""#), "{log}");
+}